@@ -0,0 +1,231 @@
+//! Circuit breaker for failing fast when the model server is down
+//!
+//! When a local server crashes or is restarting, every request otherwise waits
+//! for a connection error or the full timeout. A [`CircuitBreaker`] counts
+//! consecutive failures and, once a threshold is reached, "opens": further
+//! requests fail immediately with [`Error::CircuitOpen`] until a cooldown has
+//! passed. It then lets a single probe request through (half-open). A successful
+//! probe closes the circuit; a failed one reopens it for another cooldown.
+//!
+//! # What Counts as a Failure
+//!
+//! - Network errors while sending the request (connection refused, timeouts)
+//! - HTTP 5xx responses
+//!
+//! Client errors (4xx, including 429 rate limits) mean the server is up and
+//! answering, so they count as successes as far as the breaker is concerned.
+//!
+//! # Sharing
+//!
+//! `CircuitBreaker` is cheap to clone and all clones share state. Give the same
+//! breaker to every client pointed at one endpoint so they back off together.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use open_agent::{AgentOptions, CircuitBreaker, Client};
+//! use std::time::Duration;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Open after 3 consecutive failures, probe again after 30 seconds
+//! let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+//!
+//! let options = AgentOptions::builder()
+//!     .model("qwen2.5-32b-instruct")
+//!     .base_url("http://localhost:1234/v1")
+//!     .circuit_breaker(breaker.clone())
+//!     .build()?;
+//!
+//! // Both clients trip and recover together
+//! let planner = Client::new(options.clone())?;
+//! let worker = Client::new(options)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Observable state of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+
+    /// Requests fail fast with [`Error::CircuitOpen`] until the cooldown ends
+    Open,
+
+    /// The cooldown ended and a single probe request is being let through
+    HalfOpen,
+}
+
+/// Internal breaker state, with the timestamps needed for transitions
+#[derive(Debug)]
+enum BreakerState {
+    /// Counting consecutive failures
+    Closed { failures: u32 },
+    /// Tripped at `since`
+    Open { since: Instant },
+    /// Probe request started at `since`
+    HalfOpen { since: Instant },
+}
+
+/// Shared consecutive-failure circuit breaker
+///
+/// Counts consecutive failures (network errors and HTTP 5xx responses; 4xx
+/// means the server is up and counts as a success). At the threshold the
+/// breaker opens and requests fail fast with [`Error::CircuitOpen`] until the
+/// cooldown has passed. A single probe request is then let through: success
+/// closes the circuit, failure reopens it for another cooldown.
+///
+/// Clones share state, so give one breaker to every client pointed at the same
+/// endpoint.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit
+    failure_threshold: u32,
+    /// How long the circuit stays open before probing again
+    cooldown: Duration,
+    /// State shared by all clones
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker
+    ///
+    /// # Arguments
+    ///
+    /// * `failure_threshold` - Consecutive failures before the circuit opens (minimum 1)
+    /// * `cooldown` - How long to fail fast before letting a probe request through
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: 0 })),
+        }
+    }
+
+    /// Returns the current state
+    ///
+    /// An open circuit whose cooldown has elapsed still reports [`CircuitState::Open`]
+    /// until the next request turns it into a probe.
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            BreakerState::Closed { .. } => CircuitState::Closed,
+            BreakerState::Open { .. } => CircuitState::Open,
+            BreakerState::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Manually closes the circuit and clears the failure count
+    pub fn reset(&self) {
+        *self.lock() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Checks whether a request may be sent
+    ///
+    /// Moves an open circuit to half-open once the cooldown has passed and lets
+    /// that one request through as the probe. If a probe never reports back (its
+    /// future was dropped), another probe is allowed after a further cooldown.
+    pub(crate) fn check(&self) -> Result<()> {
+        let mut state = self.lock();
+        let since = match *state {
+            BreakerState::Closed { .. } => return Ok(()),
+            BreakerState::Open { since } | BreakerState::HalfOpen { since } => since,
+        };
+
+        let elapsed = since.elapsed();
+        if elapsed >= self.cooldown {
+            *state = BreakerState::HalfOpen {
+                since: Instant::now(),
+            };
+            Ok(())
+        } else {
+            Err(Error::circuit_open(self.cooldown - elapsed))
+        }
+    }
+
+    /// Records a request that reached a healthy server
+    pub(crate) fn record_success(&self) {
+        *self.lock() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Records a failed request, opening the circuit at the threshold
+    pub(crate) fn record_failure(&self) {
+        let mut state = self.lock();
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            // A failed probe reopens immediately
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => self.failure_threshold,
+        };
+
+        *state = if failures >= self.failure_threshold {
+            BreakerState::Open {
+                since: Instant::now(),
+            }
+        } else {
+            BreakerState::Closed { failures }
+        };
+    }
+
+    /// Locks the shared state, recovering from a poisoned lock
+    ///
+    /// State updates can't panic halfway, so the data is always consistent.
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        assert!(breaker.check().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(breaker.check(), Err(Error::CircuitOpen { .. })));
+
+        // Clones share state
+        assert_eq!(breaker.clone().state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.check().is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        // First request after the cooldown is the probe; others still fail fast
+        assert!(breaker.check().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.check().is_err());
+
+        // Failed probe reopens
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -89,8 +89,9 @@
 //! The client maintains several pieces of state:
 //!
 //! - **history**: Full conversation history (`Vec<Message>`)
-//! - **current_stream**: Active SSE stream being consumed (`Option<ContentStream>`)
+//! - **current_stream**: Active SSE stream being consumed (`Option<EventStream>`)
 //! - **interrupted**: Atomic flag for cancellation (`Arc<AtomicBool>`)
+//! - **interrupt_signal**: Optional future that interrupts when it completes
 //! - **auto_exec_buffer**: Buffered blocks for auto-execution mode (`Vec<ContentBlock>`)
 //! - **auto_exec_index**: Current position in buffer (usize)
 //!
@@ -118,6 +119,10 @@
 //! # }
 //! ```
 //!
+//! For structured concurrency, [`Client::interrupt_on`] accepts any future
+//! (e.g. a `CancellationToken`'s `cancelled_owned()`) and races it against the
+//! stream, so cancellation takes effect even while waiting for the next chunk.
+//!
 //! ## Hook Integration
 //!
 //! Hooks provide extension points throughout the request lifecycle:
@@ -312,16 +317,28 @@
 //! # }
 //! ```
 
+use crate::circuit_breaker::CircuitState;
+use crate::context::{
+    TokenizerHint, estimate_messages_with_hint, normalize_roles, truncate_messages,
+};
+use crate::hooks::{HistoryChangedEvent, Hooks, PostReceiveEvent};
+use crate::tools::ToolOutput;
 use crate::types::{
-    AgentOptions, ContentBlock, Message, MessageRole, OpenAIContent, OpenAIContentPart,
-    OpenAIFunction, OpenAIMessage, OpenAIRequest, OpenAIToolCall, TextBlock,
+    AgentOptions, CompletedResponse, ContentBlock, FinishReason, ImageBlock,
+    IterationLimitBehavior, Message, MessageRole, OpenAIChunk, OpenAIContent, OpenAIContentPart,
+    OpenAIFunction, OpenAIMessage, OpenAIRequest, OpenAIToolCall, PriceTable, ResponseMeta,
+    StreamEvent, TextBlock, ToolErrorPolicy, ToolResultBlock, ToolUseBlock, Usage,
 };
-use crate::utils::{ToolCallAggregator, parse_sse_stream};
+use crate::utils::{SseChunk, ToolCallAggregator, parse_sse_events, parse_sse_stream};
 use crate::{Error, Result};
 use futures::stream::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 /// A pinned, boxed stream of content blocks from the model.
@@ -338,7 +355,7 @@ use std::time::Duration;
 ///
 /// The stream can yield several types of content blocks:
 ///
-/// - **TextBlock**: Incremental text responses from the model
+/// - **TextBlock**: The response text, as one block once the response completes
 /// - **ToolUseBlock**: Requests to execute a tool with specific parameters
 /// - **ToolResultBlock**: Results from tool execution (in manual mode)
 ///
@@ -379,6 +396,141 @@ use std::time::Duration;
 /// ```
 pub type ContentStream = Pin<Box<dyn Stream<Item = Result<ContentBlock>> + Send>>;
 
+/// Filters a content stream down to the text of its text blocks.
+///
+/// Tool calls, images, and other non-text blocks are skipped; errors pass
+/// through. Saves repeating the `if let ContentBlock::Text` match when only the
+/// text matters. See [`Client::receive_text()`] for the client equivalent.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{AgentOptions, query, text_only};
+/// use futures::StreamExt;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .build()?;
+///
+/// let mut text = text_only(query("Tell me a joke", &options).await?);
+/// while let Some(chunk) = text.next().await {
+///     print!("{}", chunk?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn text_only(stream: ContentStream) -> impl Stream<Item = Result<String>> + Send + Unpin {
+    stream.filter_map(|block| {
+        futures::future::ready(match block {
+            Ok(ContentBlock::Text(text)) => Some(Ok(text.text)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    })
+}
+
+/// An item of a fanned-out stream: a block, or a shared error.
+///
+/// [`Error`] isn't `Clone`, so each consumer of [`fan_out`] gets the same error
+/// behind an `Arc`.
+pub type SharedBlockResult = std::result::Result<ContentBlock, Arc<Error>>;
+
+/// Tees a content stream to several consumers through a broadcast channel.
+///
+/// A [`ContentStream`] can only be consumed once. `fan_out` spawns a task that
+/// drives the stream and broadcasts every item to `consumers` receivers, cloning
+/// each block, so a UI and an audit log can read the same generation. Each
+/// receiver sees every item in order and then `RecvError::Closed` once the stream
+/// has ended.
+///
+/// The stream is driven as fast as it produces, not at the pace of the slowest
+/// consumer. Each receiver buffers up to `capacity` items; a consumer that falls
+/// further behind loses the oldest ones and gets `RecvError::Lagged(n)` with the
+/// number skipped, then continues from the oldest item still buffered. Pick a
+/// capacity that covers the longest stall you expect, or treat `Lagged` as a gap
+/// in the log. Once every receiver is dropped the task stops and drops the
+/// stream, closing its connection.
+///
+/// Must be called from within a Tokio runtime.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidInput` if `consumers` or `capacity` is zero.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{AgentOptions, ContentBlock, fan_out, query};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .build()?;
+///
+/// let mut receivers = fan_out(query("Summarize the incident", &options).await?, 2, 256)?;
+/// let mut audit = receivers.pop().unwrap();
+/// let mut ui = receivers.pop().unwrap();
+///
+/// tokio::spawn(async move {
+///     while let Ok(item) = audit.recv().await {
+///         println!("[audit] {:?}", item);
+///     }
+/// });
+/// while let Ok(item) = ui.recv().await {
+///     if let ContentBlock::Text(text) = item? {
+///         print!("{}", text.text);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn fan_out(
+    stream: ContentStream,
+    consumers: usize,
+    capacity: usize,
+) -> Result<Vec<tokio::sync::broadcast::Receiver<SharedBlockResult>>> {
+    if consumers == 0 {
+        return Err(Error::invalid_input("fan_out needs at least one consumer"));
+    }
+    if capacity == 0 {
+        return Err(Error::invalid_input("fan_out capacity must be at least 1"));
+    }
+
+    // Subscribe everyone before the first item is sent, so no one misses it
+    let (sender, first) = tokio::sync::broadcast::channel(capacity);
+    let mut receivers = vec![first];
+    receivers.extend((1..consumers).map(|_| sender.subscribe()));
+
+    tokio::spawn(async move {
+        let mut stream = stream;
+        loop {
+            // Stop waiting on the stream as soon as no one is listening
+            let item = tokio::select! {
+                item = stream.next() => item,
+                _ = sender.closed() => break,
+            };
+            let Some(item) = item else {
+                break;
+            };
+            // An error means every receiver is gone
+            if sender.send(item.map_err(Arc::new)).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(receivers)
+}
+
+/// A pinned, boxed stream of [`StreamEvent`]s from the model.
+///
+/// Like [`ContentStream`], but also carries incremental events such as tool call
+/// argument fragments. Complete blocks arrive as [`StreamEvent::Block`].
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>;
+
 /// Simple query function for single-turn interactions without conversation history.
 ///
 /// This is a stateless convenience function for simple queries that don't require
@@ -402,7 +554,7 @@ pub type ContentStream = Pin<Box<dyn Stream<Item = Result<ContentBlock>> + Send>
 /// 1. Creates a temporary HTTP client with configured timeout
 /// 2. Builds message array (system prompt + user prompt)
 /// 3. Converts tools to OpenAI format if provided
-/// 4. Makes HTTP POST request to `/chat/completions`
+/// 4. Makes HTTP POST request to the completions path (`/chat/completions` by default)
 /// 5. Parses Server-Sent Events (SSE) response stream
 /// 6. Aggregates chunks into complete content blocks
 /// 7. Returns stream that yields blocks as they complete
@@ -525,11 +677,333 @@ pub type ContentStream = Pin<Box<dyn Stream<Item = Result<ContentBlock>> + Send>
 pub async fn query(prompt: &str, options: &AgentOptions) -> Result<ContentStream> {
     // Create HTTP client with configured timeout
     // The timeout applies to the entire request, not individual chunks
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(options.timeout()))
-        .build()
-        .map_err(Error::Http)?;
+    let client = http_client_builder(options).build().map_err(Error::Http)?;
+
+    query_with_client(&client, prompt, options).await
+}
+
+/// Single-turn query using a caller-supplied HTTP client.
+///
+/// Identical to [`query()`], except that the request is sent through `client`
+/// instead of a freshly built one. Use this when the SDK's default client can't
+/// express your network setup: mTLS client certificates, a corporate proxy, a
+/// custom CA bundle, and so on.
+///
+/// # Timeouts
+///
+/// `AgentOptions::timeout` is **not** applied to the supplied client. You are
+/// responsible for configuring timeouts on the `reqwest::Client` yourself.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{query_with_client, AgentOptions};
+/// use futures::StreamExt;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let http = reqwest::Client::builder()
+///     .proxy(reqwest::Proxy::all("http://proxy.corp.example:3128")?)
+///     .timeout(Duration::from_secs(120))
+///     .build()?;
+///
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("https://gateway.corp.example/v1")
+///     .build()?;
+///
+/// let mut stream = query_with_client(&http, "Hello!", &options).await?;
+/// while let Some(block) = stream.next().await {
+///     println!("{:?}", block?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn query_with_client(
+    client: &reqwest::Client,
+    prompt: &str,
+    options: &AgentOptions,
+) -> Result<ContentStream> {
+    let request = chat_request(options, query_messages(prompt, options));
+    let (stream, _) = send_query(client, options, request).await?;
+    Ok(stream)
+}
+
+/// Single-turn query that also returns a handle to the response's details.
+///
+/// Like [`query()`], but alongside the stream comes a [`QueryHandle`] exposing
+/// the completion id, finish reason, and token usage. They are filled in as the
+/// stream is consumed: the id with the first block, the finish reason and
+/// usage once the stream has ended. The request asks the server to report
+/// usage (`stream_options.include_usage`), as [`query_complete()`] does.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{query_with_handle, AgentOptions};
+/// use futures::StreamExt;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .build()?;
+///
+/// let (mut stream, handle) = query_with_handle("Hello!", &options).await?;
+/// while let Some(block) = stream.next().await {
+///     println!("{:?}", block?);
+/// }
+///
+/// println!(
+///     "completion {} finished with {:?}, usage {:?}",
+///     handle.id().unwrap_or_default(),
+///     handle.finish_reason(),
+///     handle.usage()
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub async fn query_with_handle(
+    prompt: &str,
+    options: &AgentOptions,
+) -> Result<(ContentStream, QueryHandle)> {
+    let client = http_client_builder(options).build().map_err(Error::Http)?;
+
+    let mut request = chat_request(options, query_messages(prompt, options));
+    request_usage(&mut request);
+    let (stream, info) = send_query(&client, options, request).await?;
+    Ok((stream, QueryHandle { info }))
+}
+
+/// Details of a response streamed by [`query_with_handle()`].
+///
+/// Shares state with the stream, so values appear as it is consumed. Cheap to
+/// clone; clones see the same values.
+#[derive(Debug, Clone)]
+pub struct QueryHandle {
+    info: ResponseSlot,
+}
+
+impl QueryHandle {
+    /// Returns the completion id, once the first chunk has arrived.
+    pub fn id(&self) -> Option<String> {
+        self.meta().map(|meta| meta.id().to_string())
+    }
+
+    /// Returns the id, model, and creation time of the response, once the first chunk has arrived.
+    pub fn meta(&self) -> Option<ResponseMeta> {
+        lock_info(&self.info).meta.clone()
+    }
+
+    /// Returns why the response ended, once it has.
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        lock_info(&self.info).finish_reason.clone()
+    }
+
+    /// Returns the token usage, if the server reported it.
+    pub fn usage(&self) -> Option<Usage> {
+        lock_info(&self.info).usage
+    }
+}
+
+/// Single-turn query with a prepared list of messages.
+///
+/// Like [`query()`], but the caller supplies the whole conversation, e.g. a
+/// few-shot prompt of example questions and answers followed by the real
+/// question. The messages are converted exactly as [`Client`] converts its
+/// history, after the system prompt from `options`. Nothing is stored: each
+/// call is independent.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidInput`] if `messages` is empty, if a tool use block
+/// appears outside an assistant message, or if a tool result doesn't answer a
+/// tool call in the preceding assistant message (the same checks as
+/// [`Client::append_message`]). Otherwise, the same errors as [`query()`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{query_with_messages, AgentOptions, ContentBlock, Message, TextBlock};
+/// use futures::StreamExt;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-7b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .system_prompt("Classify the sentiment as positive or negative.")
+///     .build()?;
+///
+/// let answer = |text: &str| Message::assistant(vec![ContentBlock::Text(TextBlock::new(text))]);
+/// let messages = vec![
+///     Message::user("I love this!"),
+///     answer("positive"),
+///     Message::user("Worst purchase ever."),
+///     answer("negative"),
+///     Message::user("The battery lasts all day."),
+/// ];
+///
+/// let mut stream = query_with_messages(messages, &options).await?;
+/// while let Some(block) = stream.next().await {
+///     if let ContentBlock::Text(text) = block? {
+///         println!("{}", text.text);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn query_with_messages(
+    messages: Vec<Message>,
+    options: &AgentOptions,
+) -> Result<ContentStream> {
+    if messages.is_empty() {
+        return Err(Error::invalid_input("messages cannot be empty"));
+    }
+    for (i, message) in messages.iter().enumerate() {
+        validate_appended_message(&messages[..i], message)?;
+    }
+
+    let client = http_client_builder(options).build().map_err(Error::Http)?;
+
+    let request = chat_request(options, history_messages(options, &messages)?);
+    let (stream, _) = send_query(&client, options, request).await?;
+    Ok(stream)
+}
+
+/// Sends a stateless query and streams the complete blocks of the response.
+///
+/// Shared by `query_with_client()`, `query_with_handle()` and
+/// `query_with_messages()`. Response details are recorded into the returned slot.
+async fn send_query(
+    client: &reqwest::Client,
+    options: &AgentOptions,
+    request: OpenAIRequest,
+) -> Result<(ContentStream, ResponseSlot)> {
+    // Dry run: hand back the request itself instead of sending it
+    if options.dry_run() {
+        let json = serde_json::to_string_pretty(&request)?;
+        let stream: ContentStream = Box::pin(futures::stream::once(async move {
+            Ok(ContentBlock::Text(TextBlock::new(json)))
+        }));
+        return Ok((stream, ResponseSlot::default()));
+    }
+
+    let (blocks, slot) = stream_query_blocks(client, options, &request).await?;
+    Ok((buffer_stream(blocks, options.stream_buffer_size()), slot))
+}
+
+/// Posts a stateless query and streams the complete blocks of the response, unbuffered.
+///
+/// Shared by `send_query()` and `query_complete()`, which handle dry-run mode
+/// themselves. Response details are recorded into the returned slot.
+async fn stream_query_blocks(
+    client: &reqwest::Client,
+    options: &AgentOptions,
+    request: &OpenAIRequest,
+) -> Result<(ContentStream, ResponseSlot)> {
+    let slot = ResponseSlot::default();
+
+    // POST to the chat completions endpoint; non-success statuses become errors
+    let idempotency_key = request_idempotency_key(options);
+    let response =
+        post_chat_completion(client, options, request, idempotency_key.as_deref(), None).await?;
+
+    // Drop the incremental events; query() only yields complete blocks
+    let chunks = record_response_info(
+        chunk_stream(client, options, request, idempotency_key, response),
+        Arc::clone(&slot),
+    );
+    Ok((blocks_only(event_stream(chunks)), slot))
+}
+
+/// Asks the server to report token usage at the end of the stream.
+///
+/// Leaves `stream_options` alone if `extra_body` already sets it.
+fn request_usage(request: &mut OpenAIRequest) {
+    request
+        .extra_body
+        .entry("stream_options")
+        .or_insert_with(|| serde_json::json!({"include_usage": true}));
+}
+
+/// Runs a single-turn query and waits for the whole response.
+///
+/// For callers who assemble the full answer before using it anyway, such as
+/// request handlers. The response is still streamed from the server, then
+/// collected into a [`CompletedResponse`] holding the text, any tool calls, the
+/// finish reason, and token usage. Tool calls are returned, not executed.
+///
+/// The request asks the server to report usage (`stream_options.include_usage`);
+/// `usage` is `None` if it doesn't. Set `stream_options` in
+/// [`extra_body`](crate::AgentOptionsBuilder::extra_body) to override this.
+///
+/// In [dry-run mode](crate::AgentOptionsBuilder::dry_run), `text` holds the
+/// request JSON and nothing is sent.
+///
+/// # Errors
+///
+/// Returns the same errors as [`query()`], including any error that ends the
+/// stream partway through.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{AgentOptions, FinishReason, query_complete};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .build()?;
+///
+/// let response = query_complete("Name three rivers", &options).await?;
+/// println!("{}", response.text);
+/// if response.finish_reason == Some(FinishReason::Length) {
+///     eprintln!("(truncated)");
+/// }
+/// if let Some(usage) = response.usage {
+///     eprintln!("{} tokens", usage.total_tokens);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn query_complete(prompt: &str, options: &AgentOptions) -> Result<CompletedResponse> {
+    let client = http_client_builder(options).build().map_err(Error::Http)?;
+
+    let mut request = chat_request(options, query_messages(prompt, options));
+    request_usage(&mut request);
+
+    if options.dry_run() {
+        return Ok(CompletedResponse {
+            text: serde_json::to_string_pretty(&request)?,
+            tool_uses: Vec::new(),
+            finish_reason: None,
+            usage: None,
+        });
+    }
+
+    let (mut blocks, slot) = stream_query_blocks(&client, options, &request).await?;
+    let mut text = String::new();
+    let mut tool_uses = Vec::new();
+    while let Some(block) = blocks.next().await {
+        match block? {
+            ContentBlock::Text(block) => text.push_str(&block.text),
+            ContentBlock::ToolUse(tool_use) => tool_uses.push(tool_use),
+            _ => {}
+        }
+    }
 
+    let info = std::mem::take(&mut *lock_info(&slot));
+    Ok(CompletedResponse {
+        text,
+        tool_uses,
+        finish_reason: info.finish_reason,
+        usage: info.usage,
+    })
+}
+
+/// Builds the messages for a single-turn query: the system prompt, if any, and the prompt.
+fn query_messages(prompt: &str, options: &AgentOptions) -> Vec<OpenAIMessage> {
     // Build messages array for the API request
     // OpenAI format expects an array of message objects with role and content
     let mut messages = Vec::new();
@@ -554,91 +1028,906 @@ pub async fn query(prompt: &str, options: &AgentOptions) -> Result<ContentStream
         tool_call_id: None,
     });
 
-    // Convert tools to OpenAI format if any are provided
-    // Tools are described using JSON Schema for parameter validation
-    let tools = if !options.tools().is_empty() {
-        Some(
-            options
-                .tools()
-                .iter()
-                .map(|t| t.to_openai_format())
-                .collect(),
-        )
+    messages
+}
+
+/// Converts a conversation into API messages, led by the configured system prompt.
+///
+/// Shared by `Client::build_request()`, `Client::export_openai_messages()` and
+/// `query_with_messages()`.
+fn history_messages(
+    options: &AgentOptions,
+    conversation: &[Message],
+) -> Result<Vec<OpenAIMessage>> {
+    // Build messages array for API request
+    // This includes system prompt + full conversation history
+    let mut messages = Vec::new();
+
+    // Add system prompt as first message if configured
+    // System prompts are added fresh for each request (not from history)
+    if !options.system_prompt().is_empty() {
+        messages.push(OpenAIMessage {
+            role: "system".to_string(),
+            content: Some(OpenAIContent::Text(options.system_prompt().to_string())),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+
+    // Optionally merge same-role runs for strict chat templates
+    let history = if options.normalize_roles() {
+        Cow::Owned(normalize_roles(conversation))
     } else {
-        None
+        Cow::Borrowed(conversation)
     };
 
-    // Build the OpenAI-compatible request payload
-    // stream=true enables Server-Sent Events for incremental responses
-    let request = OpenAIRequest {
-        model: options.model().to_string(),
-        messages,
-        stream: true, // Critical: enables SSE streaming
-        max_tokens: options.max_tokens(),
-        temperature: Some(options.temperature()),
-        tools,
-    };
+    // Convert conversation history to OpenAI message format
+    // This includes user prompts, assistant responses, and tool results
+    for msg in history.iter() {
+        // Separate blocks by type to determine message structure
+        let mut text_blocks = Vec::new();
+        let mut image_blocks = Vec::new();
+        let mut tool_use_blocks = Vec::new();
+        let mut tool_result_blocks = Vec::new();
 
-    // Make HTTP POST request to the chat completions endpoint
-    let url = format!("{}/chat/completions", options.base_url());
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", options.api_key()))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(Error::Http)?;
+        for block in &msg.content {
+            match block {
+                ContentBlock::Text(text) => text_blocks.push(text),
+                ContentBlock::Image(image) => image_blocks.push(image),
+                ContentBlock::ToolUse(tool_use) => tool_use_blocks.push(tool_use),
+                ContentBlock::ToolResult(tool_result) => tool_result_blocks.push(tool_result),
+            }
+        }
 
-    // Check for HTTP-level errors before processing the stream
-    // This catches authentication failures, rate limits, invalid models, etc.
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_else(|e| {
-            eprintln!("WARNING: Failed to read error response body: {}", e);
-            "Unknown error (failed to read response body)".to_string()
-        });
-        return Err(Error::api(format!("API error {}: {}", status, body)));
-    }
+        // Handle different message types based on content blocks
+        // Case 1: Message contains tool results (should be separate tool messages)
+        if !tool_result_blocks.is_empty() {
+            // Tool messages only carry text; images follow in one user message
+            let mut image_parts = Vec::new();
 
-    // Parse the Server-Sent Events (SSE) stream
-    // The response body is a stream of "data: {...}" events
-    let sse_stream = parse_sse_stream(response);
+            for tool_result in tool_result_blocks {
+                // Plain-text results are sent verbatim, JSON results serialized
+                let content = tool_result.content_for_api();
 
-    // Aggregate SSE chunks into complete content blocks
-    // ToolCallAggregator handles partial JSON and assembles complete tool calls
-    // The scan() combinator maintains state across stream items
-    let stream = sse_stream.scan(ToolCallAggregator::new(), |aggregator, chunk_result| {
-        let result = match chunk_result {
-            Ok(chunk) => match aggregator.process_chunk(chunk) {
-                Ok(blocks) => {
-                    if blocks.is_empty() {
-                        Some(None) // Intermediate chunk, continue streaming
-                    } else {
-                        Some(Some(Ok(blocks))) // Complete block(s) ready
-                    }
+                messages.push(OpenAIMessage {
+                    role: "tool".to_string(),
+                    content: Some(OpenAIContent::Text(content)),
+                    tool_calls: None,
+                    tool_call_id: Some(tool_result.tool_use_id().to_string()),
+                });
+
+                if !tool_result.images().is_empty() {
+                    image_parts.push(OpenAIContentPart::text(format!(
+                        "Image output of tool call {}:",
+                        tool_result.tool_use_id()
+                    )));
+                    image_parts.extend(
+                        tool_result
+                            .images()
+                            .iter()
+                            .map(OpenAIContentPart::from_image),
+                    );
                 }
-                Err(e) => Some(Some(Err(e))), // Propagate processing error
-            },
-            Err(e) => Some(Some(Err(e))), // Propagate stream error
-        };
-        futures::future::ready(result)
-    });
+            }
 
-    // Flatten the stream to emit individual blocks
-    // filter_map removes None values (incomplete chunks)
-    // flat_map expands Vec<ContentBlock> into individual items
-    let flattened = stream
-        .filter_map(|item| async move { item })
-        .flat_map(|result| {
-            futures::stream::iter(match result {
-                Ok(blocks) => blocks.into_iter().map(Ok).collect(),
-                Err(e) => vec![Err(e)],
-            })
-        });
+            if !image_parts.is_empty() {
+                messages.push(OpenAIMessage {
+                    role: "user".to_string(),
+                    content: Some(OpenAIContent::Parts(image_parts)),
+                    tool_calls: None,
+                    tool_call_id: None,
+                });
+            }
+        }
+        // Case 2: Message contains tool use blocks (assistant with tool calls)
+        else if !tool_use_blocks.is_empty() {
+            // Build tool_calls array
+            let tool_calls: Vec<OpenAIToolCall> = tool_use_blocks
+                .iter()
+                .map(|tool_use| {
+                    // Serialize the input as a JSON string (OpenAI API requirement)
+                    let arguments = serde_json::to_string(tool_use.input())
+                        .unwrap_or_else(|_| "{}".to_string());
+
+                    OpenAIToolCall {
+                        id: tool_use.id().to_string(),
+                        call_type: "function".to_string(),
+                        function: OpenAIFunction {
+                            name: tool_use.name().to_string(),
+                            arguments,
+                        },
+                    }
+                })
+                .collect();
 
-    // Pin and box the stream for type erasure and safe async usage
-    Ok(Box::pin(flattened))
+            // Extract any text content (some models include reasoning before tool calls)
+            // Note: OpenAI API requires content field even if empty when tool_calls present
+            let content = if !text_blocks.is_empty() {
+                let text = text_blocks
+                    .iter()
+                    .map(|t| t.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Some(OpenAIContent::Text(text))
+            } else {
+                // Empty string satisfies OpenAI API schema (content is required)
+                Some(OpenAIContent::Text(String::new()))
+            };
+
+            messages.push(OpenAIMessage {
+                role: "assistant".to_string(),
+                content,
+                tool_calls: Some(tool_calls),
+                tool_call_id: None,
+            });
+        }
+        // Case 3: Message contains images (use OpenAIContent::Parts)
+        else if !image_blocks.is_empty() {
+            // Log debug info about images being serialized
+            log::debug!(
+                "Serializing message with {} image(s) for {:?} role",
+                image_blocks.len(),
+                msg.role
+            );
+
+            // Build content parts array preserving original order
+            let mut content_parts = Vec::new();
+
+            // Re-iterate through content blocks to maintain order
+            for block in &msg.content {
+                match block {
+                    ContentBlock::Text(text) => {
+                        content_parts.push(OpenAIContentPart::text(&text.text));
+                    }
+                    ContentBlock::Image(image) => {
+                        // Log image details (truncate URL for privacy)
+                        // Cut at a char boundary: URLs may hold multi-byte characters
+                        let url = image.url();
+                        let url_display = match url.char_indices().nth(100) {
+                            Some((end, _)) => {
+                                format!("{}... ({} chars)", &url[..end], url.chars().count())
+                            }
+                            None => url.to_string(),
+                        };
+                        let detail_str = match image.detail() {
+                            crate::types::ImageDetail::Low => "low",
+                            crate::types::ImageDetail::High => "high",
+                            crate::types::ImageDetail::Auto => "auto",
+                        };
+                        log::debug!("  - Image: {} (detail: {})", url_display, detail_str);
+
+                        content_parts.push(OpenAIContentPart::from_image(image));
+                    }
+                    ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => {}
+                }
+            }
+
+            // Defensive check: content_parts should never be empty at this point
+            // If it is, it indicates a logic error (e.g., all blocks were filtered out)
+            if content_parts.is_empty() {
+                return Err(Error::other(
+                    "Internal error: Message with images produced empty content array",
+                ));
+            }
+
+            let role_str = match msg.role {
+                MessageRole::System => "system",
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+                MessageRole::Tool => "tool",
+            };
+
+            messages.push(OpenAIMessage {
+                role: role_str.to_string(),
+                content: Some(OpenAIContent::Parts(content_parts)),
+                tool_calls: None,
+                tool_call_id: None,
+            });
+        }
+        // Case 4: Message contains only text (normal message, backward compatible)
+        else {
+            let content = text_blocks
+                .iter()
+                .map(|t| t.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let role_str = match msg.role {
+                MessageRole::System => "system",
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+                MessageRole::Tool => "tool",
+            };
+
+            messages.push(OpenAIMessage {
+                role: role_str.to_string(),
+                content: Some(OpenAIContent::Text(content)),
+                tool_calls: None,
+                tool_call_id: None,
+            });
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Builds the chat completions payload for a prepared message list.
+///
+/// Shared by `query_with_client()` and `Client::build_request()` so both entry
+/// points send the same sampling parameters and tool definitions.
+fn chat_request(options: &AgentOptions, messages: Vec<OpenAIMessage>) -> OpenAIRequest {
+    chat_request_with_tools(options, messages, |tool| tool.is_enabled())
+}
+
+/// Builds the chat completions payload, offering the tools `enabled` accepts.
+///
+/// `Client` passes its own per-conversation view of which tools are enabled.
+fn chat_request_with_tools(
+    options: &AgentOptions,
+    messages: Vec<OpenAIMessage>,
+    enabled: impl Fn(&crate::tools::Tool) -> bool,
+) -> OpenAIRequest {
+    // Convert enabled tools to OpenAI format if any are provided
+    // Tools are described using JSON Schema for parameter validation
+    let tools: Vec<_> = options
+        .tools()
+        .iter()
+        .filter(|t| enabled(t))
+        .map(|t| t.to_openai_format())
+        .collect();
+    let tools = if tools.is_empty() { None } else { Some(tools) };
+    // Servers reject parallel_tool_calls on a request without tools
+    let parallel_tool_calls = tools.as_ref().and(options.allow_parallel_tool_calls());
+
+    // Build the OpenAI-compatible request payload
+    // stream=true enables Server-Sent Events for incremental responses
+    OpenAIRequest {
+        model: options.model().to_string(),
+        messages,
+        stream: true, // Critical: enables SSE streaming
+        max_tokens: options.max_tokens(),
+        temperature: Some(options.temperature()),
+        top_p: options.top_p(),
+        min_p: options.min_p(),
+        repetition_penalty: options.repetition_penalty(),
+        seed: options.seed(),
+        service_tier: options.service_tier().map(str::to_string),
+        reasoning_effort: options.reasoning_effort(),
+        tools,
+        parallel_tool_calls,
+        tool_choice: None,
+        extra_body: options.extra_body().clone(),
+    }
+}
+
+/// Keeps only the named tools in a request, for `Client::send_with_tools()`.
+fn restrict_tools(request: &mut OpenAIRequest, allowed: &[String]) {
+    if let Some(tools) = &mut request.tools {
+        tools.retain(|tool| {
+            tool["function"]["name"]
+                .as_str()
+                .is_some_and(|name| allowed.iter().any(|a| a == name))
+        });
+        if tools.is_empty() {
+            request.tools = None;
+            // Servers reject parallel_tool_calls on a request without tools
+            request.parallel_tool_calls = None;
+        }
+    }
+}
+
+/// Prompt sent by `Client::continue_if_truncated()` after a response cut off by length.
+const CONTINUATION_PROMPT: &str =
+    "Continue exactly where you left off, without repeating anything.";
+
+/// First backoff delay between attempts under `ToolErrorPolicy::Retry`.
+const TOOL_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(200);
+
+/// Longest `Client::interrupt_and_drain()` waits for the rest of a response.
+const INTERRUPT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Note appended when `IterationLimitBehavior::InjectNotice` stops tool execution.
+const ITERATION_LIMIT_NOTICE: &str = "[Notice] The tool call limit for this task has been reached. \
+Do not call any more tools. Using the information gathered so far, give your final answer now.";
+
+/// Starts an HTTP client configured from the options' timeout, TCP keepalive
+/// and User-Agent.
+///
+/// Shared by `Client::new()`, the `query` functions, [`embed`](crate::embed),
+/// [`generate_image`](crate::generate_image) and
+/// [`list_models`](crate::list_models).
+pub(crate) fn http_client_builder(options: &AgentOptions) -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(options.timeout()))
+        .tcp_keepalive(options.tcp_keepalive())
+        .user_agent(options.user_agent())
+}
+
+/// POSTs a chat completions request and checks the response status.
+///
+/// Shared by `query_with_client()` and `Client::send_request()`. Honours the
+/// configured circuit breaker: fails fast while it is open, and records network
+/// errors and 5xx responses as failures. HTTP-level errors (authentication, rate
+/// limits, invalid models, ...) are converted with [`error_from_response`].
+///
+/// `idempotency_key` is sent as the `Idempotency-Key` header, `last_event_id` as
+/// the `Last-Event-ID` header when resuming a stream, and the configured trace id
+/// in its own header.
+async fn post_chat_completion(
+    client: &reqwest::Client,
+    options: &AgentOptions,
+    request: &OpenAIRequest,
+    idempotency_key: Option<&str>,
+    last_event_id: Option<&str>,
+) -> Result<reqwest::Response> {
+    let breaker = options.circuit_breaker();
+    if let Some(breaker) = breaker {
+        breaker.check()?;
+    }
+
+    let url = format!("{}{}", options.base_url(), options.completions_path());
+    let mut builder = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", options.api_key()))
+        .header("Content-Type", "application/json");
+    if let Some(key) = idempotency_key {
+        builder = builder.header("Idempotency-Key", key);
+    }
+    if let Some(id) = last_event_id {
+        builder = builder.header("Last-Event-ID", id);
+    }
+    if let Some(trace_id) = options.trace_id() {
+        builder = builder.header(options.trace_id_header(), trace_id);
+    }
+    let result = builder.json(request).send().await;
+
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            if let Some(breaker) = breaker {
+                breaker.record_failure();
+            }
+            return Err(Error::Http(e));
+        }
+    };
+
+    if let Some(breaker) = breaker {
+        // 4xx means the server is up and answering; only 5xx counts against it
+        if response.status().is_server_error() {
+            breaker.record_failure();
+        } else {
+            breaker.record_success();
+        }
+    }
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    Ok(response)
+}
+
+/// Returns the `Idempotency-Key` for a new request, if it should carry one.
+///
+/// A key pinned with [`with_idempotency_key`](crate::retry::with_idempotency_key)
+/// wins. Otherwise, when the `idempotency` option is on, the key of a surrounding
+/// retry helper is used, or a fresh one is generated.
+fn request_idempotency_key(options: &AgentOptions) -> Option<String> {
+    crate::retry::pinned_idempotency_key().or_else(|| {
+        options
+            .idempotency()
+            .then(|| crate::retry::shared_idempotency_key().unwrap_or_else(new_idempotency_key))
+    })
+}
+
+/// Generates a random `Idempotency-Key` value, formatted as a UUID v4.
+pub(crate) fn new_idempotency_key() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// A pinned, boxed stream of parsed SSE chunks.
+type ChunkStream = Pin<Box<dyn Stream<Item = Result<OpenAIChunk>> + Send>>;
+
+/// Parses a chat completions response body into chunks.
+///
+/// With [`resume_streams`](crate::AgentOptionsBuilder::resume_streams) enabled,
+/// the stream reconnects after a mid-stream network error (see
+/// [`resumable_chunk_stream`]); otherwise the error ends it. A reconnect re-sends
+/// `idempotency_key`, the key of the original request.
+fn chunk_stream(
+    client: &reqwest::Client,
+    options: &AgentOptions,
+    request: &OpenAIRequest,
+    idempotency_key: Option<String>,
+    response: reqwest::Response,
+) -> ChunkStream {
+    if options.resume_streams() {
+        resumable_chunk_stream(
+            client.clone(),
+            options.clone(),
+            request.clone(),
+            idempotency_key,
+            response,
+        )
+    } else {
+        parse_sse_stream(response, options.max_sse_line_bytes())
+    }
+}
+
+/// Reconnection attempts allowed per response when resuming streams.
+const MAX_STREAM_RESUMES: u32 = 3;
+
+/// State of a stream that can reconnect with `Last-Event-ID`.
+struct ResumableStream {
+    /// Chunks from the current connection
+    events: Pin<Box<dyn Stream<Item = Result<SseChunk>> + Send>>,
+    client: reqwest::Client,
+    options: AgentOptions,
+    /// The original request, re-sent on reconnect
+    request: OpenAIRequest,
+    /// `Idempotency-Key` of the original request, reused on reconnect
+    idempotency_key: Option<String>,
+    /// Id of the last event received
+    last_event_id: Option<String>,
+    /// Every event id received, to spot a server replaying the stream
+    seen_ids: HashSet<String>,
+    /// Set after a reconnect until the first chunk has been checked
+    verify_resume: bool,
+    /// Reconnects made so far
+    resumes: u32,
+    /// Set once an error has ended the stream
+    done: bool,
+}
+
+/// Parses SSE chunks, reconnecting after a network error mid-stream.
+///
+/// On a dropped connection the request is re-sent with `Last-Event-ID` set to the
+/// last event id seen, and parsing continues on the new response. This only
+/// works for servers that number their events and honour the header, so:
+///
+/// - Without any event id yet, the error is returned as usual.
+/// - The first chunk after a reconnect must carry an id that hasn't been seen. A
+///   server that restarts generation instead (replaying known ids, or sending
+///   none) would duplicate output, so the stream fails with [`Error::Stream`].
+/// - At most [`MAX_STREAM_RESUMES`] reconnects are attempted.
+fn resumable_chunk_stream(
+    client: reqwest::Client,
+    options: AgentOptions,
+    request: OpenAIRequest,
+    idempotency_key: Option<String>,
+    response: reqwest::Response,
+) -> ChunkStream {
+    let state = ResumableStream {
+        events: parse_sse_events(response, options.max_sse_line_bytes()),
+        client,
+        options,
+        request,
+        idempotency_key,
+        last_event_id: None,
+        seen_ids: HashSet::new(),
+        verify_resume: false,
+        resumes: 0,
+        done: false,
+    };
+
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        if state.done {
+            return None;
+        }
+
+        loop {
+            match state.events.next().await? {
+                Ok(SseChunk { id, chunk }) => {
+                    let verify = std::mem::take(&mut state.verify_resume);
+                    if verify && id.as_ref().is_none_or(|id| state.seen_ids.contains(id)) {
+                        state.done = true;
+                        let err = Error::stream(
+                            "Stream dropped and the server does not support resuming it \
+                             (events were replayed after reconnecting)",
+                        );
+                        return Some((Err(err), state));
+                    }
+
+                    if let Some(id) = id {
+                        if state.seen_ids.insert(id.clone()) {
+                            state.last_event_id = Some(id);
+                        }
+                    }
+                    return Some((Ok(chunk), state));
+                }
+                Err(Error::Http(e)) => {
+                    let resumable = state.resumes < MAX_STREAM_RESUMES;
+                    let Some(last_id) = state.last_event_id.clone().filter(|_| resumable) else {
+                        state.done = true;
+                        return Some((Err(Error::Http(e)), state));
+                    };
+
+                    state.resumes += 1;
+                    log::warn!("Stream dropped ({}); resuming after event {}", e, last_id);
+
+                    let resumed = post_chat_completion(
+                        &state.client,
+                        &state.options,
+                        &state.request,
+                        state.idempotency_key.as_deref(),
+                        Some(&last_id),
+                    )
+                    .await;
+                    match resumed {
+                        Ok(response) => {
+                            let max_line_bytes = state.options.max_sse_line_bytes();
+                            state.events = parse_sse_events(response, max_line_bytes);
+                            state.verify_resume = true;
+                        }
+                        Err(e) => {
+                            state.done = true;
+                            return Some((Err(e), state));
+                        }
+                    }
+                }
+                // Parse errors don't end the stream
+                Err(e) => return Some((Err(e), state)),
+            }
+        }
+    }))
+}
+
+/// Response-level details gathered from the chunks of one response.
+#[derive(Debug, Default)]
+struct ResponseInfo {
+    /// Id, model, and creation time, from the first chunk
+    meta: Option<ResponseMeta>,
+    /// The last finish reason seen
+    finish_reason: Option<FinishReason>,
+    /// Usage, if the server reported it
+    usage: Option<Usage>,
+    /// Text received since the last finish reason, i.e. not yet emitted as a block
+    partial_text: String,
+    /// Leaves the response out of `Client::total_usage()`, after `clear_history()`
+    excluded_from_usage: bool,
+}
+
+/// One step of the auto-execution loop
+enum AutoStep {
+    /// The final answer; the loop is over
+    Answer(Vec<ContentBlock>),
+    /// Tool calls, already recorded in history, waiting to be executed
+    ToolCalls(Vec<ToolUseBlock>),
+}
+
+/// Progress of a turn started with `Client::send_streaming_tools()`
+#[derive(Debug, Default)]
+struct ToolActivity {
+    /// Events not yet returned by `receive_event()`
+    events: VecDeque<StreamEvent>,
+    /// Calls announced with `ToolCallStarted` but not executed yet
+    pending: Vec<ToolUseBlock>,
+    /// Whether the auto-execution loop has started
+    started: bool,
+    /// Whether the final answer is in the auto-execution buffer
+    answered: bool,
+}
+
+/// Token usage summed over the responses of a conversation.
+#[derive(Debug, Clone, Copy, Default)]
+struct UsageTally {
+    /// Sum of the usage reported so far
+    total: Usage,
+    /// Responses that reached the client
+    responses: usize,
+    /// How many of those reported usage
+    reported: usize,
+}
+
+impl UsageTally {
+    /// Adds a finished (or abandoned) response; ignored if it never started.
+    fn record(&mut self, info: &ResponseInfo) {
+        if info.meta.is_none() || info.excluded_from_usage {
+            return;
+        }
+        self.responses += 1;
+        if let Some(usage) = info.usage {
+            self.reported += 1;
+            self.total.prompt_tokens += usage.prompt_tokens;
+            self.total.completion_tokens += usage.completion_tokens;
+            self.total.total_tokens += usage.total_tokens;
+        }
+    }
+
+    /// The summed usage, if every response reported it.
+    fn complete_total(&self) -> Option<Usage> {
+        (self.responses > 0 && self.reported == self.responses).then_some(self.total)
+    }
+}
+
+/// Estimated usage of a conversation, from its history.
+///
+/// Each assistant message counts as one response whose prompt was the system
+/// prompt plus everything before it.
+fn estimate_history_usage(system_prompt: &str, history: &[Message], hint: TokenizerHint) -> Usage {
+    let mut conversation = Vec::with_capacity(history.len() + 1);
+    if !system_prompt.is_empty() {
+        conversation.push(Message::system(system_prompt));
+    }
+    let offset = conversation.len();
+    conversation.extend_from_slice(history);
+
+    let mut usage = Usage::default();
+    for (i, message) in conversation.iter().enumerate().skip(offset) {
+        if message.role == MessageRole::Assistant {
+            let prompt = estimate_messages_with_hint(&conversation[..i], hint);
+            let completion = estimate_messages_with_hint(std::slice::from_ref(message), hint);
+            // Saturate rather than overflow on absurdly long histories
+            usage.prompt_tokens = usage
+                .prompt_tokens
+                .saturating_add(u32::try_from(prompt).unwrap_or(u32::MAX));
+            usage.completion_tokens = usage
+                .completion_tokens
+                .saturating_add(u32::try_from(completion).unwrap_or(u32::MAX));
+        }
+    }
+    usage.total_tokens = usage.prompt_tokens.saturating_add(usage.completion_tokens);
+    usage
+}
+
+/// A [`ResponseInfo`] shared between a response stream and its reader.
+type ResponseSlot = Arc<Mutex<ResponseInfo>>;
+
+/// Records response-level details into `slot` as chunks pass through.
+fn record_response_info(chunks: ChunkStream, slot: ResponseSlot) -> ChunkStream {
+    Box::pin(chunks.inspect(move |chunk| {
+        let Ok(chunk) = chunk else {
+            return;
+        };
+        let mut info = lock_info(&slot);
+        info.meta
+            .get_or_insert_with(|| ResponseMeta::from_chunk(chunk));
+        for content in chunk
+            .choices
+            .iter()
+            .filter_map(|c| c.delta.content.as_deref())
+        {
+            info.partial_text.push_str(content);
+        }
+        if let Some(reason) = chunk
+            .choices
+            .iter()
+            .find_map(|c| c.finish_reason.as_deref())
+        {
+            info.finish_reason = Some(FinishReason::from_api(reason));
+            // The aggregator emits the buffered text with the finish reason
+            info.partial_text.clear();
+        }
+        if let Some(usage) = chunk.usage {
+            info.usage = Some(usage);
+        }
+    }))
+}
+
+/// Locks the interrupt reason, recovering from a poisoned lock.
+fn lock_reason(slot: &Mutex<Option<String>>) -> std::sync::MutexGuard<'_, Option<String>> {
+    slot.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Locks a response slot, recovering from a poisoned lock.
+fn lock_info(slot: &Mutex<ResponseInfo>) -> std::sync::MutexGuard<'_, ResponseInfo> {
+    slot.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Turns parsed SSE chunks into a stream of events.
+///
+/// Shared by `query_with_client()` and `Client::send_request()`. Chunks are fed
+/// through a [`ToolCallAggregator`], which yields tool argument fragments as they
+/// arrive and complete blocks at the end of the turn.
+fn event_stream(sse_stream: ChunkStream) -> EventStream {
+    // Aggregate SSE chunks into events
+    // ToolCallAggregator handles partial JSON and assembles complete tool calls
+    // The scan() combinator maintains state across stream items
+    let stream = sse_stream.scan(ToolCallAggregator::new(), |aggregator, chunk_result| {
+        let result = match chunk_result {
+            Ok(chunk) => match aggregator.process_chunk_events(chunk) {
+                Ok(events) => {
+                    if events.is_empty() {
+                        Some(None) // Intermediate chunk, continue streaming
+                    } else {
+                        Some(Some(Ok(events))) // Fragments or complete block(s) ready
+                    }
+                }
+                Err(e) => Some(Some(Err(e))), // Propagate processing error
+            },
+            Err(e) => Some(Some(Err(e))), // Propagate stream error
+        };
+        futures::future::ready(result)
+    });
+
+    // Flatten the stream to emit individual events
+    // filter_map removes None values (incomplete chunks)
+    // flat_map expands Vec<StreamEvent> into individual items
+    let flattened = stream
+        .filter_map(|item| async move { item })
+        .flat_map(|result| {
+            futures::stream::iter(match result {
+                Ok(events) => events.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            })
+        });
+
+    // Pin and box the stream for type erasure and safe async usage
+    Box::pin(flattened)
+}
+
+/// Whether a message only carries tool results.
+fn is_tool_result_message(message: &Message) -> bool {
+    !message.content.is_empty()
+        && message
+            .content
+            .iter()
+            .all(|block| matches!(block, ContentBlock::ToolResult(_)))
+}
+
+/// Whether a recorded user message is a turn `Client::replay()` should send.
+///
+/// Tool results and the empty prompts that continue a tool cycle are not.
+fn is_replayable_turn(message: &Message) -> bool {
+    message.content.iter().any(|block| match block {
+        ContentBlock::Text(text) => !text.text.is_empty(),
+        ContentBlock::Image(_) => true,
+        ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => false,
+    }) && !message
+        .content
+        .iter()
+        .any(|block| matches!(block, ContentBlock::ToolResult(_)))
+}
+
+/// Prepends `prefix` to the first text block of an event stream.
+///
+/// Used by `Client::send_with_prefill()`, whose response continues the prefix.
+fn prefix_first_text(prefix: String, events: EventStream) -> EventStream {
+    Box::pin(futures::stream::unfold(
+        (events, Some(prefix)),
+        |(mut events, mut prefix)| async move {
+            let event = match events.next().await? {
+                Ok(StreamEvent::Block(ContentBlock::Text(text))) if prefix.is_some() => {
+                    let full = prefix.take().unwrap_or_default() + &text.text;
+                    Ok(StreamEvent::Block(ContentBlock::Text(TextBlock::new(full))))
+                }
+                other => other,
+            };
+            Some((event, (events, prefix)))
+        },
+    ))
+}
+
+/// Keeps only the complete blocks of an event stream.
+fn blocks_only(events: EventStream) -> ContentStream {
+    Box::pin(events.filter_map(|event| async move {
+        match event {
+            Ok(StreamEvent::Block(block)) => Some(Ok(block)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }))
+}
+
+/// Routes a stream through a bounded channel when a buffer size is set.
+///
+/// A spawned task drives `stream` and forwards each item; once `size` items are
+/// waiting it blocks on the channel, which stops it polling the HTTP response.
+/// Dropping the returned stream closes the channel and ends the task.
+fn buffer_stream<T: Send + 'static>(
+    stream: Pin<Box<dyn Stream<Item = T> + Send>>,
+    size: Option<usize>,
+) -> Pin<Box<dyn Stream<Item = T> + Send>> {
+    let Some(size) = size else {
+        return stream;
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel(size);
+    tokio::spawn(async move {
+        let mut stream = stream;
+        loop {
+            // Stop as soon as the consumer is gone, not at the next item; a stalled
+            // response would otherwise keep its connection open indefinitely
+            let item = tokio::select! {
+                item = stream.next() => item,
+                _ = tx.closed() => break,
+            };
+            let Some(item) = item else { break };
+            if tx.send(item).await.is_err() {
+                break; // Consumer dropped the stream
+            }
+        }
+    });
+
+    Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx))
+}
+
+/// Single-turn query that deserializes the model's text response into `T`.
+///
+/// Drives the stream to completion, concatenates all text blocks, and parses the
+/// result with `serde_json`. Use it with a system prompt (or a server-side JSON
+/// mode) that tells the model to answer with JSON only.
+///
+/// Surrounding whitespace and a single Markdown code fence (` ```json ... ``` `),
+/// which many local models add even when asked not to, are stripped before parsing.
+///
+/// # Errors
+///
+/// Returns any error from [`query()`] or the stream, or an [`Error::Api`] naming
+/// the target type and quoting the response text if it doesn't parse into `T`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{query_typed, AgentOptions};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Classification {
+///     label: String,
+///     confidence: f32,
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .system_prompt(r#"Reply only with JSON: {"label": string, "confidence": number}"#)
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .build()?;
+///
+/// let result: Classification = query_typed("Classify: 'refund not received'", &options).await?;
+/// println!("{} ({:.2})", result.label, result.confidence);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn query_typed<T: DeserializeOwned>(prompt: &str, options: &AgentOptions) -> Result<T> {
+    let mut stream = query(prompt, options).await?;
+
+    let mut text = String::new();
+    while let Some(block) = stream.next().await {
+        if let ContentBlock::Text(block) = block? {
+            text.push_str(&block.text);
+        }
+    }
+
+    parse_typed_response(&text)
+}
+
+/// Parses concatenated response text into `T` for `query_typed` / `send_typed`.
+fn parse_typed_response<T: DeserializeOwned>(text: &str) -> Result<T> {
+    let mut json = text.trim();
+
+    // Strip a single surrounding Markdown code fence, with or without a language tag
+    if let Some(inner) = json
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        json = inner
+            .split_once('\n')
+            .map_or(inner, |(_lang, body)| body)
+            .trim();
+    }
+
+    serde_json::from_str(json).map_err(|e| {
+        Error::api(format!(
+            "Failed to parse response as {}: {} (response: {:?})",
+            std::any::type_name::<T>(),
+            e,
+            text
+        ))
+    })
 }
 
 /// Stateful client for multi-turn conversations with automatic history management.
@@ -832,8 +2121,9 @@ pub struct Client {
     /// Configuration options including model, API key, tools, hooks, etc.
     ///
     /// This field contains all the settings that control how the client behaves.
-    /// It's set once during construction and cannot be modified (though you can
-    /// access it via `options()` for inspection).
+    /// Read it via `options()`; change it between turns via `options_mut()` or
+    /// shortcuts such as `set_temperature()`. Changes take effect from the next
+    /// request.
     options: AgentOptions,
 
     /// Complete conversation history as a sequence of messages.
@@ -852,7 +2142,13 @@ pub struct Client {
     ///
     /// The stream is set by `send()` and consumed by `receive()`. When the stream
     /// is exhausted, `receive()` returns `Ok(None)` and sets this back to `None`.
-    current_stream: Option<ContentStream>,
+    current_stream: Option<EventStream>,
+
+    /// Hooks in effect when the current stream was requested.
+    ///
+    /// PostReceive hooks for the stream's blocks come from here, so
+    /// [`set_hooks()`](Client::set_hooks) leaves a response already streaming alone.
+    stream_hooks: Hooks,
 
     /// Reusable HTTP client for making API requests.
     ///
@@ -873,15 +2169,80 @@ pub struct Client {
     /// operations. However, only one thread should call `send()`/`receive()`.
     interrupted: Arc<AtomicBool>,
 
-    /// Buffer of content blocks for auto-execution mode.
+    /// Why the client was interrupted, if a reason was given.
     ///
-    /// When `auto_execute_tools` is enabled, `receive()` internally calls the
-    /// auto-execution loop which buffers all final text blocks here. Subsequent
-    /// calls to `receive()` return blocks from this buffer one at a time.
+    /// Set by `interrupt_with_reason()` and cleared with `interrupted` by `send()`.
+    interrupt_reason: Mutex<Option<String>>,
+
+    /// Future registered via `interrupt_on()` that interrupts when it completes.
     ///
-    /// **Only used when `options.auto_execute_tools == true`**.
+    /// Raced against the stream in `receive()`, so cancellation takes effect
+    /// without waiting for the next chunk. Unlike `interrupted`, this is NOT reset
+    /// by `send()`: it stays armed across requests until it fires.
+    interrupt_signal: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+
+    /// The most recent request built by `send()` / `send_message()`.
     ///
-    /// The buffer is cleared when starting a new auto-execution loop.
+    /// Recorded whether or not the request was actually sent, so dry-run mode
+    /// can expose the exact payload. `None` until the first send.
+    last_request: Option<OpenAIRequest>,
+
+    /// Metadata, finish reason, and usage of the most recent response.
+    ///
+    /// Shared with the response stream, which records them as chunks arrive.
+    /// Cleared by each send.
+    last_response: ResponseSlot,
+
+    /// Usage of the responses before the current one, for `total_usage()`.
+    ///
+    /// Reset by `clear_history()`.
+    usage_tally: UsageTally,
+
+    /// Tools offered in the current turn, if limited by `send_with_tools()`.
+    ///
+    /// Cleared by the other send methods.
+    turn_tools: Option<Vec<String>>,
+
+    /// Per-conversation overrides of each tool's [`is_enabled()`](crate::Tool::is_enabled).
+    ///
+    /// Written only by `set_tool_enabled()` and dropped when the tool is removed.
+    /// Not shared with other clients.
+    tool_overrides: HashMap<String, bool>,
+
+    /// Whether the last auto-execution loop stopped at `max_tool_iterations`.
+    ///
+    /// Reset at the start of each loop. **Only used when `options.auto_execute_tools == true`**.
+    hit_iteration_limit: bool,
+
+    /// Whether the last built request used the tool-phase temperature.
+    ///
+    /// Only such a request's text answer is re-requested at the normal temperature.
+    tool_phase_request: bool,
+
+    /// Tool call batches executed by the current auto-execution loop.
+    ///
+    /// Reset at the start of each loop. **Only used when `options.auto_execute_tools == true`**.
+    auto_iterations: u32,
+
+    /// Whether the current auto-execution loop has injected the iteration-limit notice.
+    ///
+    /// Reset at the start of each loop. **Only used when `options.auto_execute_tools == true`**.
+    auto_notice_sent: bool,
+
+    /// Tool activity of a [`send_streaming_tools()`](Client::send_streaming_tools) turn.
+    ///
+    /// `None` for turns started any other way. Drained by `receive_event()`.
+    tool_activity: Option<ToolActivity>,
+
+    /// Buffer of content blocks for auto-execution mode.
+    ///
+    /// When `auto_execute_tools` is enabled, `receive()` internally calls the
+    /// auto-execution loop which buffers all final text blocks here. Subsequent
+    /// calls to `receive()` return blocks from this buffer one at a time.
+    ///
+    /// **Only used when `options.auto_execute_tools == true`**.
+    ///
+    /// The buffer is cleared when starting a new auto-execution loop.
     auto_exec_buffer: Vec<ContentBlock>,
 
     /// Current read position in the auto-execution buffer.
@@ -926,17 +2287,143 @@ impl Client {
     pub fn new(options: AgentOptions) -> Result<Self> {
         // Build HTTP client with configured timeout
         // This client is reused across all requests for connection pooling
-        let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(options.timeout()))
+        let http_client = http_client_builder(&options)
             .build()
             .map_err(|e| Error::config(format!("Failed to build HTTP client: {}", e)))?;
 
+        Self::with_http_client(options, http_client)
+    }
+
+    /// Creates a new client with room for `capacity` history messages.
+    ///
+    /// Like [`Client::new`], but the history is allocated up front, so a long
+    /// session doesn't repeatedly reallocate it as messages are added. Each
+    /// turn adds at least two messages (the prompt and the reply), plus two per
+    /// tool call.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Client::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::with_capacity(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?, 1000)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_capacity(options: AgentOptions, capacity: usize) -> Result<Self> {
+        let mut client = Self::new(options)?;
+        client.history = Vec::with_capacity(capacity);
+        Ok(client)
+    }
+
+    /// Creates a new client after checking the server offers the configured model.
+    ///
+    /// Like [`Client::new`], but also queries the server's `/models` endpoint (see
+    /// [`list_models`](crate::list_models)) so a misspelled or unloaded model fails
+    /// at startup rather than partway through a conversation. Use [`Client::new`]
+    /// where no network round-trip is wanted, e.g. for offline construction.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Config`] if the model isn't in the server's list
+    /// - Network or HTTP errors from the `/models` request
+    /// - Any error from [`Client::new`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new_validated(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_validated(options: AgentOptions) -> Result<Self> {
+        let client = Self::new(options)?;
+
+        let models = crate::models::list_models_with_client(
+            &client.http_client,
+            client.options.base_url(),
+            client.options.api_key(),
+        )
+        .await?;
+        check_model_listed(client.options.model(), &models)?;
+
+        Ok(client)
+    }
+
+    /// Creates a new client that sends requests through a caller-supplied HTTP client.
+    ///
+    /// Use this when you need network configuration the SDK doesn't expose, such as
+    /// mTLS client certificates, proxies, or custom root certificates. The SDK does
+    /// not build its own `reqwest::Client` in this case.
+    ///
+    /// # Timeouts
+    ///
+    /// `AgentOptions::timeout` is **not** applied to the supplied client. You are
+    /// responsible for configuring timeouts on the `reqwest::Client` yourself;
+    /// without one, requests can wait indefinitely.
+    ///
+    /// # Errors
+    ///
+    /// Currently infallible; returns `Result` for symmetry with [`Client::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let ca = reqwest::Certificate::from_pem(&std::fs::read("corp-ca.pem")?)?;
+    /// let http = reqwest::Client::builder()
+    ///     .add_root_certificate(ca)
+    ///     .proxy(reqwest::Proxy::all("http://proxy.corp.example:3128")?)
+    ///     .timeout(Duration::from_secs(120))
+    ///     .build()?;
+    ///
+    /// let client = Client::with_http_client(
+    ///     AgentOptions::builder()
+    ///         .model("qwen2.5-32b-instruct")
+    ///         .base_url("https://gateway.corp.example/v1")
+    ///         .build()?,
+    ///     http,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_http_client(options: AgentOptions, http_client: reqwest::Client) -> Result<Self> {
         Ok(Self {
+            stream_hooks: options.hooks().clone(),
             options,
             history: Vec::new(),  // Empty conversation history
             current_stream: None, // No active stream yet
             http_client,
             interrupted: Arc::new(AtomicBool::new(false)), // Not interrupted initially
+            interrupt_reason: Mutex::new(None),            // No reason recorded
+            interrupt_signal: None,                        // No interrupt future registered
+            last_request: None,                            // Nothing sent yet
+            last_response: ResponseSlot::default(),        // No response yet
+            usage_tally: UsageTally::default(),            // Nothing used yet
+            turn_tools: None,                              // Every tool offered
+            tool_overrides: HashMap::new(),                // Tools' own defaults apply
+            hit_iteration_limit: false,                    // No auto loop has run
+            tool_phase_request: false,                     // Nothing built yet
+            auto_iterations: 0,                            // No tools executed
+            auto_notice_sent: false,                       // No notice injected
+            tool_activity: None,                           // No observed turn
             auto_exec_buffer: Vec::new(),                  // Empty buffer for auto mode
             auto_exec_index: 0,                            // Start at beginning of buffer
         })
@@ -1047,12 +2534,227 @@ impl Client {
     /// # }
     /// ```
     pub async fn send(&mut self, prompt: &str) -> Result<()> {
-        use crate::hooks::UserPromptSubmitEvent;
+        self.reset_turn_state();
+        self.send_prompt(prompt).await
+    }
 
+    /// Sends a prompt with the current turn state.
+    ///
+    /// The body of [`send()`](Client::send), also used by the auto-execution loop
+    /// to request the next response, which must keep the turn's tool subset and
+    /// tool activity.
+    async fn send_prompt(&mut self, prompt: &str) -> Result<()> {
         // Reset interrupt flag for new query
         // This allows the client to be reused after a previous interruption
         // Uses SeqCst ordering to ensure visibility across all threads
-        self.interrupted.store(false, Ordering::SeqCst);
+        self.reset_interrupt();
+
+        self.push_user_prompt(prompt).await?;
+
+        let request = self.build_request()?;
+        self.send_request(request).await
+    }
+
+    /// Sends a prompt, offering the model only the named tools for this turn.
+    ///
+    /// Works like [`send()`](Client::send), but the request's `tools` array is
+    /// limited to `allowed`, e.g. only read-only tools during a review step, or
+    /// only a `confirm` tool on a confirmation turn. An empty slice sends the
+    /// turn with no tools at all. The registry is left as is: the next `send()`
+    /// offers every tool again.
+    ///
+    /// With auto-execution, the restriction holds for every request of the
+    /// turn's tool loop, and a call to a tool outside `allowed` is not run: the
+    /// model gets an error tool result instead (PreToolUse hooks are skipped,
+    /// PostToolUse hooks still see it). In manual mode it covers this one
+    /// request, so send the follow-up after tool results with
+    /// `send_with_tools()` again to keep it. Disabled tools stay hidden even
+    /// when named.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if a name doesn't match a registered
+    /// tool, before anything is sent, plus any error [`send()`](Client::send)
+    /// can return.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{AgentOptions, Client, tool};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let confirm = tool("confirm", "Confirm the pending order")
+    ///     .build(|_| async { Ok(serde_json::json!({"confirmed": true})) });
+    /// let cancel = tool("cancel", "Cancel the pending order")
+    ///     .build(|_| async { Ok(serde_json::json!({"cancelled": true})) });
+    ///
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .tools(vec![confirm, cancel])
+    ///     .auto_execute_tools(true)
+    ///     .build()?)?;
+    ///
+    /// client.send_with_tools("The user said yes, go ahead", &["confirm"]).await?;
+    /// while client.receive().await?.is_some() {}
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_with_tools(&mut self, prompt: &str, allowed: &[&str]) -> Result<()> {
+        if let Some(unknown) = allowed.iter().find(|name| self.get_tool(name).is_none()) {
+            return Err(Error::invalid_input(format!(
+                "send_with_tools: no tool named '{}' is registered",
+                unknown
+            )));
+        }
+
+        self.reset_turn_state();
+        self.turn_tools = Some(allowed.iter().map(|name| name.to_string()).collect());
+        self.send_prompt(prompt).await
+    }
+
+    /// Sends a prompt and reports tool activity through [`receive_event()`](Client::receive_event).
+    ///
+    /// In auto-execution mode tool calls are normally invisible: `receive()` only
+    /// returns the final answer. After this method, `receive_event()` still runs
+    /// the tools automatically, but also yields a [`StreamEvent::ToolCallStarted`]
+    /// for each call before it runs and a [`StreamEvent::ToolCallCompleted`] with
+    /// its result afterwards, so a UI can show a live tool log. The final answer
+    /// follows as [`StreamEvent::Block`]s.
+    ///
+    /// Events arrive per batch: the calls of one response are all announced,
+    /// then run, then reported in call order. The events are
+    /// informational; the loop, hooks and history behave exactly as with
+    /// [`send()`](Client::send). Draining the turn with `receive()` instead
+    /// skips the events.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if `auto_execute_tools` is disabled, plus any
+    /// error [`send()`](Client::send) can return.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{AgentOptions, Client, ContentBlock, StreamEvent, tool};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let weather = tool("get_weather", "Current weather for a city")
+    ///     .param("city", "string")
+    ///     .build(|_| async { Ok(serde_json::json!({"temp_c": 21})) });
+    ///
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .tool(weather)
+    ///     .auto_execute_tools(true)
+    ///     .build()?)?;
+    ///
+    /// client.send_streaming_tools("Is it warm in Lisbon?").await?;
+    /// while let Some(event) = client.receive_event().await? {
+    ///     match event {
+    ///         StreamEvent::ToolCallStarted { name, input, .. } => println!("-> {} {}", name, input),
+    ///         StreamEvent::ToolCallCompleted { name, result, .. } => println!("<- {} {}", name, result),
+    ///         StreamEvent::Block(ContentBlock::Text(text)) => println!("{}", text.text),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_streaming_tools(&mut self, prompt: &str) -> Result<()> {
+        if !self.options.auto_execute_tools() {
+            return Err(Error::config(
+                "send_streaming_tools requires auto_execute_tools to be enabled",
+            ));
+        }
+
+        self.reset_turn_state();
+        self.send_prompt(prompt).await?;
+        self.tool_activity = Some(ToolActivity::default());
+        Ok(())
+    }
+
+    /// Clears the per-turn settings left by the previous `send*()` call.
+    fn reset_turn_state(&mut self) {
+        self.turn_tools = None;
+        self.tool_activity = None;
+    }
+
+    /// Sends a prompt with the start of the assistant's reply already written.
+    ///
+    /// The request ends with an assistant message holding `assistant_prefix`, so
+    /// the model continues from it instead of starting fresh. This steers the
+    /// output format, e.g. a prefix of `{"answer":` gets small models to emit
+    /// JSON. The prefix is prepended to the first text block, so `receive()`
+    /// yields the full reply. If the response has no text, the prefix isn't
+    /// emitted.
+    ///
+    /// The prefix message is only part of this request, not of the history.
+    /// In auto-execution mode the stored reply includes it.
+    ///
+    /// Servers differ in how they continue a trailing assistant message.
+    /// llama.cpp does so automatically. vLLM needs `continue_final_message: true`
+    /// and `add_generation_prompt: false` in the request, which are only sent
+    /// with the [`continue_final_message`](crate::AgentOptionsBuilder::continue_final_message)
+    /// option, since other servers may reject unknown fields.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`send()`](Client::send).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = Client::new(AgentOptions::default())?;
+    /// client
+    ///     .send_with_prefill("Is Rust memory safe? Answer in JSON.", r#"{"answer":"#)
+    ///     .await?;
+    ///
+    /// let json = client.receive_text().await?.unwrap_or_default();
+    /// assert!(json.starts_with(r#"{"answer":"#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_with_prefill(&mut self, prompt: &str, assistant_prefix: &str) -> Result<()> {
+        self.reset_interrupt();
+        self.reset_turn_state();
+
+        self.push_user_prompt(prompt).await?;
+
+        let mut request = self.build_request()?;
+        request.messages.push(OpenAIMessage {
+            role: "assistant".to_string(),
+            content: Some(OpenAIContent::Text(assistant_prefix.to_string())),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+        if self.options.continue_final_message() {
+            for (key, value) in [
+                ("continue_final_message", true),
+                ("add_generation_prompt", false),
+            ] {
+                request
+                    .extra_body
+                    .entry(key)
+                    .or_insert(serde_json::Value::Bool(value));
+            }
+        }
+        self.send_request(request).await?;
+
+        if let Some(stream) = self.current_stream.take() {
+            self.current_stream = Some(prefix_first_text(assistant_prefix.to_string(), stream));
+        }
+        Ok(())
+    }
+
+    /// Runs the UserPromptSubmit hooks and adds the resulting prompt to history.
+    ///
+    /// Shared by `send()` and `send_with_prefill()`. Fails if a hook blocks the prompt.
+    async fn push_user_prompt(&mut self, prompt: &str) -> Result<()> {
+        use crate::hooks::UserPromptSubmitEvent;
 
         // Execute UserPromptSubmit hooks
         // Hooks run BEFORE adding to history, allowing modification or blocking
@@ -1085,277 +2787,124 @@ impl Client {
         // This ensures history consistency even if request fails
         // Empty prompts are still added (needed for tool continuation)
         self.history.push(Message::user(final_prompt));
+        self.enforce_history_cap();
+        Ok(())
+    }
 
-        // Build messages array for API request
-        // This includes system prompt + full conversation history
-        let mut messages = Vec::new();
-
-        // Add system prompt as first message if configured
-        // System prompts are added fresh for each request (not from history)
-        if !self.options.system_prompt().is_empty() {
-            messages.push(OpenAIMessage {
-                role: "system".to_string(),
-                content: Some(OpenAIContent::Text(
-                    self.options.system_prompt().to_string(),
-                )),
-                tool_calls: None,
-                tool_call_id: None,
-            });
+    /// Evicts the oldest turns once history exceeds `max_history_messages`.
+    ///
+    /// A leading system message counts toward the cap but is always kept, and so
+    /// is the latest message, even when the cap leaves no room for it.
+    fn enforce_history_cap(&mut self) {
+        let Some(max) = self.options.max_history_messages() else {
+            return;
+        };
+        if self.history.len() <= max {
+            return;
         }
 
-        // Convert conversation history to OpenAI message format
-        // This includes user prompts, assistant responses, and tool results
-        for msg in &self.history {
-            // Separate blocks by type to determine message structure
-            let mut text_blocks = Vec::new();
-            let mut image_blocks = Vec::new();
-            let mut tool_use_blocks = Vec::new();
-            let mut tool_result_blocks = Vec::new();
-
-            for block in &msg.content {
-                match block {
-                    ContentBlock::Text(text) => text_blocks.push(text),
-                    ContentBlock::Image(image) => image_blocks.push(image),
-                    ContentBlock::ToolUse(tool_use) => tool_use_blocks.push(tool_use),
-                    ContentBlock::ToolResult(tool_result) => tool_result_blocks.push(tool_result),
-                }
-            }
-
-            // Handle different message types based on content blocks
-            // Case 1: Message contains tool results (should be separate tool messages)
-            if !tool_result_blocks.is_empty() {
-                for tool_result in tool_result_blocks {
-                    // Serialize the tool result content as JSON string
-                    let content =
-                        serde_json::to_string(tool_result.content()).unwrap_or_else(|e| {
-                            format!("{{\"error\": \"Failed to serialize: {}\"}}", e)
-                        });
-
-                    messages.push(OpenAIMessage {
-                        role: "tool".to_string(),
-                        content: Some(OpenAIContent::Text(content)),
-                        tool_calls: None,
-                        tool_call_id: Some(tool_result.tool_use_id().to_string()),
-                    });
-                }
-            }
-            // Case 2: Message contains tool use blocks (assistant with tool calls)
-            else if !tool_use_blocks.is_empty() {
-                // Build tool_calls array
-                let tool_calls: Vec<OpenAIToolCall> = tool_use_blocks
-                    .iter()
-                    .map(|tool_use| {
-                        // Serialize the input as a JSON string (OpenAI API requirement)
-                        let arguments = serde_json::to_string(tool_use.input())
-                            .unwrap_or_else(|_| "{}".to_string());
-
-                        OpenAIToolCall {
-                            id: tool_use.id().to_string(),
-                            call_type: "function".to_string(),
-                            function: OpenAIFunction {
-                                name: tool_use.name().to_string(),
-                                arguments,
-                            },
-                        }
-                    })
-                    .collect();
-
-                // Extract any text content (some models include reasoning before tool calls)
-                // Note: OpenAI API requires content field even if empty when tool_calls present
-                let content = if !text_blocks.is_empty() {
-                    let text = text_blocks
-                        .iter()
-                        .map(|t| t.text.as_str())
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    Some(OpenAIContent::Text(text))
-                } else {
-                    // Empty string satisfies OpenAI API schema (content is required)
-                    Some(OpenAIContent::Text(String::new()))
-                };
+        let has_system = self
+            .history
+            .first()
+            .is_some_and(|m| m.role == MessageRole::System);
+        let keep = max.saturating_sub(usize::from(has_system)).max(1);
+        let before = self.history.len();
+        self.history = truncate_messages(&self.history, keep, true);
+        log::debug!(
+            "Evicted {} history messages (max_history_messages = {})",
+            before - self.history.len(),
+            max
+        );
+    }
 
-                messages.push(OpenAIMessage {
-                    role: "assistant".to_string(),
-                    content,
-                    tool_calls: Some(tool_calls),
-                    tool_call_id: None,
-                });
+    /// Builds the API request for the current history and options.
+    ///
+    /// Shared by `send()` and `send_message()`: serializes the system prompt and
+    /// the full conversation history (text, images, tool calls, tool results) into
+    /// OpenAI message format and attaches the registered tools.
+    ///
+    /// In auto mode, a request that offers tools uses the
+    /// [`tool_phase_temperature`](crate::AgentOptionsBuilder::tool_phase_temperature)
+    /// if one is set; `tool_phase_request` records whether it did.
+    fn build_request(&mut self) -> Result<OpenAIRequest> {
+        let messages = history_messages(&self.options, &self.history)?;
+        let mut request = chat_request_with_tools(&self.options, messages, |tool| {
+            self.is_tool_enabled(tool.name())
+        });
+        if let Some(allowed) = &self.turn_tools {
+            restrict_tools(&mut request, allowed);
+        }
+        self.tool_phase_request = false;
+        if let Some(temperature) = self.options.tool_phase_temperature() {
+            if self.options.auto_execute_tools() && request.tools.is_some() {
+                request.temperature = Some(temperature);
+                self.tool_phase_request = true;
             }
-            // Case 3: Message contains images (use OpenAIContent::Parts)
-            else if !image_blocks.is_empty() {
-                // Log debug info about images being serialized
-                log::debug!(
-                    "Serializing message with {} image(s) for {:?} role",
-                    image_blocks.len(),
-                    msg.role
-                );
-
-                // Build content parts array preserving original order
-                let mut content_parts = Vec::new();
+        }
+        Ok(request)
+    }
 
-                // Re-iterate through content blocks to maintain order
-                for block in &msg.content {
-                    match block {
-                        ContentBlock::Text(text) => {
-                            content_parts.push(OpenAIContentPart::text(&text.text));
-                        }
-                        ContentBlock::Image(image) => {
-                            // Log image details (truncate URL for privacy)
-                            let url_display = if image.url().len() > 100 {
-                                format!("{}... ({} chars)", &image.url()[..100], image.url().len())
-                            } else {
-                                image.url().to_string()
-                            };
-                            let detail_str = match image.detail() {
-                                crate::types::ImageDetail::Low => "low",
-                                crate::types::ImageDetail::High => "high",
-                                crate::types::ImageDetail::Auto => "auto",
-                            };
-                            log::debug!("  - Image: {} (detail: {})", url_display, detail_str);
-
-                            content_parts.push(OpenAIContentPart::from_image(image));
-                        }
-                        ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => {}
-                    }
-                }
+    /// Builds a request for the final answer of the auto-execution loop.
+    ///
+    /// Like [`build_request`](Client::build_request), but always at the normal
+    /// temperature, and with `tool_choice: "none"` so the model answers rather
+    /// than calling tools. The tools are still sent, since some chat templates
+    /// need their definitions to render the tool calls already in history.
+    fn build_answer_request(&mut self) -> Result<OpenAIRequest> {
+        let mut request = self.build_request()?;
+        self.tool_phase_request = false;
+        request.temperature = Some(self.options.temperature());
+        if request.tools.is_some() {
+            request.tool_choice = Some("none".to_string());
+        }
+        Ok(request)
+    }
 
-                // Defensive check: content_parts should never be empty at this point
-                // If it is, it indicates a logic error (e.g., all blocks were filtered out)
-                if content_parts.is_empty() {
-                    return Err(Error::other(
-                        "Internal error: Message with images produced empty content array",
-                    ));
-                }
+    /// Sends a built request and stores the response stream for `receive()`.
+    ///
+    /// The request is kept for [`last_request()`](Client::last_request). In dry-run
+    /// mode no HTTP call is made and no stream is set, so `receive()` returns `Ok(None)`.
+    async fn send_request(&mut self, request: OpenAIRequest) -> Result<()> {
+        let request = self.last_request.insert(request);
+        let previous = std::mem::take(&mut *lock_info(&self.last_response));
+        self.usage_tally.record(&previous);
 
-                let role_str = match msg.role {
-                    MessageRole::System => "system",
-                    MessageRole::User => "user",
-                    MessageRole::Assistant => "assistant",
-                    MessageRole::Tool => "tool",
-                };
+        if self.options.dry_run() {
+            self.current_stream = None;
+            return Ok(());
+        }
 
-                messages.push(OpenAIMessage {
-                    role: role_str.to_string(),
-                    content: Some(OpenAIContent::Parts(content_parts)),
-                    tool_calls: None,
-                    tool_call_id: None,
-                });
-            }
-            // Case 4: Message contains only text (normal message, backward compatible)
-            else {
-                let content = text_blocks
-                    .iter()
-                    .map(|t| t.text.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n");
+        // POST to the chat completions endpoint; non-success statuses become errors
+        let idempotency_key = request_idempotency_key(&self.options);
+        let response = post_chat_completion(
+            &self.http_client,
+            &self.options,
+            request,
+            idempotency_key.as_deref(),
+            None,
+        )
+        .await?;
 
-                let role_str = match msg.role {
-                    MessageRole::System => "system",
-                    MessageRole::User => "user",
-                    MessageRole::Assistant => "assistant",
-                    MessageRole::Tool => "tool",
-                };
+        // Store the stream for consumption via receive()
+        // The stream is NOT consumed here - that happens in receive()
+        self.stream_hooks = self.options.hooks().clone();
+        let chunks = chunk_stream(
+            &self.http_client,
+            &self.options,
+            request,
+            idempotency_key,
+            response,
+        );
+        self.current_stream = Some(buffer_stream(
+            event_stream(record_response_info(
+                chunks,
+                Arc::clone(&self.last_response),
+            )),
+            self.options.stream_buffer_size(),
+        ));
 
-                messages.push(OpenAIMessage {
-                    role: role_str.to_string(),
-                    content: Some(OpenAIContent::Text(content)),
-                    tool_calls: None,
-                    tool_call_id: None,
-                });
-            }
-        }
-
-        // Convert tools to OpenAI format if any are registered
-        // Each tool is described with name, description, and JSON Schema parameters
-        let tools = if !self.options.tools().is_empty() {
-            Some(
-                self.options
-                    .tools()
-                    .iter()
-                    .map(|t| t.to_openai_format())
-                    .collect(),
-            )
-        } else {
-            None
-        };
-
-        // Build the OpenAI-compatible request payload
-        let request = OpenAIRequest {
-            model: self.options.model().to_string(),
-            messages,
-            stream: true, // Always stream for progressive rendering
-            max_tokens: self.options.max_tokens(),
-            temperature: Some(self.options.temperature()),
-            tools,
-        };
-
-        // Make HTTP POST request to chat completions endpoint
-        let url = format!("{}/chat/completions", self.options.base_url());
-        let response = self
-            .http_client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.options.api_key()),
-            )
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(Error::Http)?;
-
-        // Check for HTTP-level errors before processing stream
-        // This catches authentication, rate limits, invalid models, etc.
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_else(|e| {
-                eprintln!("WARNING: Failed to read error response body: {}", e);
-                "Unknown error (failed to read response body)".to_string()
-            });
-            return Err(Error::api(format!("API error {}: {}", status, body)));
-        }
-
-        // Parse Server-Sent Events (SSE) stream from response
-        let sse_stream = parse_sse_stream(response);
-
-        // Aggregate SSE chunks into complete content blocks
-        // ToolCallAggregator maintains state to handle incremental JSON chunks
-        // that may arrive split across multiple SSE events
-        let stream = sse_stream.scan(ToolCallAggregator::new(), |aggregator, chunk_result| {
-            let result = match chunk_result {
-                Ok(chunk) => match aggregator.process_chunk(chunk) {
-                    Ok(blocks) => {
-                        if blocks.is_empty() {
-                            Some(None) // Partial chunk, keep aggregating
-                        } else {
-                            Some(Some(Ok(blocks))) // Complete block(s) ready
-                        }
-                    }
-                    Err(e) => Some(Some(Err(e))), // Processing error
-                },
-                Err(e) => Some(Some(Err(e))), // Stream error
-            };
-            futures::future::ready(result)
-        });
-
-        // Flatten the stream to emit individual blocks
-        // filter_map removes None values (partial chunks)
-        // flat_map converts Vec<ContentBlock> to individual items
-        let flattened = stream
-            .filter_map(|item| async move { item })
-            .flat_map(|result| {
-                futures::stream::iter(match result {
-                    Ok(blocks) => blocks.into_iter().map(Ok).collect(),
-                    Err(e) => vec![Err(e)],
-                })
-            });
-
-        // Store the stream for consumption via receive()
-        // The stream is NOT consumed here - that happens in receive()
-        self.current_stream = Some(Box::pin(flattened));
-
-        Ok(())
-    }
+        Ok(())
+    }
 
     /// Internal method that returns one block from the current stream.
     ///
@@ -1378,7 +2927,56 @@ impl Client {
     /// This method checks the interrupt flag on every call, allowing responsive
     /// cancellation. The check uses SeqCst ordering for immediate visibility of
     /// interrupts from other threads.
+    ///
+    /// Each block is passed through the PostReceive hooks, which may replace it
+    /// or drop it (in which case the next block is read instead).
     async fn receive_one(&mut self) -> Result<Option<ContentBlock>> {
+        loop {
+            let Some(block) = self.receive_raw().await? else {
+                return Ok(None);
+            };
+
+            if let Some(block) = self.apply_post_receive(block).await {
+                return Ok(Some(block));
+            }
+        }
+    }
+
+    /// Runs the PostReceive hooks on a block.
+    ///
+    /// Returns the block (possibly replaced by a hook), or `None` if a hook dropped it.
+    async fn apply_post_receive(&self, block: ContentBlock) -> Option<ContentBlock> {
+        let hooks = &self.stream_hooks;
+        if hooks.post_receive.is_empty() {
+            return Some(block);
+        }
+
+        let event = PostReceiveEvent::new(block.clone());
+        match hooks.execute_post_receive(event).await {
+            // A blocking decision drops this block
+            Some(decision) if !decision.continue_execution() => None,
+            Some(decision) => Some(decision.modified_block().cloned().unwrap_or(block)),
+            None => Some(block),
+        }
+    }
+
+    /// Reads the next complete block from the current stream, before PostReceive
+    /// hooks run. Incremental events are skipped.
+    async fn receive_raw(&mut self) -> Result<Option<ContentBlock>> {
+        loop {
+            match self.receive_raw_event().await? {
+                Some(StreamEvent::Block(block)) => return Ok(Some(block)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads the next event from the current stream.
+    ///
+    /// Handles interrupt checking (both the flag and any `interrupt_on()` future)
+    /// and clears `current_stream` when interrupted.
+    async fn receive_raw_event(&mut self) -> Result<Option<StreamEvent>> {
         // Check interrupt flag before attempting to receive
         // Uses SeqCst to ensure we see the latest value from any thread
         if self.interrupted.load(Ordering::SeqCst) {
@@ -1389,8 +2987,25 @@ impl Client {
 
         // Poll the current stream if one exists
         if let Some(stream) = &mut self.current_stream {
-            match stream.next().await {
-                Some(Ok(block)) => Ok(Some(block)), // Got a block
+            // Race the registered interrupt future (if any) against the next chunk
+            let next = match &mut self.interrupt_signal {
+                Some(signal) => tokio::select! {
+                    _ = signal.as_mut() => None,
+                    next = stream.next() => Some(next),
+                },
+                None => Some(stream.next().await),
+            };
+
+            let Some(next) = next else {
+                // Interrupt future fired: behave exactly like interrupt()
+                self.interrupt_signal = None;
+                self.interrupted.store(true, Ordering::SeqCst);
+                self.current_stream = None;
+                return Ok(None);
+            };
+
+            match next {
+                Some(Ok(event)) => Ok(Some(event)), // Got an event
                 Some(Err(e)) => Err(e),             // Stream error
                 None => Ok(None),                   // Stream ended
             }
@@ -1461,7 +3076,17 @@ impl Client {
         &self,
         tool_name: &str,
         input: serde_json::Value,
-    ) -> Result<serde_json::Value> {
+    ) -> Result<ToolOutput> {
+        // Execute the tool's async function
+        self.runnable_tool(tool_name)?
+            .execute_with_images(input)
+            .await
+    }
+
+    /// Looks up a registered, enabled tool for auto-execution.
+    ///
+    /// Returns a ToolError if no tool has that name or the tool is disabled.
+    fn runnable_tool(&self, tool_name: &str) -> Result<&crate::tools::Tool> {
         // Find tool in registered tools by name
         let tool = self
             .options
@@ -1470,8 +3095,167 @@ impl Client {
             .find(|t| t.name() == tool_name)
             .ok_or_else(|| Error::tool(format!("Tool '{}' not found", tool_name)))?;
 
-        // Execute the tool's async function
-        tool.execute(input).await
+        // The model may still name a tool it was told about earlier
+        if !self.is_tool_enabled(tool_name) {
+            return Err(Error::tool(format!("Tool '{}' is disabled", tool_name)));
+        }
+
+        Ok(tool)
+    }
+
+    /// Executes a tool, retrying handler failures under [`ToolErrorPolicy::Retry`].
+    ///
+    /// Other policies run the tool once; the caller decides what to do with an error.
+    /// Unknown and disabled tools fail at once: retrying can't change the outcome.
+    async fn execute_tool_with_policy(
+        &self,
+        tool_name: &str,
+        input: serde_json::Value,
+    ) -> Result<ToolOutput> {
+        use crate::retry::{RetryConfig, retry_with_backoff};
+
+        match self.options.tool_error_policy() {
+            ToolErrorPolicy::Retry { max } => {
+                let tool = self.runnable_tool(tool_name)?;
+                let config = RetryConfig::new()
+                    .with_max_attempts(max.saturating_add(1))
+                    .with_initial_delay(TOOL_RETRY_INITIAL_DELAY);
+                retry_with_backoff(config, || tool.execute_with_images(input.clone())).await
+            }
+            _ => self.execute_tool_internal(tool_name, input).await,
+        }
+    }
+
+    /// Runs one tool call for the auto-execution loop, hooks included.
+    ///
+    /// Executes the PreToolUse hooks, the tool itself (unless a hook blocked it)
+    /// and the PostToolUse hooks, returning the result to record along with any
+    /// images the tool produced. Failures become a JSON error result for the
+    /// model, except under [`ToolErrorPolicy::Abort`], where the error is returned.
+    async fn run_tool_call(
+        &self,
+        tool_use: &ToolUseBlock,
+        history_snapshot: Vec<serde_json::Value>,
+    ) -> Result<ToolOutput> {
+        // ============================================================
+        // Execute PreToolUse hooks
+        // ============================================================
+        use crate::hooks::PreToolUseEvent;
+        let pre_event = PreToolUseEvent::new(
+            tool_use.name().to_string(),
+            tool_use.input().clone(),
+            tool_use.id().to_string(),
+            history_snapshot.clone(),
+        );
+
+        // Track whether to execute and what input to use
+        let mut tool_input = tool_use.input().clone();
+        let mut should_execute = true;
+        let mut block_reason = None;
+
+        // A tool left out by send_with_tools() is never run, even if the model calls it
+        let offered = self
+            .turn_tools
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|name| name == tool_use.name()));
+
+        // Execute all PreToolUse hooks
+        if !offered {
+            should_execute = false;
+        } else if let Some(decision) = self.options.hooks().execute_pre_tool_use(pre_event).await {
+            if !decision.continue_execution() {
+                // Hook blocked execution
+                should_execute = false;
+                block_reason = decision.reason().map(|s| s.to_string());
+            } else if let Some(modified) = decision.modified_input() {
+                // Hook modified the input
+                tool_input = modified.clone();
+            }
+        }
+
+        // ============================================================
+        // Execute tool (or create error result if blocked)
+        // ============================================================
+        // Images bypass the hooks and filters, which only see the JSON result
+        let mut images = Vec::new();
+        let result = if should_execute {
+            // Actually execute the tool, retrying if the policy says so.
+            // Truncated arguments are reported instead of run, even when repaired.
+            let outcome = if tool_use.is_partial() {
+                Err(tool_use.arguments_error().unwrap_or_else(|| {
+                    Error::tool(format!(
+                        "Tool '{}' arguments were cut off mid-stream; the call was not run",
+                        tool_use.name()
+                    ))
+                }))
+            } else {
+                self.execute_tool_with_policy(tool_use.name(), tool_input.clone())
+                    .await
+            };
+            match outcome {
+                Ok(output) => {
+                    // Success - use the result
+                    images = output.images;
+                    output.value
+                }
+                Err(e) if self.options.tool_error_policy() == ToolErrorPolicy::Abort => {
+                    return Err(e);
+                }
+                Err(e) => {
+                    // Tool execution failed - convert to JSON error
+                    // This allows the conversation to continue
+                    serde_json::json!({
+                        "error": e.to_string(),
+                        "tool": tool_use.name(),
+                        "id": tool_use.id()
+                    })
+                }
+            }
+        } else if !offered {
+            serde_json::json!({
+                "error": format!("Tool '{}' is not available for this turn", tool_use.name()),
+                "tool": tool_use.name(),
+                "id": tool_use.id()
+            })
+        } else {
+            // Tool blocked by PreToolUse hook - create error result
+            serde_json::json!({
+                "error": "Tool execution blocked by hook",
+                "reason": block_reason.unwrap_or_else(|| "No reason provided".to_string()),
+                "tool": tool_use.name(),
+                "id": tool_use.id()
+            })
+        };
+
+        // ============================================================
+        // Execute PostToolUse hooks
+        // ============================================================
+        use crate::hooks::PostToolUseEvent;
+        let post_event = PostToolUseEvent::new(
+            tool_use.name().to_string(),
+            tool_input,
+            tool_use.id().to_string(),
+            result.clone(),
+            history_snapshot,
+        );
+
+        let mut final_result = result;
+        if let Some(decision) = self.options.hooks().execute_post_tool_use(post_event).await {
+            // PostToolUse can replace the result. modified_input is
+            // still honoured for hooks written before modify_tool_result
+            if let Some(modified) = decision.modified_result().or(decision.modified_input()) {
+                final_result = modified.clone();
+            }
+        }
+
+        // Tool result filters run last and regardless of the hook chain's decision
+        Ok(ToolOutput {
+            value: self
+                .options
+                .hooks()
+                .filter_tool_result(Some(tool_use.name()), final_result),
+            images,
+        })
     }
 
     /// Auto-execution loop that handles tool calls automatically.
@@ -1514,18 +3298,50 @@ impl Client {
     /// - Assistant messages with text + tool use blocks
     /// - User messages with tool result blocks
     ///
-    /// This creates a proper conversation flow that the model can follow.
+    /// This creates a proper conversation flow that the model can follow. Each
+    /// added message is reported to the HistoryChanged hooks.
+    ///
+    /// # Result Ordering
+    ///
+    /// Tool results are added to history in the same order as the tool calls in
+    /// the assistant message, no matter how long each tool takes. Some models
+    /// match results to calls by position rather than by id. The calls in one
+    /// response run one after another, or concurrently when `max_parallel_tools`
+    /// is set, and their results are collected before any is added.
     ///
     /// # Error Recovery
     ///
     /// If a tool execution fails, the error is converted to a JSON error response
     /// and added as the tool result. This allows the conversation to continue
-    /// and lets the model handle the error.
+    /// and lets the model handle the error. The `tool_error_policy` option can
+    /// retry the tool first or abort the loop instead.
     async fn auto_execute_loop(&mut self) -> Result<Vec<ContentBlock>> {
-        use crate::types::ToolResultBlock;
+        self.start_auto_loop();
+
+        loop {
+            match self.auto_step().await? {
+                AutoStep::Answer(blocks) => return Ok(blocks),
+                AutoStep::ToolCalls(tool_uses) => {
+                    self.execute_tool_calls(&tool_uses).await?;
+                }
+            }
+        }
+    }
+
+    /// Resets the per-loop state before an auto-execution loop starts.
+    fn start_auto_loop(&mut self) {
+        self.auto_iterations = 0;
+        self.auto_notice_sent = false;
+        self.hit_iteration_limit = false;
+    }
 
-        // Track iterations to prevent infinite loops
-        let mut iteration = 0;
+    /// Runs the auto-execution loop up to the next batch of tool calls.
+    ///
+    /// Covers steps 1-5 of [`auto_execute_loop()`](Client::auto_execute_loop):
+    /// reads the current response and either returns the final answer or records
+    /// the assistant message and returns its tool calls, which the caller then
+    /// runs with [`execute_tool_calls()`](Client::execute_tool_calls).
+    async fn auto_step(&mut self) -> Result<AutoStep> {
         let max_iterations = self.options.max_tool_iterations();
 
         loop {
@@ -1537,7 +3353,7 @@ impl Client {
 
             // Empty response means stream ended or was interrupted
             if blocks.is_empty() {
-                return Ok(Vec::new());
+                return Ok(AutoStep::Answer(Vec::new()));
             }
 
             // ========================================================================
@@ -1560,28 +3376,63 @@ impl Client {
             // ========================================================================
             // If the response contains no tool calls, we've reached the final answer
             if tool_blocks.is_empty() {
+                // An answer sampled at the tool-phase temperature is discarded and
+                // requested again at the normal temperature
+                let answer_temperature = Some(self.options.temperature());
+                if self.tool_phase_request
+                    && self
+                        .last_request
+                        .as_ref()
+                        .is_some_and(|request| request.temperature != answer_temperature)
+                {
+                    let request = self.build_answer_request()?;
+                    self.send_request(request).await?;
+                    continue;
+                }
+
                 // Add assistant's final text response to history
                 if !text_blocks.is_empty() {
                     let assistant_msg = Message::assistant(text_blocks.clone());
                     self.history.push(assistant_msg);
+                    self.notify_history_changed().await;
                 }
                 // Return text blocks to caller via buffered receive()
-                return Ok(text_blocks);
+                return Ok(AutoStep::Answer(text_blocks));
             }
 
             // ========================================================================
             // STEP 4: Check iteration limit BEFORE executing tools
             // ========================================================================
             // Increment counter and check if we've hit the max
-            iteration += 1;
-            if iteration > max_iterations {
-                // Max iterations reached - stop execution and return what we have
-                // This prevents infinite tool-calling loops
+            self.auto_iterations += 1;
+            if self.auto_iterations > max_iterations {
+                // Max iterations reached - the pending tool calls are never executed,
+                // so only the text goes into history (keeps tool calls/results paired)
+                self.hit_iteration_limit = true;
                 if !text_blocks.is_empty() {
                     let assistant_msg = Message::assistant(text_blocks.clone());
                     self.history.push(assistant_msg);
+                    self.notify_history_changed().await;
+                }
+
+                match self.options.on_iteration_limit() {
+                    IterationLimitBehavior::Error => {
+                        return Err(Error::tool(format!(
+                            "max_tool_iterations ({}) reached with tool calls still pending",
+                            max_iterations
+                        )));
+                    }
+                    IterationLimitBehavior::InjectNotice if !self.auto_notice_sent => {
+                        // Ask for a final answer; a second overrun returns partial text
+                        self.auto_notice_sent = true;
+                        self.history.push(Message::user(ITERATION_LIMIT_NOTICE));
+                        self.notify_history_changed().await;
+                        let request = self.build_answer_request()?;
+                        self.send_request(request).await?;
+                        continue;
+                    }
+                    _ => return Ok(AutoStep::Answer(text_blocks)),
                 }
-                return Ok(text_blocks);
             }
 
             // ========================================================================
@@ -1593,119 +3444,126 @@ impl Client {
             all_blocks.extend(tool_blocks.clone());
             let assistant_msg = Message::assistant(all_blocks);
             self.history.push(assistant_msg);
+            self.notify_history_changed().await;
+
+            return Ok(AutoStep::ToolCalls(
+                tool_blocks
+                    .into_iter()
+                    .filter_map(|block| match block {
+                        ContentBlock::ToolUse(tool_use) => Some(tool_use),
+                        _ => None,
+                    })
+                    .collect(),
+            ));
+        }
+    }
 
-            // ========================================================================
-            // STEP 6: Execute all tools and collect results
-            // ========================================================================
-            for block in tool_blocks {
-                if let ContentBlock::ToolUse(tool_use) = block {
-                    // Create simplified history snapshot for hooks
-                    // TODO: Full serialization of history for hooks
-                    let history_snapshot: Vec<serde_json::Value> =
-                        self.history.iter().map(|_| serde_json::json!({})).collect();
-
-                    // ============================================================
-                    // Execute PreToolUse hooks
-                    // ============================================================
-                    use crate::hooks::PreToolUseEvent;
-                    let pre_event = PreToolUseEvent::new(
-                        tool_use.name().to_string(),
-                        tool_use.input().clone(),
-                        tool_use.id().to_string(),
-                        history_snapshot.clone(),
-                    );
-
-                    // Track whether to execute and what input to use
-                    let mut tool_input = tool_use.input().clone();
-                    let mut should_execute = true;
-                    let mut block_reason = None;
-
-                    // Execute all PreToolUse hooks
-                    if let Some(decision) =
-                        self.options.hooks().execute_pre_tool_use(pre_event).await
-                    {
-                        if !decision.continue_execution() {
-                            // Hook blocked execution
-                            should_execute = false;
-                            block_reason = decision.reason().map(|s| s.to_string());
-                        } else if let Some(modified) = decision.modified_input() {
-                            // Hook modified the input
-                            tool_input = modified.clone();
-                        }
-                    }
-
-                    // ============================================================
-                    // Execute tool (or create error result if blocked)
-                    // ============================================================
-                    let result = if should_execute {
-                        // Actually execute the tool
-                        match self
-                            .execute_tool_internal(tool_use.name(), tool_input.clone())
-                            .await
-                        {
-                            Ok(res) => res, // Success - use the result
-                            Err(e) => {
-                                // Tool execution failed - convert to JSON error
-                                // This allows the conversation to continue
-                                serde_json::json!({
-                                    "error": e.to_string(),
-                                    "tool": tool_use.name(),
-                                    "id": tool_use.id()
-                                })
-                            }
-                        }
-                    } else {
-                        // Tool blocked by PreToolUse hook - create error result
-                        serde_json::json!({
-                            "error": "Tool execution blocked by hook",
-                            "reason": block_reason.unwrap_or_else(|| "No reason provided".to_string()),
-                            "tool": tool_use.name(),
-                            "id": tool_use.id()
-                        })
-                    };
-
-                    // ============================================================
-                    // Execute PostToolUse hooks
-                    // ============================================================
-                    use crate::hooks::PostToolUseEvent;
-                    let post_event = PostToolUseEvent::new(
-                        tool_use.name().to_string(),
-                        tool_input,
-                        tool_use.id().to_string(),
-                        result.clone(),
-                        history_snapshot,
-                    );
-
-                    let mut final_result = result;
-                    if let Some(decision) =
-                        self.options.hooks().execute_post_tool_use(post_event).await
-                    {
-                        // PostToolUse can modify the result
-                        // Note: Uses modified_input field (naming is historical)
-                        if let Some(modified) = decision.modified_input() {
-                            final_result = modified.clone();
-                        }
-                    }
-
-                    // ============================================================
-                    // Add tool result to history
-                    // ============================================================
-                    // Tool results are added as user messages (per OpenAI convention)
-                    let tool_result = ToolResultBlock::new(tool_use.id(), final_result);
-                    let tool_result_msg =
-                        Message::user_with_blocks(vec![ContentBlock::ToolResult(tool_result)]);
-                    self.history.push(tool_result_msg);
+    /// Executes one batch of tool calls and requests the next response.
+    ///
+    /// Covers steps 6-7 of [`auto_execute_loop()`](Client::auto_execute_loop).
+    /// Returns the results in call order, as they were added to history.
+    async fn execute_tool_calls(
+        &mut self,
+        tool_uses: &[ToolUseBlock],
+    ) -> Result<Vec<ToolResultBlock>> {
+        // ========================================================================
+        // STEP 6: Execute all tools and collect results
+        // ========================================================================
+        // Calls run one at a time unless `max_parallel_tools` opts into running
+        // up to that many at once. Results are pushed in call order either way
+        // (see "Result Ordering" above)
+        // TODO: Full serialization of history for hooks
+        let history_snapshot: Vec<serde_json::Value> =
+            self.history.iter().map(|_| serde_json::json!({})).collect();
+        let limit = self.options.max_parallel_tools().unwrap_or(1);
+
+        let mut results = Vec::with_capacity(tool_uses.len());
+        let mut calls = futures::stream::iter(
+            tool_uses
+                .iter()
+                .map(|tool_use| self.run_tool_call(tool_use, history_snapshot.clone())),
+        )
+        .buffered(limit);
+        while let Some(result) = calls.next().await {
+            // An abort stops the loop here: later calls are never started, and
+            // dropping the stream cancels any still running in parallel mode
+            let aborted = result.is_err();
+            results.push(result);
+            if aborted {
+                break;
+            }
+        }
+        drop(calls);
+
+        // Only ToolErrorPolicy::Abort surfaces an error here. The failed call and
+        // the calls that never ran still get a result, so every tool call in
+        // history stays answered and the next request is valid
+        let mut results = results.into_iter();
+        let mut abort = None;
+        let mut added = Vec::with_capacity(tool_uses.len());
+        for tool_use in tool_uses {
+            let output = match results.next() {
+                Some(Ok(output)) => output,
+                Some(Err(e)) => {
+                    let result = serde_json::json!({
+                        "error": e.to_string(),
+                        "tool": tool_use.name(),
+                        "id": tool_use.id()
+                    });
+                    abort = Some(e);
+                    ToolOutput::new(
+                        self.options
+                            .hooks()
+                            .filter_tool_result(Some(tool_use.name()), result),
+                    )
                 }
+                None => ToolOutput::new(serde_json::json!({
+                    "error": "Not run: an earlier tool call in this turn failed",
+                    "tool": tool_use.name(),
+                    "id": tool_use.id()
+                })),
+            };
+
+            // Tool results are added as user messages (per OpenAI convention)
+            let mut tool_result = output.images.into_iter().fold(
+                ToolResultBlock::new(tool_use.id(), output.value),
+                |block, image| block.with_image(image),
+            );
+            if let Some(max_len) = self.options.tool_result_max_len() {
+                tool_result = tool_result.truncated(max_len);
             }
+            added.push(tool_result.clone());
+            let tool_result_msg =
+                Message::user_with_blocks(vec![ContentBlock::ToolResult(tool_result)]);
+            self.history.push(tool_result_msg);
+            self.notify_history_changed().await;
+        }
+        if let Some(e) = abort {
+            return Err(e);
+        }
 
-            // ========================================================================
-            // STEP 7: Continue conversation to get next response
-            // ========================================================================
-            // Send empty string to continue - the history contains all context
-            self.send("").await?;
+        // ========================================================================
+        // STEP 7: Continue conversation to get next response
+        // ========================================================================
+        // Send empty string to continue - the history contains all context.
+        // The next response is either more tool calls or the final text answer
+        self.send_prompt("").await?;
+        self.notify_history_changed().await;
+
+        Ok(added)
+    }
 
-            // Loop continues to collect and process the next response
-            // This will either be more tool calls or the final text answer
+    /// Fires the HistoryChanged hooks for the message just added to history.
+    ///
+    /// Called by the auto-execution loop after each push.
+    async fn notify_history_changed(&self) {
+        let hooks = self.options.hooks();
+        if hooks.history_changed.is_empty() {
+            return;
+        }
+        if let Some(message) = self.history.last() {
+            let event = HistoryChangedEvent::new(message.clone(), self.history.len() - 1);
+            hooks.execute_history_changed(event).await;
         }
     }
 
@@ -1717,7 +3575,8 @@ impl Client {
     /// ## Manual Mode (default)
     ///
     /// Streams blocks directly from the API response as they arrive. You receive:
-    /// - `TextBlock`: Incremental text from the model
+    /// - `TextBlock`: The model's text. Deltas are buffered and the response's
+    ///   text arrives as a single block when it completes, not one per token
     /// - `ToolUseBlock`: Requests to execute tools
     /// - Other block types as they're emitted
     ///
@@ -1928,7 +3787,8 @@ impl Client {
         // Reset interrupt flag for new query
         // This allows the client to be reused after a previous interruption
         // Uses SeqCst ordering to ensure visibility across all threads
-        self.interrupted.store(false, Ordering::SeqCst);
+        self.reset_interrupt();
+        self.reset_turn_state();
 
         // Note: We do NOT run UserPromptSubmit hooks here because:
         // 1. The message is already fully constructed
@@ -1938,271 +3798,10 @@ impl Client {
         // Add message to history BEFORE sending request
         // This ensures history consistency even if request fails
         self.history.push(message);
+        self.enforce_history_cap();
 
-        // The rest of the logic is identical to send() - build and execute request
-        // Build messages array for API request
-        // This includes system prompt + full conversation history
-        let mut messages = Vec::new();
-
-        // Add system prompt as first message if configured
-        // System prompts are added fresh for each request (not from history)
-        if !self.options.system_prompt().is_empty() {
-            messages.push(OpenAIMessage {
-                role: "system".to_string(),
-                content: Some(OpenAIContent::Text(
-                    self.options.system_prompt().to_string(),
-                )),
-                tool_calls: None,
-                tool_call_id: None,
-            });
-        }
-
-        // Convert conversation history to OpenAI message format
-        // This includes user prompts, assistant responses, and tool results
-        for msg in &self.history {
-            // Separate blocks by type to determine message structure
-            let mut text_blocks = Vec::new();
-            let mut image_blocks = Vec::new();
-            let mut tool_use_blocks = Vec::new();
-            let mut tool_result_blocks = Vec::new();
-
-            for block in &msg.content {
-                match block {
-                    ContentBlock::Text(text) => text_blocks.push(text),
-                    ContentBlock::Image(image) => image_blocks.push(image),
-                    ContentBlock::ToolUse(tool_use) => tool_use_blocks.push(tool_use),
-                    ContentBlock::ToolResult(tool_result) => tool_result_blocks.push(tool_result),
-                }
-            }
-
-            // Handle different message types based on content blocks
-            // Case 1: Message contains tool results (should be separate tool messages)
-            if !tool_result_blocks.is_empty() {
-                for tool_result in tool_result_blocks {
-                    // Serialize the tool result content as JSON string
-                    let content =
-                        serde_json::to_string(tool_result.content()).unwrap_or_else(|e| {
-                            format!("{{\"error\": \"Failed to serialize: {}\"}}", e)
-                        });
-
-                    messages.push(OpenAIMessage {
-                        role: "tool".to_string(),
-                        content: Some(OpenAIContent::Text(content)),
-                        tool_calls: None,
-                        tool_call_id: Some(tool_result.tool_use_id().to_string()),
-                    });
-                }
-            }
-            // Case 2: Message contains tool use blocks (assistant with tool calls)
-            else if !tool_use_blocks.is_empty() {
-                // Build tool_calls array
-                let tool_calls: Vec<OpenAIToolCall> = tool_use_blocks
-                    .iter()
-                    .map(|tool_use| {
-                        // Serialize the input as a JSON string (OpenAI API requirement)
-                        let arguments = serde_json::to_string(tool_use.input())
-                            .unwrap_or_else(|_| "{}".to_string());
-
-                        OpenAIToolCall {
-                            id: tool_use.id().to_string(),
-                            call_type: "function".to_string(),
-                            function: OpenAIFunction {
-                                name: tool_use.name().to_string(),
-                                arguments,
-                            },
-                        }
-                    })
-                    .collect();
-
-                // Extract any text content (some models include reasoning before tool calls)
-                // Note: OpenAI API requires content field even if empty when tool_calls present
-                let content = if !text_blocks.is_empty() {
-                    let text = text_blocks
-                        .iter()
-                        .map(|t| t.text.as_str())
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    Some(OpenAIContent::Text(text))
-                } else {
-                    // Empty string satisfies OpenAI API schema (content is required)
-                    Some(OpenAIContent::Text(String::new()))
-                };
-
-                messages.push(OpenAIMessage {
-                    role: "assistant".to_string(),
-                    content,
-                    tool_calls: Some(tool_calls),
-                    tool_call_id: None,
-                });
-            }
-            // Case 3: Message contains images (use OpenAIContent::Parts)
-            else if !image_blocks.is_empty() {
-                // Log debug info about images being serialized
-                log::debug!(
-                    "Serializing message with {} image(s) for {:?} role",
-                    image_blocks.len(),
-                    msg.role
-                );
-
-                // Build content parts array preserving original order
-                let mut content_parts = Vec::new();
-
-                // Re-iterate through content blocks to maintain order
-                for block in &msg.content {
-                    match block {
-                        ContentBlock::Text(text) => {
-                            content_parts.push(OpenAIContentPart::text(&text.text));
-                        }
-                        ContentBlock::Image(image) => {
-                            // Log image details (truncate URL for privacy)
-                            let url_display = if image.url().len() > 100 {
-                                format!("{}... ({} chars)", &image.url()[..100], image.url().len())
-                            } else {
-                                image.url().to_string()
-                            };
-                            let detail_str = match image.detail() {
-                                crate::types::ImageDetail::Low => "low",
-                                crate::types::ImageDetail::High => "high",
-                                crate::types::ImageDetail::Auto => "auto",
-                            };
-                            log::debug!("  - Image: {} (detail: {})", url_display, detail_str);
-
-                            content_parts.push(OpenAIContentPart::from_image(image));
-                        }
-                        ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => {}
-                    }
-                }
-
-                // Defensive check: content_parts should never be empty at this point
-                // If it is, it indicates a logic error (e.g., all blocks were filtered out)
-                if content_parts.is_empty() {
-                    return Err(Error::other(
-                        "Internal error: Message with images produced empty content array",
-                    ));
-                }
-
-                let role_str = match msg.role {
-                    MessageRole::System => "system",
-                    MessageRole::User => "user",
-                    MessageRole::Assistant => "assistant",
-                    MessageRole::Tool => "tool",
-                };
-
-                messages.push(OpenAIMessage {
-                    role: role_str.to_string(),
-                    content: Some(OpenAIContent::Parts(content_parts)),
-                    tool_calls: None,
-                    tool_call_id: None,
-                });
-            }
-            // Case 4: Message contains only text (normal message, backward compatible)
-            else {
-                let content = text_blocks
-                    .iter()
-                    .map(|t| t.text.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                let role_str = match msg.role {
-                    MessageRole::System => "system",
-                    MessageRole::User => "user",
-                    MessageRole::Assistant => "assistant",
-                    MessageRole::Tool => "tool",
-                };
-
-                messages.push(OpenAIMessage {
-                    role: role_str.to_string(),
-                    content: Some(OpenAIContent::Text(content)),
-                    tool_calls: None,
-                    tool_call_id: None,
-                });
-            }
-        }
-
-        // Convert tools to OpenAI format if any are registered
-        let tools = if !self.options.tools().is_empty() {
-            Some(
-                self.options
-                    .tools()
-                    .iter()
-                    .map(|t| t.to_openai_format())
-                    .collect(),
-            )
-        } else {
-            None
-        };
-
-        // Build the OpenAI-compatible request payload
-        let request = OpenAIRequest {
-            model: self.options.model().to_string(),
-            messages,
-            stream: true,
-            max_tokens: self.options.max_tokens(),
-            temperature: Some(self.options.temperature()),
-            tools,
-        };
-
-        // Make HTTP POST request to chat completions endpoint
-        let url = format!("{}/chat/completions", self.options.base_url());
-        let response = self
-            .http_client
-            .post(&url)
-            .header(
-                "Authorization",
-                format!("Bearer {}", self.options.api_key()),
-            )
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(Error::Http)?;
-
-        // Check for HTTP-level errors
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_else(|e| {
-                eprintln!("WARNING: Failed to read error response body: {}", e);
-                "Unknown error (failed to read response body)".to_string()
-            });
-            return Err(Error::api(format!("API error {}: {}", status, body)));
-        }
-
-        // Parse Server-Sent Events stream
-        let sse_stream = parse_sse_stream(response);
-
-        // Aggregate SSE chunks into complete content blocks
-        let stream = sse_stream.scan(ToolCallAggregator::new(), |aggregator, chunk_result| {
-            let result = match chunk_result {
-                Ok(chunk) => match aggregator.process_chunk(chunk) {
-                    Ok(blocks) => {
-                        if blocks.is_empty() {
-                            Some(None) // Partial chunk
-                        } else {
-                            Some(Some(Ok(blocks))) // Complete blocks
-                        }
-                    }
-                    Err(e) => Some(Some(Err(e))),
-                },
-                Err(e) => Some(Some(Err(e))),
-            };
-            futures::future::ready(result)
-        });
-
-        // Flatten nested Options and filter out None values
-        let stream = stream.filter_map(futures::future::ready);
-
-        // Flatten Vec<ContentBlock> into individual blocks
-        let stream = stream.flat_map(|result| {
-            futures::stream::iter(match result {
-                Ok(blocks) => blocks.into_iter().map(Ok).collect(),
-                Err(e) => vec![Err(e)],
-            })
-        });
-
-        // Store the content stream for receive() to consume
-        self.current_stream = Some(Box::pin(stream));
-
-        Ok(())
+        let request = self.build_request()?;
+        self.send_request(request).await
     }
 
     pub async fn receive(&mut self) -> Result<Option<ContentBlock>> {
@@ -2253,20 +3852,293 @@ impl Client {
         }
     }
 
-    /// Interrupts the current operation by setting the interrupt flag.
+    /// Receives the next text block's text, skipping other blocks.
     ///
-    /// This method provides a thread-safe way to cancel any in-progress streaming
-    /// operation. The interrupt flag is checked by `receive()` before each block,
-    /// allowing responsive cancellation.
+    /// Calls [`receive()`](Client::receive) until it returns a text block or the
+    /// response ends, so hooks, interrupts, and auto-execution behave the same.
+    /// Tool calls and other non-text blocks are discarded: in manual mode, use
+    /// `receive()` instead if the model may call tools.
     ///
-    /// # Behavior
+    /// # Returns
     ///
-    /// - Sets the atomic interrupt flag to `true`
-    /// - Next `receive()` call will return `Ok(None)` and clear the stream
-    /// - Flag is automatically reset to `false` on next `send()` call
-    /// - Safe to call from any thread (uses atomic operations)
-    /// - Idempotent: calling multiple times has same effect as calling once
-    /// - No-op if no operation is in progress
+    /// - `Ok(Some(text))`: The text of the next text block
+    /// - `Ok(None)`: The stream ended or was interrupted
+    /// - `Err(e)`: An error occurred during streaming
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Write a haiku about Rust").await?;
+    ///
+    /// while let Some(text) = client.receive_text().await? {
+    ///     print!("{text}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn receive_text(&mut self) -> Result<Option<String>> {
+        while let Some(block) = self.receive().await? {
+            if let ContentBlock::Text(text) = block {
+                return Ok(Some(text.text));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Writes the response text to `writer` as it streams in.
+    ///
+    /// Drains the current response with [`receive_text()`](Client::receive_text),
+    /// writing each text block's UTF-8 bytes and flushing after every block so
+    /// output appears incrementally, e.g. when piping to stdout. Non-text blocks
+    /// are skipped. An [`interrupt()`](Client::interrupt) ends the stream early
+    /// like it does for `receive()`, and the bytes written so far are returned.
+    ///
+    /// # Returns
+    ///
+    /// The total number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns any streaming error from `receive()`, or [`Error::Other`] if the
+    /// writer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Summarize the Rust book in one paragraph").await?;
+    ///
+    /// let mut stdout = tokio::io::stdout();
+    /// let written = client.stream_to_writer(&mut stdout).await?;
+    /// eprintln!("\n({} bytes)", written);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn stream_to_writer<W>(&mut self, writer: &mut W) -> Result<usize>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let write_error =
+            |e: std::io::Error| Error::other(format!("Failed to write response: {}", e));
+
+        let mut written = 0;
+        while let Some(text) = self.receive_text().await? {
+            writer
+                .write_all(text.as_bytes())
+                .await
+                .map_err(write_error)?;
+            writer.flush().await.map_err(write_error)?;
+            written += text.len();
+        }
+        Ok(written)
+    }
+
+    /// Receives the next event from the response stream.
+    ///
+    /// Like [`receive()`](Client::receive), but in manual mode it also yields
+    /// [`StreamEvent::ToolArgsDelta`] events while a tool call's arguments are being
+    /// generated, so a UI can render the call as it is written. Complete blocks arrive
+    /// as [`StreamEvent::Block`] and go through the PostReceive hooks exactly as they
+    /// would through `receive()`.
+    ///
+    /// In auto-execution mode tool calls are handled internally, so only the final
+    /// blocks are returned, each wrapped in `StreamEvent::Block`. After
+    /// [`send_streaming_tools()`](Client::send_streaming_tools), the tool calls are
+    /// also reported as `ToolCallStarted`/`ToolCallCompleted` events.
+    ///
+    /// Use either `receive()` or `receive_event()` to drain a response, not both:
+    /// they read from the same stream.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(event))`: The next event
+    /// - `Ok(None)`: The stream ended or was interrupted
+    /// - `Err(e)`: An error occurred during streaming
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock, StreamEvent};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Search the docs for streaming").await?;
+    ///
+    /// while let Some(event) = client.receive_event().await? {
+    ///     match event {
+    ///         StreamEvent::ToolArgsDelta { fragment, .. } => print!("{}", fragment),
+    ///         StreamEvent::Block(ContentBlock::ToolUse(tool)) => {
+    ///             println!("\nCalling {} with {}", tool.name(), tool.input());
+    ///         }
+    ///         StreamEvent::Block(ContentBlock::Text(text)) => println!("{}", text.text),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn receive_event(&mut self) -> Result<Option<StreamEvent>> {
+        if self.options.auto_execute_tools() {
+            if self.tool_activity.is_some() {
+                return self.receive_tool_activity().await;
+            }
+            return Ok(self.receive().await?.map(StreamEvent::Block));
+        }
+
+        loop {
+            match self.receive_raw_event().await? {
+                Some(StreamEvent::Block(block)) => {
+                    if let Some(block) = self.apply_post_receive(block).await {
+                        return Ok(Some(StreamEvent::Block(block)));
+                    }
+                }
+                event => return Ok(event),
+            }
+        }
+    }
+
+    /// Advances a [`send_streaming_tools()`](Client::send_streaming_tools) turn by one event.
+    ///
+    /// Runs the auto-execution loop one step at a time, queueing a
+    /// `ToolCallStarted` event per call before executing a batch and a
+    /// `ToolCallCompleted` event per result after it, then hands out the final
+    /// answer from the auto-execution buffer.
+    async fn receive_tool_activity(&mut self) -> Result<Option<StreamEvent>> {
+        loop {
+            let Some(activity) = self.tool_activity.as_mut() else {
+                return Ok(None);
+            };
+
+            if let Some(event) = activity.events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            if activity.answered {
+                let block = self.auto_exec_buffer.get(self.auto_exec_index).cloned();
+                if block.is_some() {
+                    self.auto_exec_index += 1;
+                }
+                return Ok(block.map(StreamEvent::Block));
+            }
+
+            if !activity.pending.is_empty() {
+                let tool_uses = std::mem::take(&mut activity.pending);
+                let results = self.execute_tool_calls(&tool_uses).await?;
+                if let Some(activity) = self.tool_activity.as_mut() {
+                    activity.events.extend(tool_uses.iter().zip(results).map(
+                        |(tool_use, result)| StreamEvent::ToolCallCompleted {
+                            id: tool_use.id().to_string(),
+                            name: tool_use.name().to_string(),
+                            result: result.content().clone(),
+                        },
+                    ));
+                }
+                continue;
+            }
+
+            if !activity.started {
+                activity.started = true;
+                self.start_auto_loop();
+            }
+
+            let step = self.auto_step().await?;
+            let Some(activity) = self.tool_activity.as_mut() else {
+                return Ok(None);
+            };
+            match step {
+                AutoStep::Answer(blocks) => {
+                    self.auto_exec_buffer = blocks;
+                    self.auto_exec_index = 0;
+                    activity.answered = true;
+                }
+                AutoStep::ToolCalls(tool_uses) => {
+                    activity.events.extend(tool_uses.iter().map(|tool_use| {
+                        StreamEvent::ToolCallStarted {
+                            id: tool_use.id().to_string(),
+                            name: tool_use.name().to_string(),
+                            input: tool_use.input().clone(),
+                        }
+                    }));
+                    activity.pending = tool_uses;
+                }
+            }
+        }
+    }
+
+    /// Sends a prompt and deserializes the model's text response into `T`.
+    ///
+    /// Calls [`send()`](Client::send), drains [`receive()`](Client::receive), concatenates
+    /// the text blocks, and parses them with `serde_json`. Works in both manual and
+    /// auto-execution mode; in auto mode, tools run first and only the final text is
+    /// parsed. The exchange is recorded in history like any other turn.
+    ///
+    /// See [`query_typed()`] for how the text is cleaned up before parsing.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `send()`/`receive()`, or an [`Error::Api`] naming the
+    /// target type and quoting the response text if it doesn't parse into `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Classification {
+    ///     label: String,
+    ///     confidence: f32,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .system_prompt(r#"Reply only with JSON: {"label": string, "confidence": number}"#)
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
+    ///
+    /// let result: Classification = client.send_typed("Classify: 'refund not received'").await?;
+    /// println!("{} ({:.2})", result.label, result.confidence);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_typed<T: DeserializeOwned>(&mut self, prompt: &str) -> Result<T> {
+        self.send(prompt).await?;
+
+        let mut text = String::new();
+        while let Some(block) = self.receive().await? {
+            if let ContentBlock::Text(block) = block {
+                text.push_str(&block.text);
+            }
+        }
+
+        parse_typed_response(&text)
+    }
+
+    /// Interrupts the current operation by setting the interrupt flag.
+    ///
+    /// This method provides a thread-safe way to cancel any in-progress streaming
+    /// operation. The interrupt flag is checked by `receive()` before each block,
+    /// allowing responsive cancellation.
+    ///
+    /// # Behavior
+    ///
+    /// - Sets the atomic interrupt flag to `true`
+    /// - Next `receive()` call will return `Ok(None)` and clear the stream
+    /// - Flag is automatically reset to `false` on next `send()` call
+    /// - Safe to call from any thread (uses atomic operations)
+    /// - Idempotent: calling multiple times has same effect as calling once
+    /// - No-op if no operation is in progress
     ///
     /// # Thread Safety
     ///
@@ -2358,6 +4230,117 @@ impl Client {
         self.interrupted.store(true, Ordering::SeqCst);
     }
 
+    /// Interrupts the current operation and records why.
+    ///
+    /// Behaves exactly like [`interrupt()`](Client::interrupt), and keeps
+    /// `reason` for [`last_interrupt_reason()`](Client::last_interrupt_reason),
+    /// so code that sees `receive()` return `Ok(None)` can tell a timeout from
+    /// a user cancel. The reason is cleared by the next `send()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Tell me a long story").await?;
+    ///
+    /// while let Some(_block) = client.receive().await? {
+    ///     // A deadline elsewhere decided to stop
+    ///     client.interrupt_with_reason("timeout after 30s");
+    /// }
+    ///
+    /// match client.last_interrupt_reason() {
+    ///     Some(reason) => println!("stopped: {}", reason),
+    ///     None => println!("done"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_with_reason(&self, reason: impl Into<String>) {
+        *lock_reason(&self.interrupt_reason) = Some(reason.into());
+        self.interrupt();
+    }
+
+    /// Returns why the current request was interrupted, or `None` if it wasn't.
+    ///
+    /// - The reason passed to [`interrupt_with_reason()`](Client::interrupt_with_reason)
+    /// - `"interrupted"` after [`interrupt()`](Client::interrupt), the
+    ///   [`interrupt_handle()`](Client::interrupt_handle) or an
+    ///   [`interrupt_on()`](Client::interrupt_on) future that fired
+    /// - `None` if the response ran to completion (or failed with an error)
+    ///
+    /// Reset by each `send()`.
+    pub fn last_interrupt_reason(&self) -> Option<String> {
+        lock_reason(&self.interrupt_reason).clone().or_else(|| {
+            self.interrupted
+                .load(Ordering::SeqCst)
+                .then(|| "interrupted".to_string())
+        })
+    }
+
+    /// Clears the interrupt flag and reason before a new request.
+    fn reset_interrupt(&self) {
+        self.interrupted.store(false, Ordering::SeqCst);
+        *lock_reason(&self.interrupt_reason) = None;
+    }
+
+    /// Interrupts the current response and reads the rest of it, discarding it.
+    ///
+    /// [`interrupt()`](Client::interrupt) drops the half-read response, which
+    /// closes its connection, so the next request pays for a new connection
+    /// (and TLS handshake). This method instead reads the remaining response to
+    /// its end, so the connection goes back to the pool in a clean state and
+    /// the next `send()` can reuse it. The server keeps generating until the
+    /// response ends, so this suits short cancellations where reuse matters
+    /// more than stopping at once.
+    ///
+    /// Waits at most 5 seconds. Afterwards the client behaves as after
+    /// `interrupt()`: `receive()` returns `Ok(None)` until the next `send()`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Timeout`] if the response didn't end within 5 seconds; it is
+    ///   dropped and its connection closed
+    /// - Any error the stream yields while being drained
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Tell me a long story").await?;
+    ///
+    /// // The user pressed cancel
+    /// if let Err(e) = client.interrupt_and_drain().await {
+    ///     eprintln!("Connection not reused: {}", e);
+    /// }
+    ///
+    /// // Reuses the pooled connection
+    /// client.send("Just the title, please").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn interrupt_and_drain(&mut self) -> Result<()> {
+        self.interrupt();
+        let Some(mut stream) = self.current_stream.take() else {
+            return Ok(());
+        };
+
+        let drain = async {
+            while let Some(event) = stream.next().await {
+                event?;
+            }
+            Ok(())
+        };
+        tokio::time::timeout(INTERRUPT_DRAIN_TIMEOUT, drain)
+            .await
+            .unwrap_or_else(|_| Err(Error::timeout()))
+    }
+
     /// Returns a clone of the interrupt handle for thread-safe cancellation.
     ///
     /// This method provides access to the shared `Arc<AtomicBool>` interrupt flag,
@@ -2387,6 +4370,67 @@ impl Client {
         self.interrupted.clone()
     }
 
+    /// Interrupts the client when the given future completes.
+    ///
+    /// The future is raced against the response stream inside `receive()`, so
+    /// cancellation takes effect immediately, even while waiting for the next
+    /// chunk, rather than on the next poll of the interrupt flag. This composes
+    /// with `tokio::select!`-style structured concurrency: pass a cancellation
+    /// future that a parent task or middleware already controls.
+    ///
+    /// # Behavior
+    ///
+    /// - When the future completes, the effect is the same as [`interrupt()`](Client::interrupt):
+    ///   `receive()` returns `Ok(None)` and the stream is dropped
+    /// - The future stays armed across `send()` calls until it fires, so a single
+    ///   request-scoped token covers every turn made on this client
+    /// - Registering a new future replaces any previously registered one
+    /// - The future's output is ignored
+    /// - [`interrupt()`](Client::interrupt) and [`interrupt_handle()`](Client::interrupt_handle)
+    ///   keep working alongside it
+    ///
+    /// # Examples
+    ///
+    /// ## With a `CancellationToken`
+    ///
+    /// ```rust,ignore
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// let token: CancellationToken = request_scope.token();
+    /// client.interrupt_on(token.clone().cancelled_owned());
+    ///
+    /// client.send("Long request").await?;
+    /// while let Some(block) = client.receive().await? {
+    ///     // Stops as soon as the token is cancelled
+    /// }
+    /// ```
+    ///
+    /// ## With a Deadline
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    /// use std::time::Duration;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.interrupt_on(tokio::time::sleep(Duration::from_secs(30)));
+    ///
+    /// client.send("Long request").await?;
+    /// while let Some(_block) = client.receive().await? {
+    ///     // Process until the deadline
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interrupt_on<F>(&mut self, future: F)
+    where
+        F: Future + Send + 'static,
+    {
+        self.interrupt_signal = Some(Box::pin(async move {
+            future.await;
+        }));
+    }
+
     /// Returns a reference to the conversation history.
     ///
     /// The history contains all messages exchanged in the conversation, including:
@@ -2421,6 +4465,69 @@ impl Client {
         &self.history
     }
 
+    /// Returns the number of completed exchanges in the history.
+    ///
+    /// An exchange is a user prompt answered by the assistant; the tool calls
+    /// and results in between belong to the same exchange. A prompt still
+    /// waiting for its answer isn't counted. Tool result messages don't count
+    /// as prompts, even though they are sent with the user role.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::{AgentOptions, Client, ContentBlock, Message, TextBlock};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.append_message(Message::user("Hi"))?;
+    /// client.append_message(Message::assistant(vec![ContentBlock::Text(TextBlock::new("Hello!"))]))?;
+    /// client.append_message(Message::user("What's 2+2?"))?;
+    ///
+    /// assert_eq!(client.turn_count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn turn_count(&self) -> usize {
+        let mut turns = 0;
+        let mut awaiting_reply = false;
+        for message in &self.history {
+            match message.role {
+                MessageRole::User if !is_tool_result_message(message) => awaiting_reply = true,
+                MessageRole::Assistant if awaiting_reply => {
+                    turns += 1;
+                    awaiting_reply = false;
+                }
+                _ => {}
+            }
+        }
+        turns
+    }
+
+    /// Returns how many history messages each role has.
+    ///
+    /// Roles without messages are absent from the map. Tool results added by
+    /// auto-execution are user messages; those added with
+    /// [`add_tool_result()`](Client::add_tool_result) are tool messages.
+    pub fn message_count_by_role(&self) -> HashMap<MessageRole, usize> {
+        let mut counts = HashMap::new();
+        for message in &self.history {
+            *counts.entry(message.role.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the number of tool calls the assistant made in the history.
+    ///
+    /// Counts every tool use block, whether the tool was run, blocked by a
+    /// hook, or failed.
+    pub fn tool_call_count(&self) -> usize {
+        self.history
+            .iter()
+            .flat_map(|message| &message.content)
+            .filter(|block| matches!(block, ContentBlock::ToolUse(_)))
+            .count()
+    }
+
     /// Returns a mutable reference to the conversation history.
     ///
     /// This allows you to modify the history directly for advanced use cases like:
@@ -2452,17 +4559,105 @@ impl Client {
         &mut self.history
     }
 
-    /// Returns a reference to the agent configuration options.
+    /// Appends a message to the conversation history after basic validation.
     ///
-    /// Provides read-only access to the `AgentOptions` used to configure this client.
+    /// This is the intention-revealing alternative to `history_mut().push()` for
+    /// pre-seeding a conversation, e.g. with hand-crafted few-shot examples before
+    /// the first real `send()`.
     ///
-    /// # Use Cases
+    /// # Validation
     ///
-    /// - Inspecting current configuration
-    /// - Debugging issues
-    /// - Conditional logic based on settings
+    /// - Tool use blocks may only appear in assistant messages
+    /// - Every tool result block must reference a tool use ID from the most recent
+    ///   assistant message already in history
+    /// - Every tool call in history must have a result before any other message
+    ///   follows it, so only tool results can be appended while the most recent
+    ///   assistant message still has unanswered calls
     ///
-    /// # Examples
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if the message would break the conversation
+    /// structure. History is left unchanged in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions, Message, TextBlock, ContentBlock};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    ///
+    /// client.append_message(Message::user("What is the capital of France?"))?;
+    /// client.append_message(Message::assistant(vec![ContentBlock::Text(
+    ///     TextBlock::new("Paris."),
+    /// )]))?;
+    ///
+    /// assert_eq!(client.history().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_message(&mut self, message: Message) -> Result<()> {
+        validate_appended_message(&self.history, &message)?;
+        self.history.push(message);
+        Ok(())
+    }
+
+    /// Appends several messages to the conversation history, validating each in turn.
+    ///
+    /// Each message is validated against the history as it would look after the
+    /// previous messages were appended, using the same rules as [`append_message`].
+    /// The operation is all-or-nothing: if any message is rejected, none are added.
+    ///
+    /// [`append_message`]: Client::append_message
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] for the first message that fails validation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions, Message, TextBlock, ContentBlock};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    ///
+    /// let answer = |text: &str| Message::assistant(vec![ContentBlock::Text(TextBlock::new(text))]);
+    /// client.append_messages(vec![
+    ///     Message::user("2 + 2?"),
+    ///     answer("4"),
+    ///     Message::user("3 * 3?"),
+    ///     answer("9"),
+    /// ])?;
+    ///
+    /// assert_eq!(client.history().len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn append_messages(&mut self, messages: Vec<Message>) -> Result<()> {
+        let original_len = self.history.len();
+
+        for message in messages {
+            if let Err(e) = validate_appended_message(&self.history, &message) {
+                // Roll back anything appended so far
+                self.history.truncate(original_len);
+                return Err(e);
+            }
+            self.history.push(message);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a reference to the agent configuration options.
+    ///
+    /// Provides read-only access to the `AgentOptions` used to configure this client.
+    ///
+    /// # Use Cases
+    ///
+    /// - Inspecting current configuration
+    /// - Debugging issues
+    /// - Conditional logic based on settings
+    ///
+    /// # Examples
     ///
     /// ```rust
     /// # use open_agent::{Client, AgentOptions};
@@ -2480,436 +4675,4434 @@ impl Client {
         &self.options
     }
 
-    /// Clears all conversation history.
-    ///
-    /// This resets the conversation to a blank slate while preserving the client
-    /// configuration (tools, hooks, model, etc.). The next message will start a
-    /// fresh conversation with no prior context.
-    ///
-    /// # State Changes
-    ///
-    /// - Clears `history` vector
-    /// - Does NOT modify current stream, options, or other state
+    /// Returns the agent configuration for changes between turns.
     ///
-    /// # Use Cases
+    /// Changes apply from the next request on; a response already streaming is
+    /// not affected. The fields are private, so every change goes through a
+    /// setter such as [`AgentOptions::set_temperature`], [`AgentOptions::set_model`]
+    /// or [`AgentOptions::add_tool`], which run the builder's validation and keep
+    /// the current value on error.
     ///
-    /// - Starting a new conversation
-    /// - Preventing context length issues
-    /// - Clearing sensitive data
-    /// - Implementing conversation sessions
+    /// The `Client` methods [`set_temperature()`](Client::set_temperature),
+    /// [`register_tool()`](Client::register_tool) and [`set_hooks()`](Client::set_hooks)
+    /// are shortcuts for the same changes.
     ///
     /// # Examples
     ///
-    /// ```rust,no_run
-    /// # use open_agent::{Client, AgentOptions, ContentBlock};
-    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let mut client = Client::new(AgentOptions::default())?;
-    ///
-    /// // First conversation
-    /// client.send("Hello").await?;
-    /// while let Some(_) = client.receive().await? {}
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
     ///
-    /// // Clear and start fresh
-    /// client.clear_history();
+    /// let options = client.options_mut();
+    /// options.set_model("qwen2.5-72b-instruct")?;
+    /// options.set_system_prompt("Answer in one sentence.");
+    /// options.set_top_p(Some(0.9))?;
     ///
-    /// // New conversation with no memory of previous
-    /// client.send("Hello again").await?;
+    /// // Validation still applies; the old value is kept
+    /// assert!(options.set_temperature(5.0).is_err());
+    /// assert_eq!(client.options().temperature(), 0.7);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn clear_history(&mut self) {
-        self.history.clear();
+    pub fn options_mut(&mut self) -> &mut AgentOptions {
+        // Forget overrides of tools removed through an earlier borrow
+        let tools = self.options.tools();
+        self.tool_overrides
+            .retain(|name, _| tools.iter().any(|t| t.name() == name));
+        &mut self.options
     }
 
-    /// Adds a tool result to the conversation history for manual tool execution.
+    /// Changes the sampling temperature used by subsequent requests.
     ///
-    /// This method is used exclusively in **manual mode** after receiving a `ToolUseBlock`.
-    /// The workflow is:
+    /// Takes effect on the next `send()`; a response already streaming is not
+    /// affected, and history is left untouched. Useful for UI controls that adjust
+    /// generation between turns.
     ///
-    /// 1. `receive()` returns a `ToolUseBlock`
-    /// 2. You execute the tool yourself
-    /// 3. Call `add_tool_result()` with the tool's output
-    /// 4. Call `send("")` to continue the conversation
-    /// 5. The model receives the tool result and generates a response
+    /// # Errors
     ///
-    /// # Parameters
+    /// Returns [`Error::InvalidInput`] if `temperature` is outside 0.0 to 2.0; the
+    /// current value is kept.
     ///
-    /// - `tool_use_id`: The unique ID from the `ToolUseBlock` (must match exactly)
-    /// - `content`: The tool's output as a JSON value
+    /// # Examples
     ///
-    /// # Behavior
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
     ///
-    /// Creates a `ToolResultBlock` and adds it to conversation history as a tool message.
-    /// This preserves the tool call/result pairing that the model needs to understand
-    /// the conversation flow.
+    /// client.set_temperature(1.1)?;
+    /// assert_eq!(client.options().temperature(), 1.1);
+    /// assert!(client.set_temperature(3.0).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_temperature(&mut self, temperature: f32) -> Result<()> {
+        self.options.set_temperature(temperature)
+    }
+
+    /// Changes the `max_tokens` limit used by subsequent requests.
     ///
-    /// # State Changes
+    /// `None` leaves the limit to the server. Takes effect on the next `send()`.
     ///
-    /// - Appends a tool message to `history`
-    /// - Does NOT modify stream or trigger any requests
+    /// # Errors
     ///
-    /// # Important Notes
+    /// Returns [`Error::InvalidInput`] for `Some(0)`; the current value is kept.
+    pub fn set_max_tokens(&mut self, max_tokens: Option<u32>) -> Result<()> {
+        self.options.set_max_tokens(max_tokens)
+    }
+
+    /// Changes the nucleus sampling threshold (`top_p`) used by subsequent requests.
     ///
-    /// - **Not used in auto mode**: Auto-execution handles tool results automatically
-    /// - **ID must match**: The `tool_use_id` must match the ID from the `ToolUseBlock`
-    /// - **No validation**: This method doesn't validate the result format
-    /// - **Must call send()**: After adding result(s), call `send("")` to continue
+    /// `None` leaves it to the server. Takes effect on the next `send()`.
     ///
-    /// # Examples
+    /// # Errors
     ///
-    /// ## Basic Manual Tool Execution
+    /// Returns [`Error::InvalidInput`] if the value is outside 0.0 to 1.0; the
+    /// current value is kept.
+    pub fn set_top_p(&mut self, top_p: Option<f32>) -> Result<()> {
+        self.options.set_top_p(top_p)
+    }
+
+    /// Returns the current history as the OpenAI messages the next request will carry.
     ///
-    /// ```rust,no_run
-    /// use open_agent::{Client, AgentOptions, ContentBlock};
-    /// use serde_json::json;
+    /// Runs the same conversion `send()` uses: the system prompt first, then
+    /// each message, with tool calls as assistant `tool_calls`, tool results as
+    /// `tool` messages and images as content parts. Nothing is sent and no
+    /// prompt is added, so it can be called at any point, e.g. after a tool
+    /// cycle to check the shapes the server will see.
     ///
-    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let mut client = Client::new(AgentOptions::default())?;
-    /// client.send("Use the calculator").await?;
+    /// # Errors
     ///
-    /// while let Some(block) = client.receive().await? {
-    ///     match block {
-    ///         ContentBlock::ToolUse(tool_use) => {
-    ///             // Execute tool manually
-    ///             let result = json!({"result": 42});
+    /// Returns an error if a message can't be converted, in which case
+    /// `send()` would fail the same way.
     ///
-    ///             // Add result to history
-    ///             client.add_tool_result(tool_use.id(), result)?;
+    /// # Examples
     ///
-    ///             // Continue conversation to get model's response
-    ///             client.send("").await?;
-    ///         }
-    ///         ContentBlock::Text(text) => {
-    ///             println!("{}", text.text);
-    ///         }
-    ///         ContentBlock::ToolResult(_) | ContentBlock::Image(_) => {}
-    ///     }
-    /// }
+    /// ```rust
+    /// use open_agent::{AgentOptions, Client, ContentBlock, Message, ToolResultBlock, ToolUseBlock};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
+    ///
+    /// client.append_message(Message::user("What's 2+2?"))?;
+    /// client.append_message(Message::assistant(vec![ContentBlock::ToolUse(
+    ///     ToolUseBlock::new("call_1", "add", serde_json::json!({"a": 2, "b": 2})),
+    /// )]))?;
+    /// client.append_message(Message::user_with_blocks(vec![ContentBlock::ToolResult(
+    ///     ToolResultBlock::new("call_1", serde_json::json!({"result": 4})),
+    /// )]))?;
+    ///
+    /// let messages = client.export_openai_messages()?;
+    /// assert_eq!(messages[1].tool_calls.as_ref().unwrap()[0].function.name, "add");
+    /// assert_eq!(messages[2].role, "tool");
     /// # Ok(())
     /// # }
     /// ```
+    pub fn export_openai_messages(&self) -> Result<Vec<OpenAIMessage>> {
+        history_messages(&self.options, &self.history)
+    }
+
+    /// Returns the most recent request built by `send()` or `send_message()`.
     ///
-    /// ## Handling Tool Errors
+    /// The request is recorded whether or not it was sent, so in
+    /// [dry-run mode](crate::AgentOptionsBuilder::dry_run) this is how you inspect
+    /// the exact payload, including serialized history, images, tool calls, and
+    /// tool definitions. Returns `None` before the first send.
     ///
-    /// ```rust,no_run
-    /// use open_agent::{Client, AgentOptions, ContentBlock};
-    /// use serde_json::json;
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::{Client, AgentOptions};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let mut client = Client::new(AgentOptions::default())?;
-    /// # client.send("test").await?;
-    /// while let Some(block) = client.receive().await? {
-    ///     if let ContentBlock::ToolUse(tool_use) = block {
-    ///         // Try to execute tool
-    ///         let result = match execute_tool(tool_use.name(), tool_use.input()) {
-    ///             Ok(output) => output,
-    ///             Err(e) => json!({
-    ///                 "error": e.to_string(),
-    ///                 "tool": tool_use.name()
-    ///             })
-    ///         };
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .dry_run(true)
+    ///     .build()?)?;
     ///
-    ///         client.add_tool_result(tool_use.id(), result)?;
-    ///         client.send("").await?;
-    ///     }
-    /// }
+    /// client.send("What's 2+2?").await?;
     ///
-    /// # fn execute_tool(name: &str, input: &serde_json::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    /// #     Ok(json!({}))
-    /// # }
+    /// let json = serde_json::to_string_pretty(client.last_request().unwrap())?;
+    /// assert!(json.contains("What's 2+2?"));
     /// # Ok(())
     /// # }
     /// ```
+    pub fn last_request(&self) -> Option<&OpenAIRequest> {
+        self.last_request.as_ref()
+    }
+
+    /// Returns the id, model, and creation time of the most recent response.
     ///
-    /// ## Multiple Tool Calls
+    /// Captured from the first chunk the server streams back, so it is available
+    /// as soon as `receive()` has returned anything for the current response.
+    /// Returns `None` before then, after a send in dry-run mode, and if the
+    /// request failed. With auto-execution, this is the last request of the loop.
+    ///
+    /// The reported model is whatever the server put in the response, which is
+    /// useful for spotting a gateway serving a different model than requested.
+    ///
+    /// # Examples
     ///
     /// ```rust,no_run
-    /// use open_agent::{Client, AgentOptions, ContentBlock};
-    /// use serde_json::json;
+    /// use open_agent::{Client, AgentOptions};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let mut client = Client::new(AgentOptions::default())?;
-    /// client.send("Calculate 2+2 and 3+3").await?;
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
     ///
-    /// let mut tool_calls = Vec::new();
+    /// client.send("Hello").await?;
+    /// while client.receive().await?.is_some() {}
     ///
-    /// // Collect all tool calls
-    /// while let Some(block) = client.receive().await? {
-    ///     if let ContentBlock::ToolUse(tool_use) = block {
-    ///         tool_calls.push(tool_use);
+    /// if let Some(meta) = client.last_response_meta() {
+    ///     if meta.model() != client.options().model() {
+    ///         eprintln!("requested {}, got {}", client.options().model(), meta.model());
     ///     }
     /// }
-    ///
-    /// // Execute and add results for all tools
-    /// for tool_call in tool_calls {
-    ///     let result = json!({"result": 42}); // Execute tool
-    ///     client.add_tool_result(tool_call.id(), result)?;
-    /// }
-    ///
-    /// // Continue conversation
-    /// client.send("").await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn add_tool_result(&mut self, tool_use_id: &str, content: serde_json::Value) -> Result<()> {
-        use crate::types::ToolResultBlock;
-
-        // Create a tool result block with the given ID and content
-        let result_block = ToolResultBlock::new(tool_use_id, content);
-
-        // Add to history as a tool message
-        // Note: ToolResultBlock is properly serialized in build_api_request()
-        // as a separate message with role="tool" and tool_call_id set
-        let serialized = serde_json::to_string(result_block.content())
-            .map_err(|e| Error::config(format!("Failed to serialize tool result: {}", e)))?;
-
-        self.history.push(Message::new(
-            MessageRole::Tool,
-            vec![ContentBlock::Text(TextBlock::new(serialized))],
-        ));
-
-        Ok(())
+    pub fn last_response_meta(&self) -> Option<ResponseMeta> {
+        lock_info(&self.last_response).meta.clone()
     }
 
-    /// Looks up a registered tool by name.
+    /// Returns why the last response ended, once it has been fully received.
     ///
-    /// This method provides access to the tool registry for manual execution scenarios.
-    /// It searches the tools registered in `AgentOptions` and returns a reference to
-    /// the matching tool if found.
+    /// Like [`CompletedResponse::finish_reason`] for `query_complete()`:
+    /// `Some(FinishReason::Length)` means the output was cut off by `max_tokens`.
+    /// Returns `None` while the response is still streaming, after a send in
+    /// dry-run mode, and if the server didn't report a reason.
     ///
-    /// # Parameters
+    /// # Examples
     ///
-    /// - `name`: The tool name to search for (case-sensitive)
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, FinishReason};
     ///
-    /// # Returns
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Write a long essay").await?;
+    /// while client.receive().await?.is_some() {}
     ///
-    /// - `Some(&Tool)`: Tool found
-    /// - `None`: No tool with that name
+    /// if client.last_finish_reason() == Some(FinishReason::Length) {
+    ///     println!("Output was truncated");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last_finish_reason(&self) -> Option<FinishReason> {
+        lock_info(&self.last_response).finish_reason.clone()
+    }
+
+    /// Returns the token usage of the conversation, summed over its responses.
+    ///
+    /// Counts every response since the client was created or
+    /// [`clear_history()`](Client::clear_history) was last called, including the
+    /// intermediate responses of auto-execution. Returns `None` unless the
+    /// server reported usage for every one of them. Many servers only do so
+    /// when asked, with `"stream_options": {"include_usage": true}` in
+    /// [`extra_body`](crate::AgentOptionsBuilder::extra_body).
+    pub fn total_usage(&self) -> Option<Usage> {
+        let mut tally = self.usage_tally;
+        tally.record(&lock_info(&self.last_response));
+        tally.complete_total()
+    }
+
+    /// Estimates what the conversation has cost so far, given per-token prices.
     ///
-    /// # Use Cases
+    /// Uses the usage reported by the server ([`total_usage()`](Client::total_usage))
+    /// when available. Otherwise falls back to token estimates from the history,
+    /// at the configured [`tokenizer_hint`](crate::AgentOptionsBuilder::tokenizer_hint):
+    /// each assistant message is priced as one response whose prompt was the
+    /// system prompt plus every earlier message. The fallback is
+    /// a rough approximation; in manual mode it only sees the responses you
+    /// added to history yourself.
     ///
-    /// - Manual tool execution in response to `ToolUseBlock`
-    /// - Validating tool availability before offering features
-    /// - Inspecting tool metadata (name, description, schema)
+    /// For the cost of a single turn, take the difference between calls.
     ///
     /// # Examples
     ///
-    /// ## Execute Tool Manually
+    /// ```rust,no_run
+    /// use open_agent::{AgentOptions, Client, PriceTable};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let prices = PriceTable { prompt_per_1k: 0.0005, completion_per_1k: 0.0015 };
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("gpt-4o-mini")
+    ///     .base_url("https://api.openai.com/v1")
+    ///     .extra_body(serde_json::from_value(serde_json::json!({
+    ///         "stream_options": {"include_usage": true}
+    ///     }))?)
+    ///     .build()?)?;
+    ///
+    /// let before = client.estimate_cost(&prices);
+    /// client.send("Summarize the plot of Hamlet").await?;
+    /// while client.receive().await?.is_some() {}
+    /// println!("${:.3} this turn", client.estimate_cost(&prices) - before);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn estimate_cost(&self, prices: &PriceTable) -> f64 {
+        let usage = self.total_usage().unwrap_or_else(|| {
+            estimate_history_usage(
+                self.options.system_prompt(),
+                &self.history,
+                self.options.tokenizer_hint(),
+            )
+        });
+        prices.cost(&usage)
+    }
+
+    /// Receives the current response, continuing it while it was cut off by length.
+    ///
+    /// Call after [`send()`](Client::send) in place of a `receive()` loop. The
+    /// response is drained, and while it ended with [`FinishReason::Length`]
+    /// the model is asked to continue where it stopped, up to
+    /// `max_continuations` times. Returns the text of all those responses
+    /// concatenated. Text already taken with `receive()` before the call isn't
+    /// included.
+    ///
+    /// Each continuation is an ordinary turn, and the continuation prompts stay
+    /// in history. So the model can see what it already wrote, every truncated
+    /// part is in history before the next prompt: with auto-execution the client
+    /// records responses itself, and in manual mode this method appends each
+    /// truncated part as an assistant message. As with `receive()`, the final
+    /// part is left for you to record in manual mode.
+    ///
+    /// If the cap is reached while the output is still truncated, the text so
+    /// far is returned and
+    /// [`last_finish_reason()`](Client::last_finish_reason) still reports
+    /// `Length`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from receiving or sending.
+    ///
+    /// # Examples
     ///
     /// ```rust,no_run
-    /// use open_agent::{Client, AgentOptions, ContentBlock};
+    /// use open_agent::{Client, AgentOptions};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let mut client = Client::new(AgentOptions::default())?;
-    /// # client.send("test").await?;
-    /// while let Some(block) = client.receive().await? {
-    ///     if let ContentBlock::ToolUse(tool_use) = block {
-    ///         if let Some(tool) = client.get_tool(tool_use.name()) {
-    ///             // Execute the tool
-    ///             let result = tool.execute(tool_use.input().clone()).await?;
-    ///             client.add_tool_result(tool_use.id(), result)?;
-    ///             client.send("").await?;
-    ///         } else {
-    ///             println!("Unknown tool: {}", tool_use.name());
-    ///         }
-    ///     }
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .max_tokens(512)
+    ///     .build()?)?;
+    ///
+    /// client.send("Write a detailed design document").await?;
+    /// let document = client.continue_if_truncated(4).await?;
+    /// println!("{document}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn continue_if_truncated(&mut self, max_continuations: u32) -> Result<String> {
+        let mut text = String::new();
+        let mut continuations = 0;
+        loop {
+            let mut part = String::new();
+            while let Some(chunk) = self.receive_text().await? {
+                part.push_str(&chunk);
+            }
+            text.push_str(&part);
+            if self.last_finish_reason() != Some(FinishReason::Length)
+                || continuations == max_continuations
+            {
+                return Ok(text);
+            }
+
+            // Manual mode doesn't record responses; the model needs to see this one
+            if !self.options.auto_execute_tools() {
+                self.history
+                    .push(Message::assistant(vec![ContentBlock::Text(
+                        TextBlock::new(part),
+                    )]));
+            }
+            continuations += 1;
+            self.send(CONTINUATION_PROMPT).await?;
+        }
+    }
+
+    /// Returns the text of the current response that hasn't been delivered yet.
+    ///
+    /// Text deltas are buffered until the response completes (see
+    /// [`receive()`](Client::receive)), so when a response is interrupted, or
+    /// the connection drops, the text generated so far never reaches the
+    /// caller. This returns it. Returns `None` if no text is pending: before
+    /// any text arrived, after the response completed normally, and after the
+    /// next `send()`.
+    ///
+    /// Use [`commit_partial()`](Client::commit_partial) to keep the text in
+    /// history.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Write a long story").await?;
+    /// client.interrupt_on(tokio::time::sleep(std::time::Duration::from_secs(10)));
+    ///
+    /// while client.receive().await?.is_some() {}
+    ///
+    /// if let Some(draft) = client.partial_text() {
+    ///     println!("Stopped early; got {} characters", draft.chars().count());
     /// }
     /// # Ok(())
     /// # }
     /// ```
+    pub fn partial_text(&self) -> Option<String> {
+        let info = lock_info(&self.last_response);
+        (!info.partial_text.is_empty()).then(|| info.partial_text.clone())
+    }
+
+    /// Adds the pending [`partial_text()`](Client::partial_text) to history as
+    /// an assistant message.
     ///
-    /// ## Check Tool Availability
+    /// Keeps what the model produced before a cancellation, so the next turn
+    /// can build on it (e.g. "continue the story"). The current response is
+    /// ended first, as if interrupted, so its remaining text can't be added a
+    /// second time.
+    ///
+    /// Returns `true` if a message was added, `false` if no text was pending.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Write a long story").await?;
+    ///
+    /// // ... the user cancels ...
+    /// client.interrupt();
+    /// client.commit_partial();
+    ///
+    /// client.send("Continue the story").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit_partial(&mut self) -> bool {
+        self.current_stream = None;
+        let text = std::mem::take(&mut lock_info(&self.last_response).partial_text);
+        if text.is_empty() {
+            return false;
+        }
+        self.history
+            .push(Message::assistant(vec![ContentBlock::Text(
+                TextBlock::new(text),
+            )]));
+        true
+    }
+
+    /// Returns the state of the configured circuit breaker, if any.
+    ///
+    /// The breaker may be shared with other clients, so this reflects failures
+    /// seen by all of them.
+    ///
+    /// # Examples
     ///
     /// ```rust
+    /// use open_agent::{AgentOptions, CircuitBreaker, CircuitState, Client};
+    /// use std::time::Duration;
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .circuit_breaker(CircuitBreaker::new(3, Duration::from_secs(30)))
+    ///     .build()?)?;
+    ///
+    /// assert_eq!(client.circuit_state(), Some(CircuitState::Closed));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.options
+            .circuit_breaker()
+            .map(|breaker| breaker.state())
+    }
+
+    /// Returns whether the last auto-execution cycle stopped at `max_tool_iterations`.
+    ///
+    /// `true` means the model was still asking for tools when it was cut off, so the
+    /// returned text may be incomplete (with [`IterationLimitBehavior::InjectNotice`],
+    /// the model was asked to wrap up first). `false` means the model finished on
+    /// its own. Reset at the start of each auto-execution cycle; always `false` in
+    /// manual mode.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
     /// # use open_agent::{Client, AgentOptions};
+    /// # async fn example(mut client: Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// client.send("Research and summarize").await?;
+    /// while let Some(block) = client.receive().await? {
+    ///     // ...
+    /// }
+    ///
+    /// if client.hit_iteration_limit() {
+    ///     eprintln!("warning: agent was cut off at the tool iteration limit");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hit_iteration_limit(&self) -> bool {
+        self.hit_iteration_limit
+    }
+
+    /// Clears all conversation history.
+    ///
+    /// This resets the conversation to a blank slate while preserving the client
+    /// configuration (tools, hooks, model, etc.). The next message will start a
+    /// fresh conversation with no prior context.
+    ///
+    /// # State Changes
+    ///
+    /// - Clears `history` vector
+    /// - Restarts the [`total_usage()`](Client::total_usage) count
+    /// - Does NOT modify current stream, options, or other state
+    ///
+    /// # Use Cases
+    ///
+    /// - Starting a new conversation
+    /// - Preventing context length issues
+    /// - Clearing sensitive data
+    /// - Implementing conversation sessions
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions, ContentBlock};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    ///
+    /// // First conversation
+    /// client.send("Hello").await?;
+    /// while let Some(_) = client.receive().await? {}
+    ///
+    /// // Clear and start fresh
+    /// client.clear_history();
+    ///
+    /// // New conversation with no memory of previous
+    /// client.send("Hello again").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.usage_tally = UsageTally::default();
+        lock_info(&self.last_response).excluded_from_usage = true;
+    }
+
+    /// Truncates conversation history to its first `index` messages.
+    ///
+    /// Unlike [`clear_history()`](Client::clear_history), this keeps a prefix of the
+    /// conversation, such as few-shot examples seeded before the first real turn.
+    /// If `index` is greater than or equal to the history length, nothing changes.
+    ///
+    /// # State Changes
+    ///
+    /// - Drops every message at position `index` and later
+    /// - Does NOT modify current stream, options, or other state
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions, Message};
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let client = Client::new(AgentOptions::default())?;
-    /// if client.get_tool("calculator").is_some() {
-    ///     println!("Calculator is available");
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.append_messages(vec![Message::user("seed"), Message::user("question")])?;
+    ///
+    /// client.clear_history_after(1);
+    /// assert_eq!(client.history().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear_history_after(&mut self, index: usize) {
+        self.history.truncate(index);
+    }
+
+    /// Marks the current end of the conversation history.
+    ///
+    /// The returned checkpoint is the current history length. Pass it to
+    /// [`rewind_to()`](Client::rewind_to) to discard everything added since.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions, Message};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.append_message(Message::user("Example question"))?;
+    /// let seeded = client.checkpoint();
+    ///
+    /// for question in ["What is Rust?", "What is Go?"] {
+    ///     client.send(question).await?;
+    ///     while let Some(_block) = client.receive().await? {}
+    ///
+    ///     // Answer each question independently of the previous one
+    ///     client.rewind_to(seeded);
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_tool(&self, name: &str) -> Option<&crate::tools::Tool> {
-        // Search registered tools by name
-        self.options
-            .tools()
+    pub fn checkpoint(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Rewinds conversation history to a checkpoint from [`checkpoint()`](Client::checkpoint).
+    ///
+    /// Equivalent to [`clear_history_after(checkpoint)`](Client::clear_history_after).
+    /// If history has already been shortened below the checkpoint, nothing changes.
+    pub fn rewind_to(&mut self, checkpoint: usize) {
+        self.clear_history_after(checkpoint);
+    }
+
+    /// Returns the messages added since a checkpoint from [`checkpoint()`](Client::checkpoint).
+    ///
+    /// Useful for incremental persistence: save the new messages after each
+    /// turn, then take a fresh checkpoint. If history has been shortened below
+    /// the checkpoint, the slice is empty.
+    ///
+    /// Checkpoints are positions in history, so they go stale when messages are
+    /// removed from the front, e.g. by
+    /// [`max_history_messages`](crate::AgentOptionsBuilder::max_history_messages)
+    /// or [`history_mut()`](Client::history_mut).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions, Message};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # fn append_to_log(_: &[Message]) {}
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// let mut saved = client.checkpoint();
+    ///
+    /// for question in ["What is Rust?", "What is Go?"] {
+    ///     client.send(question).await?;
+    ///     while let Some(_block) = client.receive().await? {}
+    ///
+    ///     // Persist only this turn's messages
+    ///     append_to_log(client.messages_since(saved));
+    ///     saved = client.checkpoint();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn messages_since(&self, checkpoint: usize) -> &[Message] {
+        self.history.get(checkpoint..).unwrap_or(&[])
+    }
+
+    /// Re-runs the user turns of a recorded conversation with new options.
+    ///
+    /// Each user message in `history` is sent in order on a fresh client built
+    /// from `options`, and the new responses replace the recorded ones. This is
+    /// useful for evaluation, e.g. replaying golden conversations against a new
+    /// model and comparing the transcripts.
+    ///
+    /// # What Is Replayed
+    ///
+    /// - User messages are sent as recorded, images included
+    /// - Recorded assistant messages, tool results, and the empty prompts that
+    ///   continue tool cycles are dropped, since the new model produces its own
+    /// - System messages are kept unless `options` sets a system prompt
+    ///
+    /// With `auto_execute_tools` enabled, tools the new model calls are executed
+    /// as usual. Otherwise tool calls are skipped: only the text of each response
+    /// is kept, so the transcript never has calls without results.
+    ///
+    /// # Returns
+    ///
+    /// The new transcript, in the same form as [`history()`](Client::history).
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first request or stream error; the partial transcript is lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions, Message};
+    /// # async fn example(golden: Vec<Message>) -> Result<(), Box<dyn std::error::Error>> {
+    /// let candidate = AgentOptions::builder()
+    ///     .model("qwen3-32b")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?;
+    ///
+    /// let transcript = Client::replay(&golden, &candidate).await?;
+    /// println!("{} messages", transcript.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn replay(history: &[Message], options: &AgentOptions) -> Result<Vec<Message>> {
+        let mut client = Client::new(options.clone())?;
+
+        for message in history {
+            match message.role {
+                MessageRole::System if options.system_prompt().is_empty() => {
+                    client.history.push(message.clone());
+                }
+                MessageRole::User if is_replayable_turn(message) => {
+                    client.send_message(message.clone()).await?;
+
+                    let mut blocks = Vec::new();
+                    while let Some(block) = client.receive().await? {
+                        blocks.push(block);
+                    }
+
+                    // Auto mode records its own messages; in manual mode keep the text
+                    if !options.auto_execute_tools() {
+                        blocks.retain(|block| matches!(block, ContentBlock::Text(_)));
+                        if !blocks.is_empty() {
+                            client.history.push(Message::assistant(blocks));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(std::mem::take(&mut client.history))
+    }
+
+    /// Adds a tool result to the conversation history for manual tool execution.
+    ///
+    /// This method is used exclusively in **manual mode** after receiving a `ToolUseBlock`.
+    /// The workflow is:
+    ///
+    /// 1. `receive()` returns a `ToolUseBlock`
+    /// 2. You execute the tool yourself
+    /// 3. Call `add_tool_result()` with the tool's output
+    /// 4. Call `send("")` to continue the conversation
+    /// 5. The model receives the tool result and generates a response
+    ///
+    /// # Parameters
+    ///
+    /// - `tool_use_id`: The unique ID from the `ToolUseBlock` (must match exactly)
+    /// - `content`: The tool's output as a JSON value
+    ///
+    /// # Behavior
+    ///
+    /// Creates a `ToolResultBlock` and adds it to conversation history as a tool message.
+    /// This preserves the tool call/result pairing that the model needs to understand
+    /// the conversation flow. The content goes through the configured tool result
+    /// filters first (see [`Hooks::add_content_filter`](crate::Hooks::add_content_filter)),
+    /// just as auto-executed results do.
+    ///
+    /// # State Changes
+    ///
+    /// - Appends a tool message to `history`
+    /// - Does NOT modify stream or trigger any requests
+    ///
+    /// # Important Notes
+    ///
+    /// - **Not used in auto mode**: Auto-execution handles tool results automatically
+    /// - **ID must match**: The `tool_use_id` must match the ID from the `ToolUseBlock`
+    /// - **No validation**: This method doesn't validate the result format
+    /// - **Must call send()**: After adding result(s), call `send("")` to continue
+    ///
+    /// # Examples
+    ///
+    /// ## Basic Manual Tool Execution
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock};
+    /// use serde_json::json;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Use the calculator").await?;
+    ///
+    /// while let Some(block) = client.receive().await? {
+    ///     match block {
+    ///         ContentBlock::ToolUse(tool_use) => {
+    ///             // Execute tool manually
+    ///             let result = json!({"result": 42});
+    ///
+    ///             // Add result to history
+    ///             client.add_tool_result(tool_use.id(), result)?;
+    ///
+    ///             // Continue conversation to get model's response
+    ///             client.send("").await?;
+    ///         }
+    ///         ContentBlock::Text(text) => {
+    ///             println!("{}", text.text);
+    ///         }
+    ///         ContentBlock::ToolResult(_) | ContentBlock::Image(_) => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Handling Tool Errors
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock};
+    /// use serde_json::json;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = Client::new(AgentOptions::default())?;
+    /// # client.send("test").await?;
+    /// while let Some(block) = client.receive().await? {
+    ///     if let ContentBlock::ToolUse(tool_use) = block {
+    ///         // Try to execute tool
+    ///         let result = match execute_tool(tool_use.name(), tool_use.input()) {
+    ///             Ok(output) => output,
+    ///             Err(e) => json!({
+    ///                 "error": e.to_string(),
+    ///                 "tool": tool_use.name()
+    ///             })
+    ///         };
+    ///
+    ///         client.add_tool_result(tool_use.id(), result)?;
+    ///         client.send("").await?;
+    ///     }
+    /// }
+    ///
+    /// # fn execute_tool(name: &str, input: &serde_json::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    /// #     Ok(json!({}))
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Multiple Tool Calls
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock};
+    /// use serde_json::json;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Calculate 2+2 and 3+3").await?;
+    ///
+    /// let mut tool_calls = Vec::new();
+    ///
+    /// // Collect all tool calls
+    /// while let Some(block) = client.receive().await? {
+    ///     if let ContentBlock::ToolUse(tool_use) = block {
+    ///         tool_calls.push(tool_use);
+    ///     }
+    /// }
+    ///
+    /// // Execute and add results for all tools
+    /// for tool_call in tool_calls {
+    ///     let result = json!({"result": 42}); // Execute tool
+    ///     client.add_tool_result(tool_call.id(), result)?;
+    /// }
+    ///
+    /// // Continue conversation
+    /// client.send("").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_tool_result(&mut self, tool_use_id: &str, content: serde_json::Value) -> Result<()> {
+        // Create a tool result block with the given ID and content
+        self.add_tool_result_block(ToolResultBlock::new(tool_use_id, content))
+    }
+
+    /// Adds a prepared tool result, images included, for manual tool execution.
+    ///
+    /// Like [`add_tool_result()`](Client::add_tool_result), but takes a whole
+    /// [`ToolResultBlock`], so a tool run by hand can hand the model images too
+    /// (see [`ToolResultBlock::with_image`] for how they are sent). Filters and
+    /// [`tool_result_max_len`](crate::AgentOptionsBuilder::tool_result_max_len)
+    /// apply to the JSON content; images are kept as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock, ImageBlock, ToolResultBlock};
+    /// use serde_json::json;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = Client::new(AgentOptions::default())?;
+    /// client.send("Plot last month's sales").await?;
+    /// while let Some(block) = client.receive().await? {
+    ///     if let ContentBlock::ToolUse(tool_use) = block {
+    ///         let chart = ImageBlock::from_base64("iVBORw0KGgo=", "image/png")?;
+    ///         client.add_tool_result_block(
+    ///             ToolResultBlock::new(tool_use.id(), json!({"days": 30})).with_image(chart),
+    ///         )?;
+    ///     }
+    /// }
+    /// client.send("").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_tool_result_block(&mut self, result: ToolResultBlock) -> Result<()> {
+        // Filters can skip tools by name, so find the call this result answers
+        let tool_name = self.history.iter().rev().find_map(|message| {
+            message.content.iter().find_map(|block| match block {
+                ContentBlock::ToolUse(tool_use) if tool_use.id() == result.tool_use_id() => {
+                    Some(tool_use.name().to_string())
+                }
+                _ => None,
+            })
+        });
+        let content = self
+            .options
+            .hooks()
+            .filter_tool_result(tool_name.as_deref(), result.content().clone());
+        let mut result_block = result.with_content(content);
+        if let Some(max_len) = self.options.tool_result_max_len() {
+            result_block = result_block.truncated(max_len);
+        }
+
+        // Add to history as a tool message. The block keeps the tool_use_id, so
+        // history_messages() sends it with role="tool" and tool_call_id set,
+        // and any images in a user message after it
+        self.history.push(Message::new(
+            MessageRole::Tool,
+            vec![ContentBlock::ToolResult(result_block)],
+        ));
+
+        Ok(())
+    }
+
+    /// Embeds a batch of inputs using this client's endpoint, model and HTTP client.
+    ///
+    /// Convenience wrapper around [`embed`](crate::embed) that reuses the configured
+    /// `base_url`, `api_key`, `model` and connection pool. If your server uses a
+    /// separate embedding model, call [`embed`](crate::embed) directly instead.
+    ///
+    /// All inputs go out in one request; see the [`embed`](crate::embed) docs for
+    /// batch size limits.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new(AgentOptions::builder()
+    ///     .model("nomic-embed-text")
+    ///     .base_url("http://localhost:11434/v1")
+    ///     .build()?)?;
+    ///
+    /// let vectors = client.embed(&["first chunk".to_string(), "second chunk".to_string()]).await?;
+    /// assert_eq!(vectors.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn embed(&self, input: &[String]) -> Result<Vec<Vec<f32>>> {
+        crate::embeddings::embed_with_client(
+            &self.http_client,
+            input,
+            self.options.model(),
+            self.options.base_url(),
+            self.options.api_key(),
+        )
+        .await
+    }
+
+    /// Generates images using this client's endpoint and HTTP client.
+    ///
+    /// Convenience wrapper around [`generate_image`](crate::generate_image) that
+    /// reuses the configured `base_url`, `api_key`, timeout and connection pool.
+    /// The image model is passed separately, since it is rarely the chat model.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-vl-7b-instruct")
+    ///     .base_url("http://localhost:8080/v1")
+    ///     .timeout(300)
+    ///     .build()?)?;
+    ///
+    /// let images = client
+    ///     .generate_image("A lighthouse at dusk, watercolor", "stable-diffusion-xl")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn generate_image(&self, prompt: &str, model: &str) -> Result<Vec<ImageBlock>> {
+        crate::images::generate_image_with_client(
+            &self.http_client,
+            prompt,
+            model,
+            self.options.base_url(),
+            self.options.api_key(),
+        )
+        .await
+    }
+
+    /// Counts the tokens `text` takes up for the configured model.
+    ///
+    /// When a [`tokenize_url`](crate::AgentOptionsBuilder::tokenize_url) is set
+    /// (automatically for llama.cpp and vLLM via
+    /// [`AgentOptions::for_provider`]), the server's tokenizer gives an exact count.
+    /// Without one, or if the endpoint can't be reached or answers with an error
+    /// status, this falls back to the [`estimate_tokens`](crate::estimate_tokens)
+    /// heuristic for a single user message, at the configured
+    /// [`tokenizer_hint`](crate::AgentOptionsBuilder::tokenizer_hint).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the endpoint answers successfully but the body contains
+    /// no token count, which usually means the URL points at something else.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use open_agent::{Client, AgentOptions, Provider};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::new(
+    ///     AgentOptions::for_provider(Provider::LlamaCpp)
+    ///         .model("qwen2.5-7b-instruct")
+    ///         .build()?,
+    /// )?;
+    ///
+    /// let tokens = client.count_prompt_tokens("How long is this prompt?").await?;
+    /// println!("{} tokens", tokens);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn count_prompt_tokens(&self, text: &str) -> Result<usize> {
+        let estimate =
+            || estimate_messages_with_hint(&[Message::user(text)], self.options.tokenizer_hint());
+
+        let Some(url) = self.options.tokenize_url() else {
+            return Ok(estimate());
+        };
+
+        let count = crate::tokenize::count_tokens_with_client(
+            &self.http_client,
+            url,
+            self.options.model(),
+            self.options.api_key(),
+            text,
+        )
+        .await?;
+
+        // An unavailable endpoint falls back to the estimate rather than failing
+        Ok(count.unwrap_or_else(estimate))
+    }
+
+    /// Looks up a registered tool by name.
+    ///
+    /// This method provides access to the tool registry for manual execution scenarios.
+    /// It searches the tools registered in `AgentOptions` and returns a reference to
+    /// the matching tool if found.
+    ///
+    /// # Parameters
+    ///
+    /// - `name`: The tool name to search for (case-sensitive)
+    ///
+    /// # Returns
+    ///
+    /// - `Some(&Tool)`: Tool found
+    /// - `None`: No tool with that name
+    ///
+    /// # Use Cases
+    ///
+    /// - Manual tool execution in response to `ToolUseBlock`
+    /// - Validating tool availability before offering features
+    /// - Inspecting tool metadata (name, description, schema)
+    ///
+    /// # Examples
+    ///
+    /// ## Execute Tool Manually
+    ///
+    /// ```rust,no_run
+    /// use open_agent::{Client, AgentOptions, ContentBlock};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut client = Client::new(AgentOptions::default())?;
+    /// # client.send("test").await?;
+    /// while let Some(block) = client.receive().await? {
+    ///     if let ContentBlock::ToolUse(tool_use) = block {
+    ///         if let Some(tool) = client.get_tool(tool_use.name()) {
+    ///             // Execute the tool
+    ///             let result = tool.execute(tool_use.input().clone()).await?;
+    ///             client.add_tool_result(tool_use.id(), result)?;
+    ///             client.send("").await?;
+    ///         } else {
+    ///             println!("Unknown tool: {}", tool_use.name());
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Check Tool Availability
+    ///
+    /// ```rust
+    /// # use open_agent::{Client, AgentOptions};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::new(AgentOptions::default())?;
+    /// if client.get_tool("calculator").is_some() {
+    ///     println!("Calculator is available");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_tool(&self, name: &str) -> Option<&crate::tools::Tool> {
+        // Search registered tools by name
+        self.options
+            .tools()
+            .iter()
+            .find(|t| t.name() == name)
+            .map(|t| t.as_ref())
+    }
+
+    /// Enables or disables a registered tool for this conversation.
+    ///
+    /// A disabled tool is left out of the tool list sent with each request, so
+    /// the model doesn't see it, and auto-execution refuses to run it. The
+    /// change takes effect from the next request and only affects this client:
+    /// other clients built from the same options, or from clones of the tool,
+    /// keep their own setting. Until this is called, a tool follows its
+    /// [`ToolBuilder::enabled`](crate::ToolBuilder::enabled) value, including
+    /// tools registered later. Unregistering a tool forgets the setting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if no tool with that name is registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use open_agent::{tool, AgentOptions, Client};
+    /// # use serde_json::json;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let delete = tool("delete_file", "Delete a file")
+    ///     .param("path", "string")
+    ///     .build(|_| async { Ok(json!({})) });
+    ///
+    /// let options = AgentOptions::builder()
+    ///     .model("qwen2.5-7b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .tool(delete)
+    ///     .build()?;
+    /// let mut client = Client::new(options.clone())?;
+    ///
+    /// // Hide the tool for this conversation only
+    /// client.set_tool_enabled("delete_file", false)?;
+    /// assert!(!client.is_tool_enabled("delete_file"));
+    /// assert!(Client::new(options)?.is_tool_enabled("delete_file"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_tool_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        if self.get_tool(name).is_none() {
+            return Err(Error::invalid_input(format!(
+                "set_tool_enabled: no tool named '{}' is registered",
+                name
+            )));
+        }
+        self.tool_overrides.insert(name.to_string(), enabled);
+        Ok(())
+    }
+
+    /// Returns whether a registered tool is enabled for this conversation.
+    ///
+    /// The tool's own [`is_enabled()`](crate::Tool::is_enabled) unless
+    /// `set_tool_enabled()` overrode it; `false` for names that aren't registered.
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        self.get_tool(name).is_some_and(|tool| {
+            self.tool_overrides
+                .get(name)
+                .copied()
+                .unwrap_or_else(|| tool.is_enabled())
+        })
+    }
+
+    /// Registers a tool after the client was created.
+    ///
+    /// The tool is offered to the model from the next `send()` on, and is available
+    /// to auto-execution and [`get_tool()`](Client::get_tool). A response already
+    /// streaming was requested with the old tool list.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Tool`] if a tool with the same name is already registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::{Client, AgentOptions, tool};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
+    ///
+    /// // A plugin enabled mid-session
+    /// let clock = tool("current_time", "Get the current time")
+    ///     .build(|_| async move { Ok(serde_json::json!({"time": "12:00"})) });
+    /// client.register_tool(clock)?;
+    /// assert!(client.get_tool("current_time").is_some());
+    ///
+    /// assert!(client.unregister_tool("current_time"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_tool(&mut self, tool: crate::tools::Tool) -> Result<()> {
+        self.options.add_tool(tool)
+    }
+
+    /// Removes a registered tool by name.
+    ///
+    /// Returns `true` if a tool was removed, `false` if none had that name. The
+    /// tool is no longer offered from the next `send()` on. If the model still
+    /// calls it (e.g. based on earlier history), auto-execution reports a "not found"
+    /// error to the model as the tool result.
+    pub fn unregister_tool(&mut self, name: &str) -> bool {
+        self.tool_overrides.remove(name);
+        self.options.remove_tool(name)
+    }
+
+    /// Replaces the lifecycle hooks for subsequent requests.
+    ///
+    /// Hooks are normally fixed by [`AgentOptionsBuilder::hooks`](crate::AgentOptionsBuilder::hooks);
+    /// this swaps them mid-session, e.g. to turn on audit or security hooks for a
+    /// flagged session only. [`options()`](Client::options) reflects the new set.
+    ///
+    /// A response already streaming is unaffected: its blocks still pass through
+    /// the PostReceive hooks it was requested with. Every other hook fires with
+    /// the new set from the next time it runs, including the tool hooks of an
+    /// auto-execution turn that is still in progress.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::{AgentOptions, Client, Hooks};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::new(AgentOptions::builder()
+    ///     .model("qwen2.5-32b-instruct")
+    ///     .base_url("http://localhost:1234/v1")
+    ///     .build()?)?;
+    ///
+    /// let session_flagged = true;
+    /// if session_flagged {
+    ///     client.set_hooks(Hooks::new().add_pre_tool_use(|event| async move {
+    ///         println!("audit: {} {}", event.tool_name, event.tool_input);
+    ///         None
+    ///     }));
+    /// }
+    /// assert_eq!(client.options().hooks().pre_tool_use.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_hooks(&mut self, hooks: Hooks) {
+        self.options.set_hooks(hooks);
+    }
+}
+
+/// Cancels an in-flight response when the client is dropped.
+///
+/// Dropping the stream closes its HTTP connection, including a read-ahead task
+/// started by [`stream_buffer_size`](crate::AgentOptionsBuilder::stream_buffer_size).
+/// The interrupt flag is set as well, so anything still holding the
+/// [`interrupt_handle()`](Client::interrupt_handle) sees the response as
+/// cancelled. This makes it safe to drop a client mid-response, e.g. when a
+/// web framework cancels the handler that owns it.
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.current_stream.take().is_some() {
+            self.interrupted.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Checks `model` is one of the ids a server listed.
+fn check_model_listed(model: &str, available: &[String]) -> Result<()> {
+    if available.iter().any(|id| id == model) {
+        return Ok(());
+    }
+    Err(Error::config(format!(
+        "Model '{}' is not available on the server (available: {})",
+        model,
+        available.join(", ")
+    )))
+}
+
+/// Converts a non-success HTTP response into an [`Error`].
+///
+/// HTTP 429 becomes [`Error::RateLimited`] carrying the response body and any
+/// rate-limit headers the server sent. Bodies reporting a missing or unloaded model become
+/// [`Error::ModelNotFound`] or [`Error::ModelNotLoaded`]; every other status
+/// becomes [`Error::Api`] with the response body.
+pub(crate) async fn error_from_response(response: reqwest::Response) -> Error {
+    let status = response.status();
+
+    // Headers must be read before the body consumes the response
+    let rate_limit = (status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+        .then(|| parse_rate_limit_headers(response.headers()));
+
+    let body = response.text().await.unwrap_or_else(|e| {
+        eprintln!("WARNING: Failed to read error response body: {}", e);
+        "Unknown error (failed to read response body)".to_string()
+    });
+    if let Some((retry_after, remaining)) = rate_limit {
+        return Error::rate_limited(body, retry_after, remaining);
+    }
+    if let Some(err) = Error::from_model_error_body(&body) {
+        return err;
+    }
+    Error::api(format!("API error {}: {}", status, body))
+}
+
+/// Extracts `retry-after` and remaining-request counts from rate-limit headers.
+///
+/// `retry-after` is accepted as (possibly fractional) seconds; the HTTP-date form
+/// and values too large for a `Duration` are ignored. Remaining requests are read from `x-ratelimit-remaining`, falling
+/// back to OpenAI's `x-ratelimit-remaining-requests`.
+fn parse_rate_limit_headers(
+    headers: &reqwest::header::HeaderMap,
+) -> (Option<Duration>, Option<u32>) {
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let retry_after = header_str("retry-after")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+
+    let remaining = header_str("x-ratelimit-remaining")
+        .or_else(|| header_str("x-ratelimit-remaining-requests"))
+        .and_then(|v| v.trim().parse::<u32>().ok());
+
+    (retry_after, remaining)
+}
+
+/// Checks that `message` can be appended to `history` without breaking the
+/// tool call/result pairing the API expects.
+fn validate_appended_message(history: &[Message], message: &Message) -> Result<()> {
+    let has_tool_use = message
+        .content
+        .iter()
+        .any(|block| matches!(block, ContentBlock::ToolUse(_)));
+    if has_tool_use && message.role != MessageRole::Assistant {
+        return Err(Error::invalid_input(
+            "Tool use blocks can only appear in assistant messages",
+        ));
+    }
+
+    // Every assistant message's tool calls need results before the conversation
+    // moves on; only the most recent one may still be waiting for them
+    let pending = pending_tool_calls(history)?;
+    let is_answer = message
+        .content
+        .iter()
+        .any(|block| matches!(block, ContentBlock::ToolResult(_)));
+    if let Some(id) = pending.first().filter(|_| !is_answer) {
+        return Err(Error::invalid_input(format!(
+            "Tool call '{}' has no result; add the tool results before the next message",
+            id
+        )));
+    }
+
+    // Tool results must answer a tool call from the most recent assistant turn
+    for block in &message.content {
+        if let ContentBlock::ToolResult(result) = block {
+            let matched = history
+                .iter()
+                .rev()
+                .find(|msg| msg.role == MessageRole::Assistant)
+                .is_some_and(|assistant| {
+                    assistant.content.iter().any(|b| {
+                        matches!(b, ContentBlock::ToolUse(tool_use) if tool_use.id() == result.tool_use_id())
+                    })
+                });
+
+            if !matched {
+                return Err(Error::invalid_input(format!(
+                    "Tool result '{}' does not match a tool use in the preceding assistant message",
+                    result.tool_use_id()
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the ids of the most recent assistant message's unanswered tool calls.
+///
+/// Fails if an earlier assistant message has a tool call without a result
+/// before the next non-result message.
+fn pending_tool_calls(history: &[Message]) -> Result<Vec<&str>> {
+    let mut pending: Vec<&str> = Vec::new();
+    for message in history {
+        let mut answered = false;
+        for block in &message.content {
+            if let ContentBlock::ToolResult(result) = block {
+                pending.retain(|id| *id != result.tool_use_id());
+                answered = true;
+            }
+        }
+        if answered {
+            continue;
+        }
+
+        if let Some(id) = pending.first() {
+            return Err(Error::invalid_input(format!(
+                "Tool call '{}' in history has no result",
+                id
+            )));
+        }
+        if message.role == MessageRole::Assistant {
+            pending = message
+                .content
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::ToolUse(tool_use) => Some(tool_use.id()),
+                    _ => None,
+                })
+                .collect();
+        }
+    }
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_creation() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let client = Client::new(options).expect("Should create client successfully");
+        assert_eq!(client.history().len(), 0);
+    }
+
+    #[test]
+    fn test_client_new_returns_result() {
+        // Test that Client::new() returns Result instead of panicking
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        // This should not panic - it should return Ok(client)
+        let result = Client::new(options);
+        assert!(result.is_ok(), "Client::new() should return Ok");
+
+        let client = result.unwrap();
+        assert_eq!(client.history().len(), 0);
+    }
+
+    #[test]
+    fn test_interrupt_flag_initial_state() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let client = Client::new(options).expect("Should create client successfully");
+        // Initially not interrupted
+        assert!(!client.interrupted.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_interrupt_sets_flag() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let client = Client::new(options).expect("Should create client successfully");
+        client.interrupt();
+        assert!(client.interrupted.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_interrupt_idempotent() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let client = Client::new(options).expect("Should create client successfully");
+        client.interrupt();
+        assert!(client.interrupted.load(Ordering::SeqCst));
+
+        // Call again - should still be interrupted
+        client.interrupt();
+        assert!(client.interrupted.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_receive_returns_none_when_interrupted() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).expect("Should create client successfully");
+
+        // Interrupt before receiving
+        client.interrupt();
+
+        // NEW SIGNATURE: receive() should return Ok(None) when interrupted
+        let result = client.receive().await;
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_typed_response() {
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Classification {
+            label: String,
+            confidence: f32,
+        }
+
+        let expected = Classification {
+            label: "refund".to_string(),
+            confidence: 0.5,
+        };
+
+        let plain: Classification =
+            parse_typed_response(r#" {"label": "refund", "confidence": 0.5} "#).unwrap();
+        assert_eq!(plain, expected);
+
+        let fenced: Classification =
+            parse_typed_response("```json\n{\"label\": \"refund\", \"confidence\": 0.5}\n```")
+                .unwrap();
+        assert_eq!(fenced, expected);
+
+        let err = parse_typed_response::<Classification>(r#"{"label": "refund"}"#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("Classification"));
+        assert!(msg.contains("missing field `confidence`"));
+    }
+
+    #[tokio::test]
+    async fn test_buffer_stream_bounds_read_ahead() {
+        use std::sync::atomic::AtomicUsize;
+
+        // Count how many blocks the producer has been asked for
+        let produced = Arc::new(AtomicUsize::new(0));
+        let counter = produced.clone();
+        let source: ContentStream = Box::pin(futures::stream::iter(0..100).map(move |i| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(ContentBlock::Text(TextBlock::new(i.to_string())))
+        }));
+
+        let mut buffered = buffer_stream(source, Some(4));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Capacity 4 plus the one item held by the blocked send()
+        assert!(produced.load(Ordering::SeqCst) <= 5);
+
+        let mut received = 0;
+        while let Some(block) = buffered.next().await {
+            block.unwrap();
+            received += 1;
+        }
+        assert_eq!(received, 100);
+    }
+
+    #[tokio::test]
+    async fn test_post_receive_hook_replaces_and_drops_blocks() {
+        use crate::hooks::{HookDecision, Hooks};
+
+        let hooks = Hooks::new().add_post_receive(|event| async move {
+            match &event.block {
+                ContentBlock::Text(text) if text.text.contains("sk-") => {
+                    Some(HookDecision::block("secret"))
+                }
+                ContentBlock::Text(text) => Some(HookDecision::modify_block(
+                    ContentBlock::Text(TextBlock::new(text.text.to_uppercase())),
+                    "shout",
+                )),
+                _ => None,
+            }
+        });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .hooks(hooks)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        let blocks =
+            ["hello", "key: sk-123", "world"].map(|t| Ok(ContentBlock::Text(TextBlock::new(t))));
+        client.current_stream = Some(Box::pin(
+            futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+        ));
+
+        let mut seen = Vec::new();
+        while let Some(block) = client.receive().await.unwrap() {
+            if let ContentBlock::Text(text) = block {
+                seen.push(text.text);
+            }
+        }
+        assert_eq!(seen, vec!["HELLO", "WORLD"]);
+    }
+
+    #[tokio::test]
+    async fn test_receive_event_surfaces_tool_argument_fragments() {
+        use crate::types::ToolUseBlock;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+        let events = || {
+            futures::stream::iter(vec![
+                Ok(StreamEvent::ToolArgsDelta {
+                    index: 0,
+                    fragment: r#"{"q":"#.to_string(),
+                }),
+                Ok(StreamEvent::ToolArgsDelta {
+                    index: 0,
+                    fragment: r#""rust"}"#.to_string(),
+                }),
+                Ok(StreamEvent::Block(ContentBlock::ToolUse(
+                    ToolUseBlock::new("call_1", "search", serde_json::json!({"q": "rust"})),
+                ))),
+            ])
+        };
+
+        let mut client = Client::new(options.clone()).unwrap();
+        client.current_stream = Some(Box::pin(events()));
+        let mut fragments = String::new();
+        let mut tool_name = None;
+        while let Some(event) = client.receive_event().await.unwrap() {
+            match event {
+                StreamEvent::ToolArgsDelta { fragment, .. } => fragments.push_str(&fragment),
+                StreamEvent::Block(ContentBlock::ToolUse(tool)) => {
+                    tool_name = Some(tool.name().to_string())
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(fragments, r#"{"q":"rust"}"#);
+        assert_eq!(tool_name.as_deref(), Some("search"));
+
+        // receive() skips the fragments and only yields the completed call
+        let mut client = Client::new(options).unwrap();
+        client.current_stream = Some(Box::pin(events()));
+        assert!(matches!(
+            client.receive().await.unwrap(),
+            Some(ContentBlock::ToolUse(_))
+        ));
+        assert!(client.receive().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_records_request_without_sending() {
+        // Unroutable base URL: any real request would fail
+        let options = AgentOptions::builder()
+            .system_prompt("Be brief")
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options.clone()).unwrap();
+        assert!(client.last_request().is_none());
+
+        client.send("Hello").await.unwrap();
+        assert!(client.receive().await.unwrap().is_none());
+
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert_eq!(request["model"], "test-model");
+        assert_eq!(request["messages"][0]["role"], "system");
+        assert_eq!(request["messages"][1]["content"], "Hello");
+
+        // query() yields the request JSON as a single text block
+        let mut stream = query("Hi", &options).await.unwrap();
+        let Some(Ok(ContentBlock::Text(text))) = stream.next().await else {
+            panic!("expected a text block");
+        };
+        let request: serde_json::Value = serde_json::from_str(&text.text).unwrap();
+        assert_eq!(request["messages"][1]["content"], "Hi");
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_normalize_roles_merges_request_messages_only() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .normalize_roles(true)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        client.append_message(Message::user("Context")).unwrap();
+        client.send("Question").await.unwrap();
+
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert_eq!(request["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(request["messages"][0]["content"], "Context\nQuestion");
+        // History keeps the messages as they were added
+        assert_eq!(client.history().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_runtime_sampling_setters() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        client.set_temperature(0.2).unwrap();
+        client.set_max_tokens(None).unwrap();
+        client.set_top_p(Some(0.9)).unwrap();
+
+        // Invalid values are rejected and leave the previous settings in place
+        assert!(client.set_temperature(2.5).is_err());
+        assert!(client.set_max_tokens(Some(0)).is_err());
+        assert!(client.set_top_p(Some(1.5)).is_err());
+
+        client.send("Hello").await.unwrap();
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert!((request["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!((request["top_p"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert!(request.get("max_tokens").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_and_unregister_tool() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        let search = || {
+            crate::tools::tool("search", "Search the web")
+                .param("query", "string")
+                .build(|_| async move { Ok(serde_json::json!({})) })
+        };
+
+        client.register_tool(search()).unwrap();
+        assert!(matches!(
+            client.register_tool(search()),
+            Err(Error::Tool(_))
+        ));
+        assert_eq!(client.options().tools().len(), 1);
+
+        client.send("Find rust docs").await.unwrap();
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert_eq!(request["tools"][0]["function"]["name"], "search");
+
+        assert!(client.unregister_tool("search"));
+        assert!(!client.unregister_tool("search"));
+        client.send("Again").await.unwrap();
+        assert!(client.last_request().unwrap().tools.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_count_prompt_tokens_falls_back_to_estimate() {
+        // Nothing listens on port 1, so the tokenize endpoint is unavailable
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .tokenize_url("http://127.0.0.1:1/tokenize")
+            .build()
+            .unwrap();
+        let client = Client::new(options).unwrap();
+
+        let text = "How many tokens is this?";
+        let expected = crate::estimate_tokens(&[Message::user(text)]);
+        assert_eq!(client.count_prompt_tokens(text).await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast() {
+        use crate::circuit_breaker::CircuitBreaker;
+
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        // Nothing listens on port 1, so the first request fails to connect
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .circuit_breaker(breaker.clone())
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        assert!(matches!(client.send("Hi").await, Err(Error::Http(_))));
+        assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+
+        // Subsequent requests short-circuit without touching the network
+        assert!(matches!(
+            client.send("Hi again").await,
+            Err(Error::CircuitOpen { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_iteration_limit_behaviors() {
+        use crate::types::{IterationLimitBehavior, ToolUseBlock};
+
+        let client_with = |behavior| {
+            let options = AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://127.0.0.1:1/v1")
+                .auto_execute_tools(true)
+                .max_tool_iterations(0)
+                .on_iteration_limit(behavior)
+                .dry_run(true) // The follow-up request after a notice isn't sent
+                .build()
+                .unwrap();
+            let mut client = Client::new(options).unwrap();
+            let blocks = vec![
+                Ok(ContentBlock::Text(TextBlock::new("Looking it up"))),
+                Ok(ContentBlock::ToolUse(ToolUseBlock::new(
+                    "call_1",
+                    "search",
+                    serde_json::json!({}),
+                ))),
+            ];
+            client.current_stream = Some(Box::pin(
+                futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+            ));
+            client
+        };
+
+        let mut client = client_with(IterationLimitBehavior::ReturnPartial);
+        let Some(ContentBlock::Text(text)) = client.receive().await.unwrap() else {
+            panic!("expected partial text");
+        };
+        assert_eq!(text.text, "Looking it up");
+        assert!(client.hit_iteration_limit());
+
+        let mut client = client_with(IterationLimitBehavior::Error);
+        assert!(matches!(client.receive().await, Err(Error::Tool(_))));
+        assert!(client.hit_iteration_limit());
+
+        let mut client = client_with(IterationLimitBehavior::InjectNotice);
+        assert!(client.receive().await.unwrap().is_none());
+        assert!(client.hit_iteration_limit());
+        let notice = client.history().last().unwrap();
+        assert_eq!(notice.role, MessageRole::User);
+        assert!(client.last_request().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_on_cancels_pending_stream() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).unwrap();
+
+        // A stream that never yields: only the interrupt future can end receive()
+        client.current_stream = Some(Box::pin(futures::stream::pending()));
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        client.interrupt_on(rx);
+
+        tx.send(()).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(1), client.receive())
+            .await
+            .expect("receive() should not hang once the interrupt future fires");
+
+        assert!(result.unwrap().is_none());
+        assert!(client.current_stream.is_none());
+        assert!(client.interrupt_signal.is_none());
+        assert!(client.interrupted.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_receive_returns_ok_none_when_no_stream() {
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).expect("Should create client successfully");
+
+        // No stream started - receive() should return Ok(None)
+        let result = client.receive().await;
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receive_error_propagation() {
+        // This test demonstrates that errors are wrapped in Err(), not Some(Err())
+        // We'll verify this behavior when we have a mock stream that produces errors
+        let options = AgentOptions::builder()
+            .system_prompt("Test")
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+
+        let client = Client::new(options).expect("Should create client successfully");
+
+        // Signature check: receive() returns Result<Option<ContentBlock>>
+        // This means we can use ? operator cleanly:
+        // while let Some(block) = client.receive().await? { ... }
+
+        // Type assertion to ensure signature is correct
+        let _: Result<Option<ContentBlock>> = std::future::ready(Ok(None)).await;
+        drop(client);
+    }
+
+    #[test]
+    fn test_append_message_validates_tool_pairing() {
+        use crate::types::{ToolResultBlock, ToolUseBlock};
+
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+
+        // Tool result without a preceding tool use is rejected
+        let orphan = Message::user_with_blocks(vec![ContentBlock::ToolResult(
+            ToolResultBlock::new("call_1", serde_json::json!({"ok": true})),
+        )]);
+        assert!(client.append_message(orphan.clone()).is_err());
+        assert!(client.history().is_empty());
+
+        // Tool use outside an assistant message is rejected
+        let misplaced = Message::user_with_blocks(vec![ContentBlock::ToolUse(ToolUseBlock::new(
+            "call_1",
+            "search",
+            serde_json::json!({}),
+        ))]);
+        assert!(client.append_message(misplaced).is_err());
+
+        // Matching pair is accepted
+        client
+            .append_message(Message::user("Search for rust"))
+            .unwrap();
+        client
+            .append_message(Message::assistant(vec![ContentBlock::ToolUse(
+                ToolUseBlock::new("call_1", "search", serde_json::json!({"q": "rust"})),
+            )]))
+            .unwrap();
+        client.append_message(orphan).unwrap();
+        assert_eq!(client.history().len(), 3);
+    }
+
+    #[test]
+    fn test_append_message_requires_every_tool_call_answered() {
+        use crate::types::{ToolResultBlock, ToolUseBlock};
+
+        let call = |id: &str| {
+            ContentBlock::ToolUse(ToolUseBlock::new(id, "search", serde_json::json!({})))
+        };
+        let result = |id: &str| {
+            Message::user_with_blocks(vec![ContentBlock::ToolResult(ToolResultBlock::new(
+                id,
+                serde_json::json!({"ok": true}),
+            ))])
+        };
+
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client
+            .append_messages(vec![
+                Message::user("Search twice"),
+                Message::assistant(vec![call("call_1"), call("call_2")]),
+                result("call_1"),
+            ])
+            .unwrap();
+
+        // call_2 is still waiting for its result
+        assert!(client.append_message(Message::user("Next")).is_err());
+        client.append_message(result("call_2")).unwrap();
+        client.append_message(Message::user("Next")).unwrap();
+
+        // An unanswered call earlier in history is caught too, not just in the
+        // latest assistant message
+        client
+            .history_mut()
+            .insert(1, Message::assistant(vec![call("call_0")]));
+        let err = client.append_message(Message::user("Again")).unwrap_err();
+        assert!(err.to_string().contains("call_0"));
+        assert_eq!(client.history().len(), 6);
+    }
+
+    #[test]
+    fn test_append_messages_is_all_or_nothing() {
+        use crate::types::ToolResultBlock;
+
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+
+        let result = client.append_messages(vec![
+            Message::user("Q1"),
+            Message::assistant(vec![ContentBlock::Text(TextBlock::new("A1"))]),
+            Message::user_with_blocks(vec![ContentBlock::ToolResult(ToolResultBlock::new(
+                "missing",
+                serde_json::json!(null),
+            ))]),
+        ]);
+        assert!(result.is_err());
+        assert!(client.history().is_empty());
+
+        client
+            .append_messages(vec![
+                Message::user("Q1"),
+                Message::assistant(vec![ContentBlock::Text(TextBlock::new("A1"))]),
+            ])
+            .unwrap();
+        assert_eq!(client.history().len(), 2);
+    }
+
+    #[test]
+    fn test_client_with_http_client() {
+        let http = reqwest::Client::builder().build().unwrap();
+        let client = Client::with_http_client(AgentOptions::default(), http)
+            .expect("Should create client with a custom HTTP client");
+        assert_eq!(client.history().len(), 0);
+    }
+
+    #[test]
+    fn test_checkpoint_and_rewind() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client
+            .append_messages(vec![Message::user("seed 1"), Message::user("seed 2")])
+            .unwrap();
+
+        let seeded = client.checkpoint();
+        assert_eq!(seeded, 2);
+
+        client.append_message(Message::user("question")).unwrap();
+        client.rewind_to(seeded);
+        assert_eq!(client.history().len(), 2);
+
+        // Rewinding past the end is a no-op
+        client.rewind_to(10);
+        assert_eq!(client.history().len(), 2);
+
+        client.clear_history_after(1);
+        assert_eq!(client.history().len(), 1);
+        assert!(matches!(
+            &client.history()[0].content[0],
+            ContentBlock::Text(t) if t.text == "seed 1"
+        ));
+    }
+
+    #[test]
+    fn test_parse_rate_limit_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("2.5"));
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("0"),
+        );
+        assert_eq!(
+            parse_rate_limit_headers(&headers),
+            (Some(Duration::from_millis(2500)), Some(0))
+        );
+
+        // The generic header takes precedence over the OpenAI-specific one
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("7"));
+        assert_eq!(parse_rate_limit_headers(&headers).1, Some(7));
+
+        // HTTP-date and garbage values are ignored rather than rejected
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("lots"));
+        assert_eq!(parse_rate_limit_headers(&headers), (None, None));
+
+        // Out-of-range delays are ignored instead of panicking
+        for value in ["1e30", "-1", "inf", "NaN"] {
+            let mut headers = HeaderMap::new();
+            headers.insert("retry-after", HeaderValue::from_static(value));
+            assert_eq!(parse_rate_limit_headers(&headers), (None, None));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_error_keeps_body() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![MockResponse::error(
+            429,
+            "Requests per minute exceeded",
+        )])
+        .await
+        .unwrap();
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        match query_complete("Hi", &options).await {
+            Err(Error::RateLimited { message, .. }) => {
+                assert!(message.contains("Requests per minute exceeded"));
+            }
+            other => panic!("unexpected: {:?}", other.map(|r| r.text)),
+        }
+    }
+
+    #[test]
+    fn test_empty_content_parts_protection() {
+        // Test for Issue #3 - Verify empty content_parts causes appropriate handling
+        // This documents expected behavior: messages with images should have content
+
+        use crate::types::{ContentBlock, ImageBlock, Message, MessageRole};
+
+        // GIVEN: Message with an image
+        let img = ImageBlock::from_url("https://example.com/test.jpg").expect("Valid URL");
+
+        let msg = Message::new(MessageRole::User, vec![ContentBlock::Image(img)]);
+
+        // WHEN: Building content_parts
+        let mut content_parts = Vec::new();
+        for block in &msg.content {
+            match block {
+                ContentBlock::Text(text) => {
+                    content_parts.push(crate::types::OpenAIContentPart::text(&text.text));
+                }
+                ContentBlock::Image(image) => {
+                    content_parts.push(crate::types::OpenAIContentPart::from_image(image));
+                }
+                ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => {}
+            }
+        }
+
+        // THEN: content_parts should not be empty
+        assert!(
+            !content_parts.is_empty(),
+            "Messages with images should produce non-empty content_parts"
+        );
+    }
+
+    /// SSE event carrying a text delta, tagged with `id`
+    fn sse_text_event(id: &str, text: &str, finish_reason: &str) -> String {
+        format!(
+            "id: {}\ndata: {{\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":0,\
+             \"model\":\"m\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"{}\"}},\
+             \"finish_reason\":{}}}]}}\n\n",
+            id, text, finish_reason
+        )
+    }
+
+    /// Serves two chat completions on a local port.
+    ///
+    /// The first response sends `first` as one chunk and then drops the connection
+    /// mid-body; the second sends `second` in full. Returns the base URL and a
+    /// receiver for the raw request heads.
+    async fn serve_dropped_then_resumed(
+        first: String,
+        second: String,
+    ) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        serve_sse(vec![(first, false), (second, true)]).await
+    }
+
+    /// Serves one SSE response per connection on a local port.
+    ///
+    /// Each body is sent as a single chunk; when its flag is false the connection
+    /// is then dropped mid-body. Returns the base URL and a receiver for the raw
+    /// request heads, lowercased.
+    async fn serve_sse(
+        responses: Vec<(String, bool)>,
+    ) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            for (body, complete) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+
+                // Read the head and the JSON body
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request).to_lowercase();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .map_or(0, |v| v.trim().parse::<usize>().unwrap());
+                        if request.len() >= end + 4 + length || n == 0 {
+                            let _ = tx.send(text[..end].to_string());
+                            break;
+                        }
+                    }
+                }
+
+                let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                            Transfer-Encoding: chunked\r\n\r\n";
+                let chunk = format!("{:x}\r\n{}\r\n", body.len(), body);
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(chunk.as_bytes()).await.unwrap();
+                if complete {
+                    socket.write_all(b"0\r\n\r\n").await.unwrap();
+                }
+                // Dropping the socket without the final chunk aborts the body
+            }
+        });
+
+        (format!("http://{}/v1", addr), rx)
+    }
+
+    #[tokio::test]
+    async fn test_resume_streams_reconnects_with_last_event_id() {
+        let first = sse_text_event("1", "Hello", "null");
+        let second = format!(
+            "{}data: [DONE]\n\n",
+            sse_text_event("2", " world", "\"stop\"")
+        );
+        let (base_url, mut requests) = serve_dropped_then_resumed(first, second).await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .resume_streams(true)
+            .build()
+            .unwrap();
+
+        let mut stream = query("Hi", &options).await.unwrap();
+        let mut text = String::new();
+        while let Some(block) = stream.next().await {
+            if let ContentBlock::Text(t) = block.unwrap() {
+                text.push_str(&t.text);
+            }
+        }
+        assert_eq!(text, "Hello world");
+
+        let initial = requests.recv().await.unwrap();
+        assert!(!initial.contains("last-event-id"));
+        let resumed = requests.recv().await.unwrap();
+        assert!(resumed.contains("last-event-id: 1"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_streams_fails_when_server_replays() {
+        // The server ignores Last-Event-ID and starts over
+        let first = sse_text_event("1", "Hello", "null");
+        let second = format!(
+            "{}data: [DONE]\n\n",
+            sse_text_event("1", "Hello", "\"stop\"")
+        );
+        let (base_url, _requests) = serve_dropped_then_resumed(first, second).await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .resume_streams(true)
+            .build()
+            .unwrap();
+
+        let results: Vec<_> = query("Hi", &options).await.unwrap().collect().await;
+        assert!(
+            results.iter().any(|r| matches!(r, Err(Error::Stream(_)))),
+            "expected a stream error, got {:?}",
+            results
+        );
+        // The replayed text never reached the caller
+        assert!(
+            !results
+                .iter()
+                .any(|r| matches!(r, Ok(ContentBlock::Text(_))))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_last_response_meta_from_first_chunk() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("alias")
+                .base_url("http://localhost:1234/v1")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(client.last_response_meta().is_none());
+
+        let chunk = |model: &str, finish: &str| -> Result<OpenAIChunk> {
+            Ok(serde_json::from_str(&format!(
+                r#"{{"id":"chatcmpl-7","object":"chat.completion.chunk","created":1700000000,
+                    "model":"{}","choices":[{{"index":0,"delta":{{"content":"hi"}},
+                    "finish_reason":{}}}]}}"#,
+                model, finish
+            ))
+            .unwrap())
+        };
+        let chunks: ChunkStream = Box::pin(futures::stream::iter(vec![
+            chunk("qwen2.5-7b", "null"),
+            chunk("other", r#""stop""#),
+        ]));
+        client.current_stream = Some(event_stream(record_response_info(
+            chunks,
+            Arc::clone(&client.last_response),
+        )));
+
+        while client.receive().await.unwrap().is_some() {}
+
+        let meta = client.last_response_meta().unwrap();
+        assert_eq!(meta.id(), "chatcmpl-7");
+        assert_eq!(meta.model(), "qwen2.5-7b");
+        assert_eq!(meta.created(), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_check_model_listed() {
+        let available = vec!["qwen2.5-32b-instruct".to_string(), "llama3.1".to_string()];
+        assert!(check_model_listed("llama3.1", &available).is_ok());
+
+        let err = check_model_listed("qwen2.5-32b", &available).unwrap_err();
+        assert!(matches!(err, Error::Config(_)));
+        assert!(err.to_string().contains("qwen2.5-32b-instruct, llama3.1"));
+    }
+
+    #[tokio::test]
+    async fn test_new_validated_propagates_network_errors() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .build()
+            .unwrap();
+        assert!(matches!(
+            Client::new_validated(options).await,
+            Err(Error::Http(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_max_history_messages_evicts_oldest_turns() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .max_history_messages(4)
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        client.history_mut().push(Message::system("Be brief"));
+
+        for i in 0..3 {
+            client.send(&format!("question {}", i)).await.unwrap();
+            client
+                .history_mut()
+                .push(Message::assistant(vec![ContentBlock::Text(
+                    TextBlock::new(format!("answer {}", i)),
+                )]));
+        }
+        client.send("question 3").await.unwrap();
+
+        // System message kept, plus the three most recent messages
+        let history = client.history();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0].role, MessageRole::System);
+        assert_eq!(history[1].role, MessageRole::User);
+        let last = match &history[3].content[0] {
+            ContentBlock::Text(t) => t.text.clone(),
+            other => panic!("unexpected block {:?}", other),
+        };
+        assert_eq!(last, "question 3");
+    }
+
+    #[tokio::test]
+    async fn test_max_history_messages_keeps_latest_message() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .max_history_messages(1)
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        client.history_mut().push(Message::system("Be brief"));
+
+        client.send("question 0").await.unwrap();
+        client.send("question 1").await.unwrap();
+
+        // The cap only leaves room for the system message, but the new prompt
+        // must still reach the model
+        let history = client.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, MessageRole::System);
+        let last = match &history[1].content[0] {
+            ContentBlock::Text(t) => t.text.clone(),
+            other => panic!("unexpected block {:?}", other),
+        };
+        assert_eq!(last, "question 1");
+    }
+
+    #[tokio::test]
+    async fn test_receive_text_skips_other_blocks() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let blocks = vec![
+            Ok(ContentBlock::Text(TextBlock::new("Hello"))),
+            Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                "call_1",
+                "search",
+                serde_json::json!({}),
+            ))),
+            Ok(ContentBlock::Text(TextBlock::new(" world"))),
+        ];
+        client.current_stream = Some(Box::pin(
+            futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+        ));
+
+        assert_eq!(
+            client.receive_text().await.unwrap().as_deref(),
+            Some("Hello")
+        );
+        assert_eq!(
+            client.receive_text().await.unwrap().as_deref(),
+            Some(" world")
+        );
+        assert_eq!(client.receive_text().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_text_only_filters_and_passes_errors() {
+        let blocks: ContentStream = Box::pin(futures::stream::iter(vec![
+            Ok(ContentBlock::Text(TextBlock::new("a"))),
+            Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                "call_1",
+                "search",
+                serde_json::json!({}),
+            ))),
+            Err(Error::stream("dropped")),
+        ]));
+
+        let items: Vec<_> = text_only(blocks).collect().await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].as_ref().unwrap(), "a");
+        assert!(matches!(items[1], Err(Error::Stream(_))));
+    }
+
+    #[tokio::test]
+    async fn test_auto_execution_keeps_tool_results_in_call_order() {
+        // The first tool is the slowest, so completion order is the reverse of call order
+        let delayed = |name: &str, delay_ms: u64| {
+            crate::tools::tool(name, "test").build(move |_| async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                Ok(serde_json::json!({}))
+            })
+        };
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .auto_execute_tools(true)
+            .dry_run(true)
+            .tool(delayed("slow", 40))
+            .tool(delayed("medium", 20))
+            .tool(delayed("fast", 0))
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        let calls = [("call_1", "slow"), ("call_2", "medium"), ("call_3", "fast")];
+        let blocks: Vec<Result<ContentBlock>> = calls
+            .iter()
+            .map(|(id, name)| {
+                Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                    *id,
+                    *name,
+                    serde_json::json!({}),
+                )))
+            })
+            .collect();
+        client.current_stream = Some(Box::pin(
+            futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+        ));
+
+        // Dry run: the follow-up request isn't sent, so the loop ends after one round
+        assert!(client.receive().await.unwrap().is_none());
+
+        let result_ids: Vec<String> = client
+            .history()
+            .iter()
+            .flat_map(|m| &m.content)
+            .filter_map(|block| match block {
+                ContentBlock::ToolResult(result) => Some(result.tool_use_id().to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(result_ids, vec!["call_1", "call_2", "call_3"]);
+
+        // Each result directly follows the assistant message, in call order
+        let request = client.last_request().unwrap();
+        let tool_call_ids: Vec<_> = request
+            .messages
+            .iter()
+            .filter_map(|m| m.tool_call_id.as_deref())
+            .collect();
+        assert_eq!(tool_call_ids, vec!["call_1", "call_2", "call_3"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_to_writer_writes_text_only() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let blocks = vec![
+            Ok(ContentBlock::Text(TextBlock::new("héllo"))),
+            Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                "call_1",
+                "search",
+                serde_json::json!({}),
+            ))),
+            Ok(ContentBlock::Text(TextBlock::new(" world"))),
+        ];
+        client.current_stream = Some(Box::pin(
+            futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+        ));
+
+        let mut output = Vec::new();
+        let written = client.stream_to_writer(&mut output).await.unwrap();
+        assert_eq!(output, "héllo world".as_bytes());
+        assert_eq!(written, output.len());
+    }
+
+    #[tokio::test]
+    async fn test_extra_body_is_sent_with_request() {
+        let extra = serde_json::json!({"grammar": "root ::= \"yes\""});
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .extra_body(extra.as_object().unwrap().clone())
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        client.send("Answer yes").await.unwrap();
+
+        let body = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert_eq!(body["grammar"], "root ::= \"yes\"");
+        assert_eq!(body["model"], "test-model");
+    }
+
+    #[tokio::test]
+    async fn test_query_complete_collects_response() {
+        let body = format!(
+            "{}{}data: {{\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":0,\
+             \"model\":\"m\",\"choices\":[],\"usage\":{{\"prompt_tokens\":9,\
+             \"completion_tokens\":2,\"total_tokens\":11}}}}\n\ndata: [DONE]\n\n",
+            sse_text_event("1", "Hello", "null"),
+            sse_text_event("2", " world", "\"length\""),
+        );
+        let (base_url, mut requests) = serve_sse(vec![(body, true)]).await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+        let response = query_complete("Hi", &options).await.unwrap();
+
+        assert_eq!(response.text, "Hello world");
+        assert!(response.tool_uses.is_empty());
+        assert_eq!(response.finish_reason, Some(FinishReason::Length));
+        assert_eq!(response.usage.unwrap().total_tokens, 11);
+        assert!(requests.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_send_with_prefill() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        client
+            .send_with_prefill("Answer in JSON", "{\"answer\":")
+            .await
+            .unwrap();
+
+        // The prefix ends the request but isn't stored in history
+        let request = client.last_request().unwrap();
+        let last = request.messages.last().unwrap();
+        assert_eq!(last.role, "assistant");
+        assert!(matches!(&last.content, Some(OpenAIContent::Text(t)) if t == "{\"answer\":"));
+        assert!(request.extra_body.is_empty());
+        assert_eq!(client.history().len(), 1);
+
+        // vLLM's continuation flags are opt-in
+        let mut vllm = Client::new(
+            AgentOptions::for_provider(crate::Provider::VLLM)
+                .model("test-model")
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        vllm.send_with_prefill("Answer in JSON", "{\"answer\":")
+            .await
+            .unwrap();
+        let request = vllm.last_request().unwrap();
+        assert_eq!(request.extra_body["continue_final_message"], true);
+        assert_eq!(request.extra_body["add_generation_prompt"], false);
+
+        // The continuation is returned with the prefix in front
+        let events = vec![
+            Ok(StreamEvent::Block(ContentBlock::Text(TextBlock::new(
+                " 42}",
+            )))),
+            Ok(StreamEvent::Block(ContentBlock::Text(TextBlock::new("!")))),
+        ];
+        client.current_stream = Some(prefix_first_text(
+            "{\"answer\":".to_string(),
+            Box::pin(futures::stream::iter(events)),
+        ));
+        assert_eq!(
+            client.receive_text().await.unwrap().as_deref(),
+            Some("{\"answer\": 42}")
+        );
+        assert_eq!(client.receive_text().await.unwrap().as_deref(), Some("!"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_completions_path() {
+        let body = format!("{}data: [DONE]\n\n", sse_text_event("1", "Hi", "\"stop\""));
+        let (base_url, mut requests) = serve_sse(vec![(body, true)]).await;
+        let root = base_url.trim_end_matches("/v1").to_string();
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&root)
+            .completions_path("/v2/chat")
+            .build()
+            .unwrap();
+        let response = query_complete("Hi", &options).await.unwrap();
+
+        assert_eq!(response.text, "Hi");
+        let head = requests.recv().await.unwrap();
+        assert!(head.starts_with("post /v2/chat http/1.1"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tool_is_hidden_and_refused() {
+        let calc = crate::tool("calculator", "Add numbers")
+            .param("a", "number")
+            .build(|_| async { Ok(serde_json::json!({"result": 2})) });
+        let shell = crate::tool("run_shell", "Run a command")
+            .param("command", "string")
+            .enabled(false)
+            .build(|_| async { Ok(serde_json::json!({})) });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .tool(calc)
+            .tool(shell)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options.clone()).unwrap();
+
+        let tools = client.build_request().unwrap().tools.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["function"]["name"], "calculator");
+        assert!(
+            client
+                .execute_tool_internal("run_shell", serde_json::json!({}))
+                .await
+                .is_err()
+        );
+
+        // Toggling at runtime takes effect on the next request
+        client.set_tool_enabled("calculator", false).unwrap();
+        assert!(client.build_request().unwrap().tools.is_none());
+        client.set_tool_enabled("run_shell", true).unwrap();
+        assert_eq!(client.build_request().unwrap().tools.unwrap().len(), 1);
+        assert!(client.set_tool_enabled("missing", true).is_err());
+
+        // ...for this client only
+        let mut other = Client::new(options).unwrap();
+        assert!(other.is_tool_enabled("calculator"));
+        assert!(!other.is_tool_enabled("run_shell"));
+        let tools = other.build_request().unwrap().tools.unwrap();
+        assert_eq!(tools[0]["function"]["name"], "calculator");
+    }
+
+    #[test]
+    fn test_registered_disabled_tool_stays_hidden() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let shell = crate::tool("run_shell", "Run a command")
+            .enabled(false)
+            .build(|_| async { Ok(serde_json::json!({})) });
+        client.register_tool(shell.clone()).unwrap();
+        assert!(!client.is_tool_enabled("run_shell"));
+        assert!(client.build_request().unwrap().tools.is_none());
+
+        // Through options_mut() as well
+        client.unregister_tool("run_shell");
+        client.options_mut().add_tool(shell).unwrap();
+        assert!(!client.is_tool_enabled("run_shell"));
+        assert!(client.build_request().unwrap().tools.is_none());
+    }
+
+    #[test]
+    fn test_reregistered_tool_forgets_override() {
+        let shell = || {
+            crate::tool("run_shell", "Run a command").build(|_| async { Ok(serde_json::json!({})) })
+        };
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client.register_tool(shell()).unwrap();
+        client.set_tool_enabled("run_shell", false).unwrap();
+
+        assert!(client.unregister_tool("run_shell"));
+        client.register_tool(shell()).unwrap();
+        assert!(client.is_tool_enabled("run_shell"));
+
+        // Removed and re-added through options_mut()
+        client.set_tool_enabled("run_shell", false).unwrap();
+        assert!(client.options_mut().remove_tool("run_shell"));
+        client.options_mut().add_tool(shell()).unwrap();
+        assert!(client.is_tool_enabled("run_shell"));
+        assert_eq!(client.build_request().unwrap().tools.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_messages_since_checkpoint() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client.append_message(Message::user("seed")).unwrap();
+        let saved = client.checkpoint();
+        assert!(client.messages_since(saved).is_empty());
+
+        client.append_message(Message::user("first")).unwrap();
+        client.append_message(Message::user("second")).unwrap();
+        let delta = client.messages_since(saved);
+        assert_eq!(delta.len(), 2);
+        assert!(matches!(&delta[0].content[0], ContentBlock::Text(t) if t.text == "first"));
+
+        // A checkpoint past the end yields nothing
+        client.rewind_to(1);
+        assert!(client.messages_since(3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_min_p_and_repetition_penalty_in_request() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://127.0.0.1:1/v1")
+            .min_p(0.05)
+            .repetition_penalty(1.1)
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        client.send("Hello").await.unwrap();
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert!((request["min_p"].as_f64().unwrap() - 0.05).abs() < 1e-6);
+        assert!((request["repetition_penalty"].as_f64().unwrap() - 1.1).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_auto_execution_reports_history_changes() {
+        let added = Arc::new(Mutex::new(Vec::new()));
+        let sink = added.clone();
+        let hooks = crate::Hooks::new().add_history_changed(move |event| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().push((event.index, event.message.role));
+            }
+        });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .auto_execute_tools(true)
+            .dry_run(true)
+            .hooks(hooks)
+            .tool(crate::tool("lookup", "test").build(|_| async { Ok(serde_json::json!({})) }))
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        client.history_mut().push(Message::user("Look it up"));
+
+        let blocks = vec![Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+            "call_1",
+            "lookup",
+            serde_json::json!({}),
+        )))];
+        client.current_stream = Some(Box::pin(
+            futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+        ));
+        assert!(client.receive().await.unwrap().is_none());
+
+        // Tool call, tool result, then the continuation prompt
+        assert_eq!(
+            *added.lock().unwrap(),
+            vec![
+                (1, MessageRole::Assistant),
+                (2, MessageRole::User),
+                (3, MessageRole::User),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_tool_result_truncates() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .tool_result_max_len(5)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        client
+            .add_tool_result("call_1", serde_json::json!("a very long result"))
+            .unwrap();
+        assert!(matches!(
+            &client.history()[0].content[0],
+            ContentBlock::ToolResult(r) if r.content() == "a ver...[truncated]"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tool_error_policy() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // Fails on the first call, succeeds afterwards
+        let client_with = |policy| {
+            let calls = Arc::new(AtomicU32::new(0));
+            let counter = calls.clone();
+            let flaky = crate::tool("transfer", "test").build(move |_| {
+                let calls = counter.clone();
+                async move {
+                    match calls.fetch_add(1, Ordering::SeqCst) {
+                        0 => Err(Error::tool("bank unavailable")),
+                        _ => Ok(serde_json::json!({"ok": true})),
+                    }
+                }
+            });
+            let options = AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .auto_execute_tools(true)
+                .dry_run(true)
+                .tool_error_policy(policy)
+                .tool(flaky)
+                .build()
+                .unwrap();
+            let mut client = Client::new(options).unwrap();
+            let blocks = vec![Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                "call_1",
+                "transfer",
+                serde_json::json!({}),
+            )))];
+            client.current_stream = Some(Box::pin(
+                futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+            ));
+            (client, calls)
+        };
+        let tool_result = |client: &Client| {
+            client
+                .history()
+                .iter()
+                .flat_map(|m| &m.content)
+                .find_map(|block| match block {
+                    ContentBlock::ToolResult(result) => Some(result.content().clone()),
+                    _ => None,
+                })
+        };
+
+        let (mut client, calls) = client_with(ToolErrorPolicy::ReportToModel);
+        assert!(client.receive().await.unwrap().is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(tool_result(&client).unwrap()["error"].is_string());
+
+        let (mut client, calls) = client_with(ToolErrorPolicy::Abort);
+        let err = client.receive().await.unwrap_err();
+        assert!(err.to_string().contains("bank unavailable"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (mut client, calls) = client_with(ToolErrorPolicy::Retry { max: 1 });
+        assert!(client.receive().await.unwrap().is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(tool_result(&client).unwrap()["ok"], true);
+
+        // Retrying a tool that doesn't exist can't help, so it fails at once
+        let (client, _) = client_with(ToolErrorPolicy::Retry { max: 3 });
+        let started = std::time::Instant::now();
+        let err = client
+            .execute_tool_with_policy("missing", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(started.elapsed() < TOOL_RETRY_INITIAL_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_replay_user_turns() {
+        let reply = |text| format!("{}data: [DONE]\n\n", sse_text_event("1", text, "\"stop\""));
+        let (base_url, mut requests) =
+            serve_sse(vec![(reply("New one"), true), (reply("New two"), true)]).await;
+        let options = AgentOptions::builder()
+            .model("candidate")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+
+        let recorded = vec![
+            Message::system("Be brief"),
+            Message::user("First?"),
+            Message::assistant(vec![ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                "call_1",
+                "lookup",
+                serde_json::json!({}),
+            ))]),
+            Message::user_with_blocks(vec![ContentBlock::ToolResult(crate::ToolResultBlock::new(
+                "call_1",
+                serde_json::json!({}),
+            ))]),
+            Message::user(""),
+            Message::assistant(vec![ContentBlock::Text(TextBlock::new("Old one"))]),
+            Message::user("Second?"),
+            Message::assistant(vec![ContentBlock::Text(TextBlock::new("Old two"))]),
+        ];
+        let transcript = Client::replay(&recorded, &options).await.unwrap();
+
+        let texts: Vec<(MessageRole, String)> = transcript
+            .iter()
+            .map(|m| match &m.content[0] {
+                ContentBlock::Text(t) => (m.role.clone(), t.text.clone()),
+                other => panic!("unexpected block {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (MessageRole::System, "Be brief".to_string()),
+                (MessageRole::User, "First?".to_string()),
+                (MessageRole::Assistant, "New one".to_string()),
+                (MessageRole::User, "Second?".to_string()),
+                (MessageRole::Assistant, "New two".to_string()),
+            ]
+        );
+        assert!(requests.recv().await.is_some());
+        assert!(requests.recv().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_query_with_messages() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .system_prompt("Classify")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let answer =
+            |text: &str| Message::assistant(vec![ContentBlock::Text(TextBlock::new(text))]);
+        let messages = vec![
+            Message::user("I love it"),
+            answer("positive"),
+            Message::user("It broke"),
+        ];
+
+        // Dry run yields the request JSON
+        let mut stream = query_with_messages(messages, &options).await.unwrap();
+        let Some(Ok(ContentBlock::Text(json))) = stream.next().await else {
+            panic!("expected the request JSON");
+        };
+        let request: serde_json::Value = serde_json::from_str(&json.text).unwrap();
+        let roles: Vec<_> = request["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+
+        assert!(query_with_messages(Vec::new(), &options).await.is_err());
+        let orphan = vec![Message::user_with_blocks(vec![ContentBlock::ToolResult(
+            crate::ToolResultBlock::new("call_1", serde_json::json!({})),
+        )])];
+        assert!(query_with_messages(orphan, &options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_auto_execution_bounds_parallel_tools() {
+        use std::sync::atomic::AtomicUsize;
+
+        // Returns the most tool calls that were running at once
+        async fn peak_concurrency(max_parallel_tools: Option<usize>) -> usize {
+            let running = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let tool = {
+                let (running, peak) = (running.clone(), peak.clone());
+                crate::tools::tool("work", "test").build(move |_| {
+                    let (running, peak) = (running.clone(), peak.clone());
+                    async move {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(serde_json::json!({}))
+                    }
+                })
+            };
+            let mut builder = AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .auto_execute_tools(true)
+                .dry_run(true)
+                .tool(tool);
+            if let Some(max) = max_parallel_tools {
+                builder = builder.max_parallel_tools(max);
+            }
+            let mut client = Client::new(builder.build().unwrap()).unwrap();
+
+            let blocks: Vec<Result<ContentBlock>> = (0..5)
+                .map(|i| {
+                    Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                        format!("call_{}", i),
+                        "work",
+                        serde_json::json!({}),
+                    )))
+                })
+                .collect();
+            client.current_stream = Some(Box::pin(
+                futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+            ));
+            assert!(client.receive().await.unwrap().is_none());
+            peak.load(Ordering::SeqCst)
+        }
+
+        // Sequential unless parallelism is opted into
+        assert_eq!(peak_concurrency(None).await, 1);
+        assert_eq!(peak_concurrency(Some(2)).await, 2);
+        assert_eq!(peak_concurrency(Some(5)).await, 5);
+        assert_eq!(peak_concurrency(Some(1)).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_auto_execution_runs_tools_in_order_and_abort_stops_the_rest() {
+        // Runs three calls to a tool that fails on call_2, returning the log
+        // and the tool results left in history
+        async fn run(
+            policy: ToolErrorPolicy,
+        ) -> (
+            Vec<String>,
+            Result<Option<ContentBlock>>,
+            Vec<(String, serde_json::Value)>,
+        ) {
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let tool = {
+                let log = log.clone();
+                crate::tools::tool("step", "test").build(move |input| {
+                    let log = log.clone();
+                    async move {
+                        let id = input["id"].as_str().unwrap_or_default().to_string();
+                        log.lock().unwrap().push(format!("start {}", id));
+                        // Earlier calls take longer, so overlap would reorder the log
+                        let delay = if id == "call_1" { 30 } else { 5 };
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        log.lock().unwrap().push(format!("end {}", id));
+                        if id == "call_2" {
+                            return Err(Error::tool("step failed"));
+                        }
+                        Ok(serde_json::json!({}))
+                    }
+                })
+            };
+            let options = AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .auto_execute_tools(true)
+                .dry_run(true)
+                .tool_error_policy(policy)
+                .tool(tool)
+                .build()
+                .unwrap();
+            let mut client = Client::new(options).unwrap();
+
+            let blocks: Vec<Result<ContentBlock>> = (1..=3)
+                .map(|i| {
+                    let id = format!("call_{}", i);
+                    Ok(ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                        id.clone(),
+                        "step",
+                        serde_json::json!({ "id": id }),
+                    )))
+                })
+                .collect();
+            client.current_stream = Some(Box::pin(
+                futures::stream::iter(blocks).map(|b| b.map(StreamEvent::Block)),
+            ));
+            let outcome = client.receive().await;
+            let log = log.lock().unwrap().clone();
+            let results = client
+                .history()
+                .iter()
+                .flat_map(|m| &m.content)
+                .filter_map(|block| match block {
+                    ContentBlock::ToolResult(result) => {
+                        Some((result.tool_use_id().to_string(), result.content().clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+            (log, outcome, results)
+        }
+
+        let (log, outcome, _) = run(ToolErrorPolicy::ReportToModel).await;
+        assert!(outcome.is_ok());
+        assert_eq!(
+            log,
+            vec![
+                "start call_1",
+                "end call_1",
+                "start call_2",
+                "end call_2",
+                "start call_3",
+                "end call_3"
+            ]
+        );
+
+        // call_3 never starts once call_2 aborts the loop
+        let (log, outcome, results) = run(ToolErrorPolicy::Abort).await;
+        assert!(outcome.unwrap_err().to_string().contains("step failed"));
+        assert_eq!(
+            log,
+            vec!["start call_1", "end call_1", "start call_2", "end call_2"]
+        );
+
+        // ...but every call is still answered in history
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["call_1", "call_2", "call_3"]);
+        assert_eq!(results[0].1, serde_json::json!({}));
+        assert!(
+            results[1].1["error"]
+                .as_str()
+                .unwrap()
+                .contains("step failed")
+        );
+        assert!(results[2].1["error"].as_str().unwrap().contains("Not run"));
+    }
+
+    #[tokio::test]
+    async fn test_trace_id_header_is_sent() {
+        let body = format!("{}data: [DONE]\n\n", sse_text_event("1", "Hi", "\"stop\""));
+        let (base_url, mut requests) = serve_sse(vec![(body, true)]).await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .trace_id("trace-123")
+            .trace_id_header("X-Request-Id")
+            .build()
+            .unwrap();
+        query_complete("Hi", &options).await.unwrap();
+
+        let head = requests.recv().await.unwrap();
+        assert!(head.contains("x-request-id: trace-123"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn test_service_tier_in_request() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .service_tier("priority")
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        let response = query_complete("Hi", &options).await.unwrap();
+        let request: serde_json::Value = serde_json::from_str(&response.text).unwrap();
+        assert_eq!(request["service_tier"], "priority");
+
+        let mut client = Client::new(options).unwrap();
+        client.send("Hi").await.unwrap();
+        assert_eq!(
+            client.last_request().unwrap().service_tier.as_deref(),
+            Some("priority")
+        );
+    }
+
+    #[test]
+    fn test_export_openai_messages_after_tool_cycle() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .system_prompt("Be brief")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        client.append_message(Message::user("Add 2 and 2")).unwrap();
+        client
+            .append_message(Message::assistant(vec![
+                ContentBlock::Text(TextBlock::new("Adding")),
+                ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                    "call_1",
+                    "add",
+                    serde_json::json!({"a": 2, "b": 2}),
+                )),
+            ]))
+            .unwrap();
+        client
+            .append_message(Message::user_with_blocks(vec![ContentBlock::ToolResult(
+                crate::ToolResultBlock::new("call_1", serde_json::json!({"result": 4})),
+            )]))
+            .unwrap();
+
+        let messages = client.export_openai_messages().unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "tool"]);
+
+        let call = &messages[2].tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.id, "call_1");
+        assert_eq!(call.function.arguments, r#"{"a":2,"b":2}"#);
+        assert_eq!(messages[3].tool_call_id.as_deref(), Some("call_1"));
+
+        // Same messages as the request send() would build
+        let request = client.build_request().unwrap();
+        assert_eq!(
+            serde_json::to_value(&request.messages).unwrap(),
+            serde_json::to_value(&messages).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_with_capacity_preallocates_history() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .build()
+            .unwrap();
+        let client = Client::with_capacity(options, 1000).unwrap();
+
+        assert!(client.history().is_empty());
+        assert!(client.history.capacity() >= 1000);
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_and_drain_consumes_stream() {
+        use std::sync::atomic::AtomicUsize;
+
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = read.clone();
+        let blocks: Vec<Result<ContentBlock>> = (0..3)
+            .map(|i| Ok(ContentBlock::Text(TextBlock::new(format!("part {}", i)))))
+            .collect();
+        client.current_stream = Some(Box::pin(futures::stream::iter(blocks).map(move |b| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            b.map(StreamEvent::Block)
+        })));
+
+        client.interrupt_and_drain().await.unwrap();
+
+        assert_eq!(read.load(Ordering::SeqCst), 3);
+        assert!(client.current_stream.is_none());
+        assert!(client.receive().await.unwrap().is_none());
+
+        // Nothing to drain is fine too
+        client.interrupt_and_drain().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_reason() {
+        let mut client = Client::new(
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url("http://localhost:1234/v1")
+                .dry_run(true)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(client.last_interrupt_reason(), None);
+
+        client.interrupt_with_reason("timeout after 30s");
+        assert!(client.receive().await.unwrap().is_none());
+        assert_eq!(
+            client.last_interrupt_reason().as_deref(),
+            Some("timeout after 30s")
+        );
+
+        // A new request starts with a clean slate
+        client.send("Hi").await.unwrap();
+        assert_eq!(client.last_interrupt_reason(), None);
+
+        client.interrupt_handle().store(true, Ordering::SeqCst);
+        assert_eq!(
+            client.last_interrupt_reason().as_deref(),
+            Some("interrupted")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        let body = format!("{}data: [DONE]\n\n", sse_text_event("1", "Hi", "\"stop\""));
+        let (base_url, mut requests) = serve_sse(vec![(body.clone(), true), (body, true)]).await;
+        let builder = || {
+            AgentOptions::builder()
+                .model("test-model")
+                .base_url(&base_url)
+        };
+
+        query_complete("Hi", &builder().build().unwrap())
+            .await
+            .unwrap();
+        let head = requests.recv().await.unwrap();
+        assert!(head.contains("user-agent: open-agent-rust/"), "{}", head);
+
+        let mut client = Client::new(builder().user_agent("myapp/1.2").build().unwrap()).unwrap();
+        client.send("Hi").await.unwrap();
+        while client.receive().await.unwrap().is_some() {}
+        let head = requests.recv().await.unwrap();
+        assert!(head.contains("user-agent: myapp/1.2"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn test_partial_text_after_interrupted_stream() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let chunk = |content: &str| -> Result<OpenAIChunk> {
+            Ok(serde_json::from_value(serde_json::json!({
+                "id": "c1", "object": "chat.completion.chunk", "created": 0, "model": "m",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+            }))
+            .unwrap())
+        };
+        // The stream stops before the finish reason, as after a cancel or dropped connection
+        let chunks: ChunkStream = Box::pin(futures::stream::iter(vec![
+            chunk("Once upon"),
+            chunk(" a time"),
+        ]));
+        client.current_stream = Some(event_stream(record_response_info(
+            chunks,
+            Arc::clone(&client.last_response),
+        )));
+
+        assert!(client.receive().await.unwrap().is_none());
+        assert_eq!(client.partial_text().as_deref(), Some("Once upon a time"));
+
+        assert!(client.commit_partial());
+        assert_eq!(client.history().len(), 1);
+        assert_eq!(client.history()[0].role, MessageRole::Assistant);
+        assert_eq!(client.partial_text(), None);
+        assert!(!client.commit_partial());
+    }
+
+    #[test]
+    fn test_history_stats() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        let text = |t: &str| ContentBlock::Text(TextBlock::new(t));
+        client.append_message(Message::user("Weather?")).unwrap();
+        client
+            .append_message(Message::assistant(vec![
+                ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                    "call_1",
+                    "weather",
+                    serde_json::json!({}),
+                )),
+                ContentBlock::ToolUse(crate::ToolUseBlock::new(
+                    "call_2",
+                    "time",
+                    serde_json::json!({}),
+                )),
+            ]))
+            .unwrap();
+        for id in ["call_1", "call_2"] {
+            client
+                .append_message(Message::user_with_blocks(vec![ContentBlock::ToolResult(
+                    crate::ToolResultBlock::new(id, serde_json::json!({})),
+                )]))
+                .unwrap();
+        }
+        client
+            .append_message(Message::assistant(vec![text("Sunny")]))
+            .unwrap();
+        client.append_message(Message::user("Thanks")).unwrap();
+
+        assert_eq!(client.turn_count(), 1);
+        assert_eq!(client.tool_call_count(), 2);
+        let counts = client.message_count_by_role();
+        assert_eq!(counts[&MessageRole::User], 4);
+        assert_eq!(counts[&MessageRole::Assistant], 2);
+        assert!(!counts.contains_key(&MessageRole::System));
+
+        client
+            .append_message(Message::assistant(vec![text("You're welcome")]))
+            .unwrap();
+        assert_eq!(client.turn_count(), 2);
+    }
+
+    #[test]
+    fn test_new_idempotency_key_is_uuid_v4() {
+        let key = new_idempotency_key();
+        let groups: Vec<&str> = key.split('-').collect();
+        assert_eq!(
+            groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert!(key.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert!(groups[2].starts_with('4'));
+        assert!(matches!(groups[3].as_bytes()[0], b'8' | b'9' | b'a' | b'b'));
+        assert_ne!(key, new_idempotency_key());
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_reused_on_resume() {
+        let first = sse_text_event("1", "Hello", "null");
+        let second = format!(
+            "{}data: [DONE]\n\n",
+            sse_text_event("2", " world", "\"stop\"")
+        );
+        let (base_url, mut requests) = serve_dropped_then_resumed(first, second).await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .resume_streams(true)
+            .idempotency(true)
+            .build()
+            .unwrap();
+        query_complete("Hi", &options).await.unwrap();
+
+        let key_of = |head: &str| {
+            head.lines()
+                .find_map(|l| l.strip_prefix("idempotency-key: "))
+                .map(str::to_string)
+        };
+        let initial = key_of(&requests.recv().await.unwrap()).unwrap();
+        let resumed = key_of(&requests.recv().await.unwrap()).unwrap();
+        assert_eq!(initial.len(), 36);
+        assert_eq!(initial, resumed);
+
+        // Off by default
+        let body = format!("{}data: [DONE]\n\n", sse_text_event("1", "Hi", "\"stop\""));
+        let (base_url, mut requests) = serve_sse(vec![(body, true)]).await;
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+        query_complete("Hi", &options).await.unwrap();
+        assert!(!requests.recv().await.unwrap().contains("idempotency-key"));
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_shared_across_retries() {
+        use crate::retry::{RetryConfig, retry_with_backoff, with_idempotency_key};
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::error(503, "busy"),
+            MockResponse::text("Hi"),
+            MockResponse::text("Hi"),
+            MockResponse::text("Hi"),
+            MockResponse::text("Hi"),
+        ])
+        .await
+        .unwrap();
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .idempotency(true)
+            .build()
+            .unwrap();
+        let config = RetryConfig::new()
+            .with_max_attempts(2)
+            .with_initial_delay(Duration::from_millis(1));
+
+        retry_with_backoff(config.clone(), || query_complete("Hi", &options))
+            .await
+            .unwrap();
+        // A separate logical request gets a new key
+        query_complete("Hi", &options).await.unwrap();
+        // A pinned key is used even without the option
+        let plain = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+        with_idempotency_key("order-1", query_complete("Hi", &plain))
+            .await
+            .unwrap();
+        // Retries alone don't turn the option on
+        retry_with_backoff(config.clone(), || query_complete("Hi", &plain))
+            .await
+            .unwrap();
+
+        let keys: Vec<Option<String>> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("idempotency-key").map(str::to_string))
+            .collect();
+        assert_eq!(keys.len(), 5);
+        assert!(keys[0].is_some());
+        assert_eq!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+        assert_eq!(keys[3].as_deref(), Some("order-1"));
+        assert_eq!(keys[4], None);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_tool_calls_only_sent_with_tools() {
+        let lookup = crate::tools::tool("lookup", "Look up a record")
+            .build(|_| async { Ok(serde_json::json!({})) });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .tool(lookup)
+            .allow_parallel_tool_calls(false)
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        let response = query_complete("Hi", &options).await.unwrap();
+        let request: serde_json::Value = serde_json::from_str(&response.text).unwrap();
+        assert_eq!(request["parallel_tool_calls"], false);
+
+        let mut client = Client::new(options.clone()).unwrap();
+        client.send("Hi").await.unwrap();
+        assert_eq!(
+            client.last_request().unwrap().parallel_tool_calls,
+            Some(false)
+        );
+
+        // Without enabled tools the flag is left out
+        client.set_tool_enabled("lookup", false).unwrap();
+        client.send("Hi").await.unwrap();
+        assert_eq!(client.last_request().unwrap().parallel_tool_calls, None);
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let response = query_complete("Hi", &options).await.unwrap();
+        assert!(!response.text.contains("parallel_tool_calls"));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_delivers_every_item_to_each_consumer() {
+        let blocks: ContentStream = Box::pin(futures::stream::iter(vec![
+            Ok(ContentBlock::Text(TextBlock::new("a"))),
+            Ok(ContentBlock::Text(TextBlock::new("b"))),
+            Err(Error::stream("dropped")),
+        ]));
+
+        let receivers = fan_out(blocks, 2, 16).unwrap();
+        assert_eq!(receivers.len(), 2);
+        for mut receiver in receivers {
+            let mut items = Vec::new();
+            while let Ok(item) = receiver.recv().await {
+                items.push(item);
+            }
+            assert_eq!(items.len(), 3);
+            assert!(matches!(&items[1], Ok(ContentBlock::Text(t)) if t.text == "b"));
+            assert!(matches!(
+                items[2].as_ref().unwrap_err().as_ref(),
+                Error::Stream(_)
+            ));
+        }
+
+        let empty = || -> ContentStream { Box::pin(futures::stream::empty()) };
+        assert!(fan_out(empty(), 0, 16).is_err());
+        assert!(fan_out(empty(), 2, 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_continue_if_truncated_stitches_responses() {
+        let truncated = |id: &str, text: &str| {
+            format!("{}data: [DONE]\n\n", sse_text_event(id, text, "\"length\""))
+        };
+        let (base_url, mut requests) = serve_sse(vec![
+            (truncated("1", "Once upon"), true),
+            (truncated("2", " a time"), true),
+            (
+                format!("{}data: [DONE]\n\n", sse_text_event("3", ".", "\"stop\"")),
+                true,
+            ),
+        ])
+        .await;
+
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        client.send("Tell a story").await.unwrap();
+        assert_eq!(
+            client.continue_if_truncated(5).await.unwrap(),
+            "Once upon a time."
+        );
+        assert_eq!(client.last_finish_reason(), Some(FinishReason::Stop));
+        // Both truncated parts were recorded; the final one is left to the caller
+        assert_eq!(client.turn_count(), 2);
+        assert_eq!(client.history()[1].text(), "Once upon");
+        assert_eq!(client.history()[2].text(), CONTINUATION_PROMPT);
+        for _ in 0..3 {
+            requests.recv().await.unwrap();
+        }
+
+        // The cap stops early and leaves the reason as Length
+        let (base_url, _requests) = serve_sse(vec![
+            (truncated("1", "a"), true),
+            (truncated("2", "b"), true),
+        ])
+        .await;
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        client.send("Go").await.unwrap();
+        assert_eq!(client.continue_if_truncated(1).await.unwrap(), "ab");
+        assert_eq!(client.last_finish_reason(), Some(FinishReason::Length));
+    }
+
+    #[tokio::test]
+    async fn test_tool_phase_temperature_in_auto_loop() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::tool_call("call_1", "lookup", serde_json::json!({})),
+            MockResponse::text("Draft at tool temperature"),
+            MockResponse::text("Final answer"),
+        ])
+        .await
+        .unwrap();
+
+        let lookup = crate::tools::tool("lookup", "Look up a record")
+            .build(|_| async { Ok(serde_json::json!({"found": true})) });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .tool(lookup)
+            .auto_execute_tools(true)
+            .temperature(0.5)
+            .tool_phase_temperature(0.0)
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).unwrap();
+        client.send("Hi").await.unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = client.receive_text().await.unwrap() {
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "Final answer");
+
+        let temperatures: Vec<f64> = server
+            .requests()
             .iter()
-            .find(|t| t.name() == name)
-            .map(|t| t.as_ref())
+            .map(|r| r.body["temperature"].as_f64().unwrap())
+            .collect();
+        assert_eq!(temperatures, vec![0.0, 0.0, 0.5]);
+
+        // Only the answer request rules out further tool calls
+        let tool_choices: Vec<serde_json::Value> = server
+            .requests()
+            .iter()
+            .map(|r| r.body["tool_choice"].clone())
+            .collect();
+        assert_eq!(
+            tool_choices,
+            vec![
+                serde_json::Value::Null,
+                serde_json::Value::Null,
+                serde_json::json!("none")
+            ]
+        );
+
+        // The discarded draft never reaches history
+        assert!(client.history().iter().all(|m| !m.text().contains("Draft")));
+        assert_eq!(client.history().last().unwrap().text(), "Final answer");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_set_temperature_keeps_streaming_answer() {
+        use crate::testing::{MockResponse, MockServer};
 
-    #[test]
-    fn test_client_creation() {
+        let server = MockServer::start(vec![MockResponse::text("Answer")])
+            .await
+            .unwrap();
+        let lookup = crate::tools::tool("lookup", "Look up a record")
+            .build(|_| async { Ok(serde_json::json!({"found": true})) });
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(server.base_url())
+            .tool(lookup)
+            .auto_execute_tools(true)
+            .temperature(0.5)
             .build()
             .unwrap();
 
-        let client = Client::new(options).expect("Should create client successfully");
-        assert_eq!(client.history().len(), 0);
+        // No tool-phase temperature: the answer is kept, not re-requested
+        let mut client = Client::new(options).unwrap();
+        client.send("Hi").await.unwrap();
+        client.set_temperature(1.0).unwrap();
+        assert_eq!(
+            client.receive_text().await.unwrap().as_deref(),
+            Some("Answer")
+        );
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(client.history().last().unwrap().text(), "Answer");
     }
 
-    #[test]
-    fn test_client_new_returns_result() {
-        // Test that Client::new() returns Result instead of panicking
+    #[tokio::test]
+    async fn test_drop_mid_stream_closes_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        for buffer in [None, Some(4)] {
+            // Sends the start of a response, then stalls until the client hangs up
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (closed_tx, closed_rx) = tokio::sync::oneshot::channel();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let body = sse_text_event("1", "Hel", "null");
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                     Transfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                while !matches!(socket.read(&mut buf).await, Ok(0) | Err(_)) {}
+                let _ = closed_tx.send(());
+            });
+
+            let mut builder = AgentOptions::builder()
+                .model("test-model")
+                .base_url(format!("http://{}/v1", addr));
+            if let Some(size) = buffer {
+                builder = builder.stream_buffer_size(size);
+            }
+            let mut client = Client::new(builder.build().unwrap()).unwrap();
+            client.send("Hi").await.unwrap();
+            let handle = client.interrupt_handle();
+
+            drop(client);
+            assert!(handle.load(Ordering::SeqCst));
+            tokio::time::timeout(Duration::from_secs(5), closed_rx)
+                .await
+                .expect("connection still open after the client was dropped")
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_closes_connection_when_receivers_drop() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Sends the start of a response, then stalls until the client hangs up
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (closed_tx, closed_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            let body = sse_text_event("1", "Hel", "null");
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                 Transfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            while !matches!(socket.read(&mut buf).await, Ok(0) | Err(_)) {}
+            let _ = closed_tx.send(());
+        });
+
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(format!("http://{}/v1", addr))
             .build()
             .unwrap();
+        let receivers = fan_out(query("Hi", &options).await.unwrap(), 2, 16).unwrap();
 
-        // This should not panic - it should return Ok(client)
-        let result = Client::new(options);
-        assert!(result.is_ok(), "Client::new() should return Ok");
+        drop(receivers);
+        tokio::time::timeout(Duration::from_secs(5), closed_rx)
+            .await
+            .expect("connection still open after every receiver was dropped")
+            .unwrap();
+    }
 
-        let client = result.unwrap();
-        assert_eq!(client.history().len(), 0);
+    #[tokio::test]
+    async fn test_query_with_handle_reports_response_details() {
+        let body = format!(
+            "{}data: {{\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":0,\
+             \"model\":\"m\",\"choices\":[],\"usage\":{{\"prompt_tokens\":3,\
+             \"completion_tokens\":2,\"total_tokens\":5}}}}\n\ndata: [DONE]\n\n",
+            sse_text_event("1", "Hi", "\"stop\"")
+        );
+        let (base_url, _requests) = serve_sse(vec![(body, true)]).await;
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(&base_url)
+            .build()
+            .unwrap();
+
+        let (mut stream, handle) = query_with_handle("Hi", &options).await.unwrap();
+        assert_eq!(handle.id(), None);
+        while let Some(block) = stream.next().await {
+            block.unwrap();
+        }
+
+        assert_eq!(handle.id().as_deref(), Some("c1"));
+        assert_eq!(handle.finish_reason(), Some(FinishReason::Stop));
+        assert_eq!(handle.usage().unwrap().total_tokens, 5);
     }
 
-    #[test]
-    fn test_interrupt_flag_initial_state() {
+    #[tokio::test]
+    async fn test_seed_and_sampling_params_repeat_across_auto_loop() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::tool_call("call_1", "lookup", serde_json::json!({})),
+            MockResponse::tool_call("call_2", "lookup", serde_json::json!({})),
+            MockResponse::text("Done"),
+        ])
+        .await
+        .unwrap();
+
+        let lookup = crate::tools::tool("lookup", "Look up a record")
+            .build(|_| async { Ok(serde_json::json!({"found": true})) });
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(server.base_url())
+            .tool(lookup)
+            .auto_execute_tools(true)
+            .seed(42)
+            .temperature(0.3)
+            .top_p(0.9)
+            .max_tokens(256)
             .build()
             .unwrap();
 
-        let client = Client::new(options).expect("Should create client successfully");
-        // Initially not interrupted
-        assert!(!client.interrupted.load(Ordering::SeqCst));
+        let mut client = Client::new(options).unwrap();
+        client.send("Hi").await.unwrap();
+        while client.receive().await.unwrap().is_some() {}
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        for request in &requests {
+            let body = &request.body;
+            assert_eq!(body["seed"], 42);
+            assert_eq!(
+                (&body["temperature"], &body["top_p"], &body["max_tokens"]),
+                (
+                    &requests[0].body["temperature"],
+                    &requests[0].body["top_p"],
+                    &requests[0].body["max_tokens"]
+                )
+            );
+        }
+        assert_eq!(requests[0].body["max_tokens"], 256);
     }
 
     #[test]
-    fn test_interrupt_sets_flag() {
+    fn test_tool_result_images_follow_tool_messages() {
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client.append_message(Message::user("Plot sales")).unwrap();
+        client
+            .append_message(Message::assistant(vec![
+                ContentBlock::ToolUse(ToolUseBlock::new("call_1", "plot", serde_json::json!({}))),
+                ContentBlock::ToolUse(ToolUseBlock::new("call_2", "stats", serde_json::json!({}))),
+            ]))
+            .unwrap();
+        let chart = crate::ImageBlock::from_base64("iVBORw0KGgo=", "image/png").unwrap();
+        client
+            .append_message(Message::user_with_blocks(vec![
+                ContentBlock::ToolResult(
+                    crate::ToolResultBlock::new("call_1", serde_json::json!({"ok": true}))
+                        .with_image(chart),
+                ),
+                ContentBlock::ToolResult(crate::ToolResultBlock::text("call_2", "mean 4.2")),
+            ]))
+            .unwrap();
+
+        let messages = client.export_openai_messages().unwrap();
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "tool", "tool", "user"]);
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_1"));
+
+        let parts = serde_json::to_value(&messages[4].content).unwrap();
+        assert_eq!(parts[0]["text"], "Image output of tool call call_1:");
+        assert_eq!(
+            parts[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+        assert_eq!(parts.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_set_hooks_applies_to_next_request_only() {
+        use crate::hooks::{HookDecision, Hooks};
+
+        let (base_url, _requests) = serve_sse(vec![
+            (
+                format!(
+                    "{}data: [DONE]\n\n",
+                    sse_text_event("1", "first", "\"stop\"")
+                ),
+                true,
+            ),
+            (
+                format!(
+                    "{}data: [DONE]\n\n",
+                    sse_text_event("2", "second", "\"stop\"")
+                ),
+                true,
+            ),
+        ])
+        .await;
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(&base_url)
             .build()
             .unwrap();
+        let mut client = Client::new(options).unwrap();
+        let shout = || {
+            Hooks::new().add_post_receive(|event| async move {
+                match event.block {
+                    ContentBlock::Text(text) => Some(HookDecision::modify_block(
+                        ContentBlock::Text(TextBlock::new(text.text.to_uppercase())),
+                        "shout",
+                    )),
+                    _ => None,
+                }
+            })
+        };
 
-        let client = Client::new(options).expect("Should create client successfully");
-        client.interrupt();
-        assert!(client.interrupted.load(Ordering::SeqCst));
+        // Swapped in while the first response is streaming: not applied to it
+        client.send("One").await.unwrap();
+        client.set_hooks(shout());
+        let mut text = String::new();
+        while let Some(chunk) = client.receive_text().await.unwrap() {
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "first");
+
+        client.send("Two").await.unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = client.receive_text().await.unwrap() {
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "SECOND");
     }
 
-    #[test]
-    fn test_interrupt_idempotent() {
+    #[tokio::test]
+    async fn test_estimate_cost_uses_reported_usage_or_history_estimate() {
+        let prices = PriceTable {
+            prompt_per_1k: 1.0,
+            completion_per_1k: 2.0,
+        };
+        let usage_event = |prompt: u32, completion: u32| {
+            format!(
+                "data: {{\"id\":\"u\",\"object\":\"chat.completion.chunk\",\"created\":0,\
+                 \"model\":\"m\",\"choices\":[],\"usage\":{{\"prompt_tokens\":{prompt},\
+                 \"completion_tokens\":{completion},\"total_tokens\":{}}}}}\n\n",
+                prompt + completion
+            )
+        };
+        let (base_url, _requests) = serve_sse(vec![
+            (
+                format!(
+                    "{}{}data: [DONE]\n\n",
+                    sse_text_event("1", "a", "\"stop\""),
+                    usage_event(100, 10)
+                ),
+                true,
+            ),
+            (
+                format!(
+                    "{}{}data: [DONE]\n\n",
+                    sse_text_event("2", "b", "\"stop\""),
+                    usage_event(200, 20)
+                ),
+                true,
+            ),
+            (
+                format!("{}data: [DONE]\n\n", sse_text_event("3", "c", "\"stop\"")),
+                true,
+            ),
+        ])
+        .await;
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(&base_url)
             .build()
             .unwrap();
+        let mut client = Client::new(options).unwrap();
 
-        let client = Client::new(options).expect("Should create client successfully");
-        client.interrupt();
-        assert!(client.interrupted.load(Ordering::SeqCst));
+        for prompt in ["One", "Two"] {
+            client.send(prompt).await.unwrap();
+            while client.receive().await.unwrap().is_some() {}
+        }
+        let total = client.total_usage().unwrap();
+        assert_eq!((total.prompt_tokens, total.completion_tokens), (300, 30));
+        assert!((client.estimate_cost(&prices) - 0.36).abs() < 1e-9);
+
+        // A response without usage switches to the history estimate
+        client.send("Three").await.unwrap();
+        while client.receive().await.unwrap().is_some() {}
+        assert_eq!(client.total_usage(), None);
+        let history = client.history().to_vec();
+        let expected = prices.cost(&estimate_history_usage(
+            "",
+            &history,
+            TokenizerHint::Generic,
+        ));
+        assert_eq!(client.estimate_cost(&prices), expected);
 
-        // Call again - should still be interrupted
-        client.interrupt();
-        assert!(client.interrupted.load(Ordering::SeqCst));
+        client.clear_history();
+        assert_eq!(client.total_usage(), None);
+        assert_eq!(client.estimate_cost(&prices), 0.0);
     }
 
     #[tokio::test]
-    async fn test_receive_returns_none_when_interrupted() {
+    async fn test_send_with_tools_limits_tools_for_the_turn() {
+        let noop = |name: &str| {
+            crate::tools::tool(name, "Test tool").build(|_| async { Ok(serde_json::json!({})) })
+        };
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
             .base_url("http://localhost:1234/v1")
+            .tools(vec![noop("confirm"), noop("cancel"), noop("search")])
+            .allow_parallel_tool_calls(false)
+            .dry_run(true)
             .build()
             .unwrap();
+        let mut client = Client::new(options).unwrap();
+        let tool_names = |client: &Client| -> Vec<String> {
+            client
+                .last_request()
+                .unwrap()
+                .tools
+                .iter()
+                .flatten()
+                .map(|t| t["function"]["name"].as_str().unwrap().to_string())
+                .collect()
+        };
 
-        let mut client = Client::new(options).expect("Should create client successfully");
+        client.send_with_tools("Yes", &["confirm"]).await.unwrap();
+        assert_eq!(tool_names(&client), vec!["confirm"]);
 
-        // Interrupt before receiving
-        client.interrupt();
+        client.send_with_tools("Just talk", &[]).await.unwrap();
+        let request = client.last_request().unwrap();
+        assert!(request.tools.is_none());
+        assert!(request.parallel_tool_calls.is_none());
 
-        // NEW SIGNATURE: receive() should return Ok(None) when interrupted
-        let result = client.receive().await;
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_none());
+        // The registry is untouched; a plain send offers everything again
+        client.send("Anything").await.unwrap();
+        assert_eq!(tool_names(&client), vec!["confirm", "cancel", "search"]);
+
+        let history_len = client.history().len();
+        let err = client.send_with_tools("Hi", &["delete"]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+        assert_eq!(client.history().len(), history_len);
+    }
+
+    #[test]
+    fn test_long_multibyte_image_url_is_logged_without_panicking() {
+        // Shift two-, three- and four-byte characters across byte 100
+        for ch in ["é", "€", "𝄞"] {
+            for padding in 0..4 {
+                let url = format!(
+                    "https://example.com/{}{}",
+                    "a".repeat(padding),
+                    ch.repeat(100)
+                );
+                let mut client = Client::new(AgentOptions::default()).unwrap();
+                client
+                    .append_message(Message::user_with_image("Describe", url.clone()).unwrap())
+                    .unwrap();
+
+                let messages = client.export_openai_messages().unwrap();
+                let parts = serde_json::to_value(&messages[0].content).unwrap();
+                assert_eq!(parts[1]["image_url"]["url"], url.as_str());
+            }
+        }
     }
 
     #[tokio::test]
-    async fn test_receive_returns_ok_none_when_no_stream() {
+    async fn test_options_mut_changes_next_request() {
         let options = AgentOptions::builder()
-            .system_prompt("Test")
-            .model("test-model")
+            .model("small-model")
             .base_url("http://localhost:1234/v1")
+            .dry_run(true)
             .build()
             .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        let options = client.options_mut();
+        options.set_model("large-model").unwrap();
+        options.set_system_prompt("Be brief");
+        options.set_temperature(0.1).unwrap();
+        assert!(options.set_model("  ").is_err());
+        assert!(options.set_temperature(-1.0).is_err());
+        assert!(options.set_max_tokens(Some(0)).is_err());
+
+        client.send("Hi").await.unwrap();
+        let request = client.last_request().unwrap();
+        assert_eq!(request.model, "large-model");
+        assert_eq!(request.temperature, Some(0.1));
+        assert_eq!(request.max_tokens, Some(4096));
+        assert!(matches!(
+            &request.messages[0].content,
+            Some(OpenAIContent::Text(text)) if text == "Be brief"
+        ));
+    }
 
-        let mut client = Client::new(options).expect("Should create client successfully");
+    #[tokio::test]
+    async fn test_send_streaming_tools_reports_tool_activity() {
+        use crate::testing::{MockResponse, MockServer};
 
-        // No stream started - receive() should return Ok(None)
-        let result = client.receive().await;
-        assert!(result.is_ok());
-        assert!(result.unwrap().is_none());
+        let server = MockServer::start(vec![
+            MockResponse::tool_call("call_1", "lookup", serde_json::json!({"key": "a"})),
+            MockResponse::text("Found it"),
+        ])
+        .await
+        .unwrap();
+
+        let lookup = crate::tools::tool("lookup", "Look up a record")
+            .build(|_| async { Ok(serde_json::json!({"found": true})) });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .tool(lookup)
+            .auto_execute_tools(true)
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).unwrap();
+        client.send_streaming_tools("Find a").await.unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = client.receive_event().await.unwrap() {
+            events.push(event);
+        }
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ToolCallStarted { id, name, input }
+                if id == "call_1" && name == "lookup" && input["key"] == "a"
+        ));
+        assert!(matches!(
+            &events[1],
+            StreamEvent::ToolCallCompleted { id, result, .. }
+                if id == "call_1" && result["found"] == true
+        ));
+        assert!(matches!(
+            &events[2],
+            StreamEvent::Block(ContentBlock::Text(text)) if text.text == "Found it"
+        ));
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(
+            client.history().last().map(|message| message.role.clone()),
+            Some(MessageRole::Assistant)
+        );
+
+        // Only available in auto-execution mode
+        let mut manual = Client::new(AgentOptions::default()).unwrap();
+        assert!(matches!(
+            manual.send_streaming_tools("Hi").await,
+            Err(Error::Config(_))
+        ));
     }
 
     #[tokio::test]
-    async fn test_receive_error_propagation() {
-        // This test demonstrates that errors are wrapped in Err(), not Some(Err())
-        // We'll verify this behavior when we have a mock stream that produces errors
+    async fn test_send_with_tools_subset_survives_auto_loop() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::ToolCalls(vec![
+                ("call_1".into(), "read".into(), serde_json::json!({})),
+                ("call_2".into(), "write".into(), serde_json::json!({})),
+            ]),
+            MockResponse::text("Done"),
+        ])
+        .await
+        .unwrap();
+
+        let wrote = Arc::new(AtomicBool::new(false));
+        let read = crate::tools::tool("read", "Read a file")
+            .build(|_| async { Ok(serde_json::json!("contents")) });
+        let write = {
+            let wrote = wrote.clone();
+            crate::tools::tool("write", "Write a file").build(move |_| {
+                let wrote = wrote.clone();
+                async move {
+                    wrote.store(true, Ordering::SeqCst);
+                    Ok(serde_json::json!("ok"))
+                }
+            })
+        };
         let options = AgentOptions::builder()
-            .system_prompt("Test")
             .model("test-model")
-            .base_url("http://localhost:1234/v1")
+            .base_url(server.base_url())
+            .tools(vec![read, write])
+            .auto_execute_tools(true)
             .build()
             .unwrap();
 
-        let client = Client::new(options).expect("Should create client successfully");
+        let mut client = Client::new(options).unwrap();
+        client.send_with_tools("Review", &["read"]).await.unwrap();
+        while client.receive().await.unwrap().is_some() {}
 
-        // Signature check: receive() returns Result<Option<ContentBlock>>
-        // This means we can use ? operator cleanly:
-        // while let Some(block) = client.receive().await? { ... }
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            let tools = request.body["tools"].as_array().unwrap();
+            assert_eq!(tools.len(), 1);
+            assert_eq!(tools[0]["function"]["name"], "read");
+        }
 
-        // Type assertion to ensure signature is correct
-        let _: Result<Option<ContentBlock>> = std::future::ready(Ok(None)).await;
-        drop(client);
+        // The model called a tool it wasn't offered: it gets an error, not a run
+        assert!(!wrote.load(Ordering::SeqCst));
+        let results = requests[1].body["messages"].to_string();
+        assert!(results.contains("contents"));
+        assert!(results.contains("not available for this turn"));
     }
 
-    #[test]
-    fn test_empty_content_parts_protection() {
-        // Test for Issue #3 - Verify empty content_parts causes appropriate handling
-        // This documents expected behavior: messages with images should have content
+    #[tokio::test]
+    async fn test_reasoning_effort_in_request() {
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .reasoning_effort(crate::ReasoningEffort::High)
+            .dry_run(true)
+            .build()
+            .unwrap();
 
-        use crate::types::{ContentBlock, ImageBlock, Message, MessageRole};
+        let response = query_complete("Hi", &options).await.unwrap();
+        let request: serde_json::Value = serde_json::from_str(&response.text).unwrap();
+        assert_eq!(request["reasoning_effort"], "high");
 
-        // GIVEN: Message with an image
-        let img = ImageBlock::from_url("https://example.com/test.jpg").expect("Valid URL");
+        let mut client = Client::new(options).unwrap();
+        client.send("Hi").await.unwrap();
+        let request = serde_json::to_value(client.last_request().unwrap()).unwrap();
+        assert_eq!(request["reasoning_effort"], "high");
 
-        let msg = Message::new(MessageRole::User, vec![ContentBlock::Image(img)]);
+        // Left out when unset
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .dry_run(true)
+            .build()
+            .unwrap();
+        let response = query_complete("Hi", &options).await.unwrap();
+        let request: serde_json::Value = serde_json::from_str(&response.text).unwrap();
+        assert!(request.get("reasoning_effort").is_none());
+    }
 
-        // WHEN: Building content_parts
-        let mut content_parts = Vec::new();
-        for block in &msg.content {
-            match block {
-                ContentBlock::Text(text) => {
-                    content_parts.push(crate::types::OpenAIContentPart::text(&text.text));
-                }
-                ContentBlock::Image(image) => {
-                    content_parts.push(crate::types::OpenAIContentPart::from_image(image));
+    #[cfg(any(test, feature = "content-filter"))]
+    #[tokio::test]
+    async fn test_tool_result_filters_apply_whatever_the_hooks_decide() {
+        use crate::hooks::{HookDecision, Hooks};
+
+        let hooks = Hooks::new()
+            // A plain continue ends the PostToolUse chain...
+            .add_post_tool_use(|_| async move { Some(HookDecision::continue_()) })
+            .add_tool_result_filter([r"\d{3}-\d{2}-\d{4}"])
+            .unwrap()
+            // ...and a block would have ended it too
+            .add_post_tool_use(|_| async move { Some(HookDecision::block("audit")) });
+        let tool = crate::tools::tool("lookup", "test")
+            .build(|_| async move { Ok(serde_json::json!({"078-05-1120": "SSN 078-05-1120"})) });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .auto_execute_tools(true)
+            .dry_run(true)
+            .hooks(hooks.clone())
+            .tool(tool)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        let call = crate::ToolUseBlock::new("call_1", "lookup", serde_json::json!({}));
+        client.current_stream = Some(Box::pin(futures::stream::iter(vec![Ok(
+            StreamEvent::Block(ContentBlock::ToolUse(call.clone())),
+        )])));
+        client.receive().await.unwrap();
+        let history = format!("{:?}", client.history());
+        assert!(history.contains("[REDACTED]"));
+        assert!(!history.contains("078-05-1120"));
+
+        // Manually added results go through the same filters
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .hooks(hooks)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        client
+            .history
+            .push(Message::assistant(vec![ContentBlock::ToolUse(call)]));
+        client
+            .add_tool_result("call_1", serde_json::json!("SSN 078-05-1120"))
+            .unwrap();
+        let history = format!("{:?}", client.history());
+        assert!(history.contains("SSN [REDACTED]"));
+        assert!(!history.contains("078-05-1120"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_execution_skips_partial_tool_calls() {
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let tool = {
+            let runs = runs.clone();
+            crate::tools::tool("search", "test").build(move |_| {
+                let runs = runs.clone();
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(serde_json::json!({}))
                 }
-                ContentBlock::ToolUse(_) | ContentBlock::ToolResult(_) => {}
-            }
-        }
+            })
+        };
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url("http://localhost:1234/v1")
+            .auto_execute_tools(true)
+            .dry_run(true)
+            .tool(tool)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+
+        // One call repaired from `{"query": "hel`, one beyond repair
+        let blocks = vec![
+            crate::ToolUseBlock::new_partial(
+                0,
+                "call_1",
+                "search",
+                serde_json::json!({"query": "hel"}),
+            ),
+            crate::ToolUseBlock::new_partial(
+                1,
+                "call_2",
+                "search",
+                serde_json::json!({"_partial": "{\"query\": [1,", "_parse_error": "EOF"}),
+            ),
+        ];
+        client.current_stream = Some(Box::pin(futures::stream::iter(
+            blocks
+                .into_iter()
+                .map(|b| Ok(StreamEvent::Block(ContentBlock::ToolUse(b)))),
+        )));
+        client.receive().await.unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        let history = format!("{:?}", client.history());
+        assert!(history.contains("cut off mid-stream"));
+        assert!(history.contains("invalid arguments"));
+    }
 
-        // THEN: content_parts should not be empty
-        assert!(
-            !content_parts.is_empty(),
-            "Messages with images should produce non-empty content_parts"
+    #[tokio::test]
+    async fn test_tool_images_reach_the_model() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::tool_call("call_1", "plot", serde_json::json!({})),
+            MockResponse::text("Sales rose in May"),
+        ])
+        .await
+        .unwrap();
+        let plot = crate::tools::tool("plot", "Plot sales").build_with_images(|_| async {
+            let chart = crate::ImageBlock::from_base64("iVBORw0KGgo=", "image/png")?;
+            Ok(ToolOutput::new(serde_json::json!({"months": 12})).with_image(chart))
+        });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .auto_execute_tools(true)
+            .tool(plot)
+            .build()
+            .unwrap();
+        let mut client = Client::new(options).unwrap();
+        client.send("Plot sales").await.unwrap();
+        while client.receive().await.unwrap().is_some() {}
+
+        let messages = server.requests()[1].body["messages"].clone();
+        let roles: Vec<&str> = messages
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles[..4], ["user", "assistant", "tool", "user"]);
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+        assert_eq!(messages[2]["content"], r#"{"months":12}"#);
+        assert_eq!(
+            messages[3]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
         );
+
+        // Manual mode: the block keeps its call id and images
+        let mut client = Client::new(AgentOptions::default()).unwrap();
+        client
+            .append_message(Message::assistant(vec![ContentBlock::ToolUse(
+                ToolUseBlock::new("call_1", "plot", serde_json::json!({})),
+            )]))
+            .unwrap();
+        let chart = crate::ImageBlock::from_base64("iVBORw0KGgo=", "image/png").unwrap();
+        client
+            .add_tool_result_block(
+                ToolResultBlock::new("call_1", serde_json::json!({"months": 12})).with_image(chart),
+            )
+            .unwrap();
+        let messages = client.export_openai_messages().unwrap();
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["assistant", "tool", "user"]);
+        assert_eq!(messages[1].tool_call_id.as_deref(), Some("call_1"));
     }
 }
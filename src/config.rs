@@ -107,6 +107,37 @@ impl Provider {
             Provider::VLLM => "http://localhost:8000/v1",
         }
     }
+
+    /// Get the URL of this provider's tokenization endpoint, if it has one.
+    ///
+    /// llama.cpp and vLLM serve `/tokenize` at the server root, next to (not
+    /// under) the `/v1` API. LM Studio and Ollama have no such endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The OpenAI-compatible base URL the server is reached at
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::Provider;
+    ///
+    /// assert_eq!(
+    ///     Provider::LlamaCpp.tokenize_url("http://localhost:8080/v1").as_deref(),
+    ///     Some("http://localhost:8080/tokenize")
+    /// );
+    /// assert_eq!(Provider::Ollama.tokenize_url("http://localhost:11434/v1"), None);
+    /// ```
+    pub fn tokenize_url(&self, base_url: &str) -> Option<String> {
+        match self {
+            Provider::LlamaCpp | Provider::VLLM => {
+                let base = base_url.trim_end_matches('/');
+                let root = base.strip_suffix("/v1").unwrap_or(base);
+                Some(format!("{}/tokenize", root))
+            }
+            Provider::LMStudio | Provider::Ollama => None,
+        }
+    }
 }
 
 // ============================================================================
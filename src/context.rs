@@ -6,8 +6,10 @@
 //!
 //! # Features
 //!
-//! - Token estimation (character-based approximation)
-//! - Message truncation with system prompt preservation
+//! - Token estimation (character-based approximation, tunable per kind of text)
+//! - Tool-call aware message truncation with system prompt preservation
+//! - Merging of consecutive same-role messages for strict chat templates
+//! - Budget planning: check a message list against a token budget before sending
 //! - Manual history management patterns
 //!
 //! # Examples
@@ -27,7 +29,7 @@
 //! }
 //! ```
 
-use crate::types::Message;
+use crate::types::{ContentBlock, ImageBlock, ImageDetail, Message, MessageRole};
 
 /// Estimate token count for message list
 ///
@@ -74,14 +76,111 @@ use crate::types::Message;
 /// }
 /// ```
 pub fn estimate_tokens(messages: &[Message]) -> usize {
-    // Character-based approximation: 1 token ≈ 4 characters
-    // This is a conservative estimate that works across model families
+    estimate_messages_with_hint(messages, TokenizerHint::Generic)
+}
+
+/// The kind of text being estimated, for [`estimate_tokens_with_hint`]
+///
+/// Tokenizers split different text at very different rates: English prose
+/// averages about 4 characters per token, source code closer to 3 (symbols,
+/// indentation and short identifiers), and Chinese, Japanese or Korean text
+/// about one token per character. Picking the hint that matches your content
+/// keeps estimates from under-budgeting.
+///
+/// Whatever the hint, the result is still an estimate; see [`estimate_tokens`]
+/// for accuracy notes, and [`Client::count_prompt_tokens`](crate::Client::count_prompt_tokens)
+/// for exact counts from servers that expose a tokenizer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenizerHint {
+    /// Mixed or unknown content: 4 bytes per token, as [`estimate_tokens`] uses
+    #[default]
+    Generic,
+    /// English prose: 4 characters per token
+    English,
+    /// Source code and structured data: 3 characters per token
+    Code,
+    /// Chinese, Japanese and Korean text: 1 token per CJK character, 4 characters per token otherwise
+    Cjk,
+}
+
+impl TokenizerHint {
+    /// Character-equivalents of `text`, at the 4-per-token rate the estimate is summed in
+    fn char_equivalents(self, text: &str) -> f64 {
+        match self {
+            TokenizerHint::Generic => text.len() as f64,
+            TokenizerHint::English => text.chars().count() as f64,
+            TokenizerHint::Code => text.chars().count() as f64 * 4.0 / 3.0,
+            TokenizerHint::Cjk => text
+                .chars()
+                .map(|c| if is_cjk(c) { 4.0 } else { 1.0 })
+                .sum(),
+        }
+    }
+}
+
+/// Whether a character belongs to a CJK script (Han, kana, Hangul, CJK punctuation)
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2E80}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FF00}'..='\u{FFEF}'
+            | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Estimate the token count of a piece of text, using a hint about its kind
+///
+/// Applies the rate for the given [`TokenizerHint`] and rounds up. This is
+/// still an approximation, only a better one for text that is clearly code
+/// or clearly CJK.
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{TokenizerHint, estimate_tokens_with_hint};
+///
+/// let code = "fn main() { println!(\"{}\", 1 + 2); }";
+/// assert!(
+///     estimate_tokens_with_hint(code, TokenizerHint::Code)
+///         > estimate_tokens_with_hint(code, TokenizerHint::English)
+/// );
+///
+/// // One token per character for Japanese
+/// assert_eq!(estimate_tokens_with_hint("こんにちは", TokenizerHint::Cjk), 5);
+/// ```
+pub fn estimate_tokens_with_hint(text: &str, hint: TokenizerHint) -> usize {
+    (hint.char_equivalents(text) / 4.0).ceil() as usize
+}
+
+/// Estimate token count for a message list, using a hint about its text
+///
+/// Like [`estimate_tokens`], which is this function with
+/// [`TokenizerHint::Generic`], but message text and tool call JSON are
+/// counted at the hint's rate. Per-message overhead and image costs are the same.
+/// The client uses the hint set with
+/// [`AgentOptionsBuilder::tokenizer_hint`](crate::AgentOptionsBuilder::tokenizer_hint)
+/// for its own estimates.
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{Message, TokenizerHint, estimate_messages_with_hint, estimate_tokens};
+///
+/// let messages = vec![Message::user("let total: u32 = items.iter().map(|i| i.price).sum();")];
+/// assert!(estimate_messages_with_hint(&messages, TokenizerHint::Code) > estimate_tokens(&messages));
+/// ```
+pub fn estimate_messages_with_hint(messages: &[Message], hint: TokenizerHint) -> usize {
+    // Character-based approximation: 1 token ≈ 4 characters, with text
+    // scaled to that rate by the hint
 
     if messages.is_empty() {
         return 0;
     }
 
     let mut total_chars = 0;
+    let mut text_chars = 0.0;
 
     for message in messages {
         // Count role overhead (approximately 1-2 tokens)
@@ -91,29 +190,22 @@ pub fn estimate_tokens(messages: &[Message]) -> usize {
         for block in &message.content {
             match block {
                 crate::types::ContentBlock::Text(text) => {
-                    total_chars += text.text.len();
+                    text_chars += hint.char_equivalents(&text.text);
                 }
                 crate::types::ContentBlock::Image(image) => {
-                    // Token estimates based on OpenAI Vision API
-                    // Local models may have significantly different token costs
-                    use crate::types::ImageDetail;
-                    let token_estimate = match image.detail() {
-                        ImageDetail::Low => 85 * 4,   // Fixed ~85 tokens (512x512 max)
-                        ImageDetail::High => 300 * 4, // Conservative upper bound (variable based on dimensions)
-                        ImageDetail::Auto => 200 * 4, // Middle ground default
-                    };
-                    total_chars += token_estimate;
+                    total_chars += image_chars(image);
                 }
                 crate::types::ContentBlock::ToolUse(tool) => {
                     // Tool calls add significant overhead
                     total_chars += tool.name().len();
                     total_chars += tool.id().len();
-                    total_chars += tool.input().to_string().len();
+                    text_chars += hint.char_equivalents(&tool.input().to_string());
                 }
                 crate::types::ContentBlock::ToolResult(result) => {
                     // Tool results add overhead
                     total_chars += result.tool_use_id().len();
-                    total_chars += result.content().to_string().len();
+                    text_chars += hint.char_equivalents(&result.content().to_string());
+                    total_chars += result.images().iter().map(image_chars).sum::<usize>();
                 }
             }
         }
@@ -123,14 +215,35 @@ pub fn estimate_tokens(messages: &[Message]) -> usize {
     total_chars += 16;
 
     // Convert characters to tokens (4 chars ≈ 1 token, round up for safety)
-    total_chars.div_ceil(4)
+    ((total_chars as f64 + text_chars) / 4.0).ceil() as usize
+}
+
+/// Character-equivalent cost of an image, for [`estimate_tokens`]
+fn image_chars(image: &ImageBlock) -> usize {
+    // Token estimates based on OpenAI Vision API
+    // Local models may have significantly different token costs
+    match image.detail() {
+        ImageDetail::Low => 85 * 4,   // Fixed ~85 tokens (512x512 max)
+        ImageDetail::High => 300 * 4, // Conservative upper bound (variable based on dimensions)
+        ImageDetail::Auto => 200 * 4, // Middle ground default
+    }
 }
 
 /// Truncate message history, keeping recent messages
 ///
-/// Always preserves the system prompt (if present) and keeps the most
-/// recent N messages. This is a simple truncation - it does NOT attempt
-/// to preserve tool chains or important context.
+/// Always preserves the system prompt (if present) and keeps roughly the most
+/// recent N messages. Truncation operates on whole turns so the result is always
+/// valid to send back to the API:
+///
+/// - An assistant message that calls tools is never separated from the
+///   messages carrying its tool results
+/// - The kept history never starts with an orphaned tool result
+/// - When the cut would fall inside a tool cycle, the rest of that turn is
+///   dropped too, so the history starts at the next user message
+///
+/// Because of this, the result may hold fewer than `keep` messages. It only
+/// holds more when the most recent turn alone is a long tool cycle with no
+/// clean place to cut; that cycle is then kept whole.
 ///
 /// # Arguments
 ///
@@ -159,9 +272,10 @@ pub fn estimate_tokens(messages: &[Message]) -> usize {
 ///
 /// # Note
 ///
-/// This is a SIMPLE truncation. For domain-specific needs (e.g.,
-/// preserving tool call chains, keeping important context), implement
-/// your own logic or use this as a starting point.
+/// Only the system prompt and the most recent turns are kept. For
+/// domain-specific needs (e.g., keeping important earlier context or
+/// summarizing dropped turns), implement your own logic or use this as a
+/// starting point.
 ///
 /// Warning: Truncating mid-conversation may remove context that the
 /// model needs to properly respond. Use judiciously at natural breakpoints.
@@ -175,27 +289,108 @@ pub fn truncate_messages(messages: &[Message], keep: usize, preserve_system: boo
     }
 
     // Check if first message is system prompt
-    let has_system = preserve_system
-        && !messages.is_empty()
-        && messages[0].role == crate::types::MessageRole::System;
-
-    if has_system {
-        // Keep system + last N messages
-        let mut result = vec![messages[0].clone()];
-        if keep > 0 && messages.len() > 1 {
-            let start = messages.len().saturating_sub(keep);
-            result.extend_from_slice(&messages[start..]);
-        }
-        result
+    let has_system =
+        preserve_system && !messages.is_empty() && messages[0].role == MessageRole::System;
+
+    let mut result = Vec::new();
+    let body = if has_system {
+        result.push(messages[0].clone());
+        &messages[1..]
     } else {
-        // Just keep last N messages
-        if keep > 0 {
-            let start = messages.len().saturating_sub(keep);
-            messages[start..].to_vec()
-        } else {
-            Vec::new()
+        messages
+    };
+
+    if keep > 0 && !body.is_empty() {
+        let naive_start = body.len().saturating_sub(keep);
+        let start = turn_aligned_start(body, naive_start);
+        result.extend_from_slice(&body[start..]);
+    }
+
+    result
+}
+
+/// Moves a cut point so the kept tail starts on a turn boundary
+///
+/// A cut that doesn't land on a tool result is kept as is. Otherwise prefers the
+/// first user message after `start` (dropping the rest of the partially kept
+/// turn); failing that, any message that isn't a tool result; failing that, the
+/// nearest such message before `start`.
+fn turn_aligned_start(messages: &[Message], start: usize) -> usize {
+    let starts_turn = |m: &Message| m.role == MessageRole::User && !is_tool_result(m);
+    let can_start = |m: &Message| !is_tool_result(m);
+
+    if messages.get(start).is_none_or(can_start) {
+        return start;
+    }
+    if let Some(offset) = messages[start..].iter().position(starts_turn) {
+        return start + offset;
+    }
+    if let Some(offset) = messages[start..].iter().position(can_start) {
+        return start + offset;
+    }
+    messages[..start].iter().rposition(can_start).unwrap_or(0)
+}
+
+/// Whether a message carries tool results (and so depends on the message before it)
+fn is_tool_result(message: &Message) -> bool {
+    message.role == MessageRole::Tool
+        || message
+            .content
+            .iter()
+            .any(|block| matches!(block, ContentBlock::ToolResult(_)))
+}
+
+/// Merge adjacent messages that share a role
+///
+/// Some chat templates (Mistral, Gemma, and others) reject conversations
+/// where two consecutive messages have the same role, which easily happens
+/// after appending messages by hand. This merges each run of same-role
+/// messages into one message holding all of their content blocks, in order.
+///
+/// Messages carrying tool results are never merged: each result is sent as its
+/// own tool message keyed by `tool_call_id`, so combining them with neighbours
+/// would either cross those boundaries or drop text sent alongside them.
+///
+/// # Arguments
+///
+/// * `messages` - Messages to normalize
+///
+/// # Returns
+///
+/// A new vector with no two adjacent mergeable messages sharing a role
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{ContentBlock, Message, TextBlock, normalize_roles};
+///
+/// let messages = vec![
+///     Message::user("First question"),
+///     Message::user("Actually, one more thing"),
+///     Message::assistant(vec![ContentBlock::Text(TextBlock::new("Sure"))]),
+/// ];
+///
+/// let normalized = normalize_roles(&messages);
+/// assert_eq!(normalized.len(), 2);
+/// assert_eq!(normalized[0].content.len(), 2);
+/// ```
+pub fn normalize_roles(messages: &[Message]) -> Vec<Message> {
+    let mut result: Vec<Message> = Vec::with_capacity(messages.len());
+
+    for message in messages {
+        match result.last_mut() {
+            Some(previous)
+                if previous.role == message.role
+                    && !is_tool_result(previous)
+                    && !is_tool_result(message) =>
+            {
+                previous.content.extend(message.content.iter().cloned());
+            }
+            _ => result.push(message.clone()),
         }
     }
+
+    result
 }
 
 /// Check if history is approaching a token limit
@@ -224,15 +419,180 @@ pub fn truncate_messages(messages: &[Message], keep: usize, preserve_system: boo
 /// # }
 /// ```
 pub fn is_approaching_limit(messages: &[Message], limit: usize, margin: f32) -> bool {
-    let estimated = estimate_tokens(messages);
+    is_approaching_limit_with_hint(messages, limit, margin, TokenizerHint::Generic)
+}
+
+/// Check if history is approaching a token limit, using a hint about its text
+///
+/// Like [`is_approaching_limit`], which is this function with
+/// [`TokenizerHint::Generic`], but estimated with
+/// [`estimate_messages_with_hint`]. Pass
+/// [`AgentOptions::tokenizer_hint()`](crate::AgentOptions::tokenizer_hint) to
+/// check against the same estimate the client uses.
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{Message, TokenizerHint, is_approaching_limit_with_hint};
+///
+/// # fn example(messages: Vec<Message>) {
+/// if is_approaching_limit_with_hint(&messages, 32000, 0.9, TokenizerHint::Code) {
+///     println!("Time to truncate!");
+/// }
+/// # }
+/// ```
+pub fn is_approaching_limit_with_hint(
+    messages: &[Message],
+    limit: usize,
+    margin: f32,
+    hint: TokenizerHint,
+) -> bool {
+    let estimated = estimate_messages_with_hint(messages, hint);
     let threshold = (limit as f32 * margin) as usize;
     estimated > threshold
 }
 
+/// Outcome of [`token_budget_fit`]: whether messages fit a budget, and how to make them fit
+///
+/// All token counts are estimates from [`estimate_messages_with_hint`], at
+/// [`TokenizerHint::Generic`] unless planned with [`token_budget_fit_with_hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetPlan {
+    fits: bool,
+    estimated_tokens: usize,
+    available_tokens: usize,
+    recommended_keep: Option<usize>,
+}
+
+impl BudgetPlan {
+    /// Whether the messages fit as they are
+    pub fn fits(&self) -> bool {
+        self.fits
+    }
+
+    /// Estimated tokens of the messages as they are
+    pub fn estimated_tokens(&self) -> usize {
+        self.estimated_tokens
+    }
+
+    /// Tokens available to the prompt: the budget minus the completion reserve
+    pub fn available_tokens(&self) -> usize {
+        self.available_tokens
+    }
+
+    /// Estimated tokens over the available amount (0 when the messages fit)
+    pub fn overflow(&self) -> usize {
+        self.estimated_tokens.saturating_sub(self.available_tokens)
+    }
+
+    /// The largest `keep` for [`truncate_messages`] (with `preserve_system`) whose result fits
+    ///
+    /// `None` when the messages already fit, and also when no truncation
+    /// helps because the system prompt alone is over budget. Check
+    /// [`fits`](Self::fits) to tell the two apart; the second case calls for
+    /// summarizing or a shorter system prompt instead.
+    pub fn recommended_keep(&self) -> Option<usize> {
+        self.recommended_keep
+    }
+}
+
+/// Plan whether a message list fits a token budget before sending it
+///
+/// Subtracts `reserve_completion` (room left for the model's reply) from
+/// `budget` and compares the estimated size of `messages` against the rest.
+/// When the messages don't fit, finds the largest `keep` that makes
+/// [`truncate_messages`]`(messages, keep, true)` fit, so the same turn-aware
+/// truncation can be applied directly.
+///
+/// Pure planning: nothing is modified or sent.
+///
+/// # Arguments
+///
+/// * `messages` - Candidate messages for the next request
+/// * `budget` - Context window size in tokens
+/// * `reserve_completion` - Tokens to keep free for the completion
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{Message, token_budget_fit, truncate_messages};
+///
+/// let mut messages = vec![Message::system("You are a helpful assistant")];
+/// for i in 0..50 {
+///     messages.push(Message::user(format!("Question {i}: {}", "x".repeat(400))));
+/// }
+///
+/// let plan = token_budget_fit(&messages, 4096, 1024);
+/// if !plan.fits() {
+///     match plan.recommended_keep() {
+///         Some(keep) => messages = truncate_messages(&messages, keep, true),
+///         None => { /* summarize instead */ }
+///     }
+/// }
+/// assert!(token_budget_fit(&messages, 4096, 1024).fits());
+/// ```
+pub fn token_budget_fit(
+    messages: &[Message],
+    budget: usize,
+    reserve_completion: usize,
+) -> BudgetPlan {
+    token_budget_fit_with_hint(messages, budget, reserve_completion, TokenizerHint::Generic)
+}
+
+/// Plan whether a message list fits a token budget, using a hint about its text
+///
+/// Like [`token_budget_fit`], which is this function with
+/// [`TokenizerHint::Generic`], but every estimate (including the one behind
+/// [`recommended_keep`](BudgetPlan::recommended_keep)) uses
+/// [`estimate_messages_with_hint`]. A programming assistant should pass
+/// [`TokenizerHint::Code`], or the configured
+/// [`AgentOptions::tokenizer_hint()`](crate::AgentOptions::tokenizer_hint), to
+/// avoid under-budgeting.
+///
+/// # Examples
+///
+/// ```rust
+/// use open_agent::{Message, TokenizerHint, token_budget_fit, token_budget_fit_with_hint};
+///
+/// let messages = vec![Message::user("fn main() { println!(\"{}\", 1 + 1); }".repeat(40))];
+///
+/// let generic = token_budget_fit(&messages, 1024, 256);
+/// let code = token_budget_fit_with_hint(&messages, 1024, 256, TokenizerHint::Code);
+/// assert!(code.estimated_tokens() > generic.estimated_tokens());
+/// ```
+pub fn token_budget_fit_with_hint(
+    messages: &[Message],
+    budget: usize,
+    reserve_completion: usize,
+    hint: TokenizerHint,
+) -> BudgetPlan {
+    let available_tokens = budget.saturating_sub(reserve_completion);
+    let estimated_tokens = estimate_messages_with_hint(messages, hint);
+    let fits = estimated_tokens <= available_tokens;
+
+    // Keeping fewer messages never grows the estimate, so the first fit from the top is the largest
+    let recommended_keep = if fits {
+        None
+    } else {
+        (0..messages.len()).rev().find(|&keep| {
+            estimate_messages_with_hint(&truncate_messages(messages, keep, true), hint)
+                <= available_tokens
+        })
+    };
+
+    BudgetPlan {
+        fits,
+        estimated_tokens,
+        available_tokens,
+        recommended_keep,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ContentBlock, Message, MessageRole, TextBlock};
+    use crate::types::{TextBlock, ToolResultBlock, ToolUseBlock};
+    use serde_json::json;
 
     #[test]
     fn test_estimate_tokens_empty() {
@@ -300,6 +660,101 @@ mod tests {
         assert_eq!(truncated.len(), 2);
     }
 
+    /// Asserts every tool result answers a tool call in the preceding assistant
+    /// message, and every tool call is answered
+    fn assert_api_valid(messages: &[Message]) {
+        let mut pending: Vec<String> = Vec::new();
+
+        for message in messages {
+            let results: Vec<&str> = message
+                .content
+                .iter()
+                .filter_map(|b| match b {
+                    ContentBlock::ToolResult(r) => Some(r.tool_use_id()),
+                    _ => None,
+                })
+                .collect();
+
+            if results.is_empty() {
+                assert!(pending.is_empty(), "unanswered tool calls: {:?}", pending);
+            }
+            for id in results {
+                let pos = pending.iter().position(|p| p == id);
+                assert!(pos.is_some(), "orphaned tool result '{}'", id);
+                pending.remove(pos.unwrap());
+            }
+
+            if message.role == MessageRole::Assistant {
+                pending.extend(message.content.iter().filter_map(|b| match b {
+                    ContentBlock::ToolUse(t) => Some(t.id().to_string()),
+                    _ => None,
+                }));
+            }
+        }
+    }
+
+    fn tool_cycle(id: &str, calls: usize) -> Vec<Message> {
+        let mut cycle = vec![Message::user(format!("Question {}", id))];
+        let uses = (0..calls)
+            .map(|i| {
+                ContentBlock::ToolUse(ToolUseBlock::new(
+                    format!("{}_{}", id, i),
+                    "lookup",
+                    json!({}),
+                ))
+            })
+            .collect();
+        cycle.push(Message::assistant(uses));
+        for i in 0..calls {
+            cycle.push(Message::user_with_blocks(vec![ContentBlock::ToolResult(
+                ToolResultBlock::new(format!("{}_{}", id, i), json!({"ok": true})),
+            )]));
+        }
+        cycle.push(Message::assistant(vec![ContentBlock::Text(
+            TextBlock::new("Answer"),
+        )]));
+        cycle
+    }
+
+    #[test]
+    fn test_truncate_messages_keeps_tool_cycles_intact() {
+        let mut messages = vec![Message::system("System prompt")];
+        messages.extend(tool_cycle("a", 1));
+        messages.push(Message::user("Plain question"));
+        messages.push(Message::assistant(vec![ContentBlock::Text(
+            TextBlock::new("Plain answer"),
+        )]));
+        messages.extend(tool_cycle("b", 3));
+        messages.extend(tool_cycle("c", 2));
+        assert_api_valid(&messages);
+
+        for keep in 0..=messages.len() {
+            for preserve_system in [true, false] {
+                let truncated = truncate_messages(&messages, keep, preserve_system);
+                assert_api_valid(&truncated);
+
+                if preserve_system {
+                    assert_eq!(truncated[0].role, MessageRole::System);
+                }
+                if keep > 0 {
+                    // The most recent turn is always retained
+                    assert_eq!(
+                        serde_json::to_value(truncated.last()).unwrap(),
+                        serde_json::to_value(messages.last()).unwrap()
+                    );
+                }
+            }
+        }
+
+        // Cutting inside cycle "b" drops the rest of it rather than orphaning results
+        let truncated = truncate_messages(&messages, 7, true);
+        assert_eq!(truncated.len(), 6);
+        assert_eq!(
+            serde_json::to_value(&truncated[1]).unwrap(),
+            serde_json::to_value(&messages[messages.len() - 5]).unwrap()
+        );
+    }
+
     #[test]
     fn test_is_approaching_limit() {
         let messages = vec![Message::user("x".repeat(1000))];
@@ -364,4 +819,114 @@ mod tests {
             token_count
         );
     }
+
+    #[test]
+    fn test_normalize_roles_merges_runs_but_not_tool_results() {
+        let mut messages = vec![Message::system("System prompt"), Message::user("Context")];
+        // Two parallel tool results in a row must stay separate messages
+        messages.extend(tool_cycle("a", 2));
+        messages.push(Message::assistant(vec![ContentBlock::Text(
+            TextBlock::new("Anything else?"),
+        )]));
+
+        let normalized = normalize_roles(&messages);
+
+        // "Context" merges into "Question a"; the trailing assistant texts merge
+        assert_eq!(normalized.len(), messages.len() - 2);
+        assert_eq!(normalized[1].content.len(), 2);
+        assert_eq!(normalized.last().unwrap().content.len(), 2);
+        let tool_results = normalized.iter().filter(|m| is_tool_result(m)).count();
+        assert_eq!(tool_results, 2);
+        assert_api_valid(&normalized);
+    }
+
+    #[test]
+    fn test_token_budget_fit() {
+        let mut messages = vec![Message::system("Be brief")];
+        for _ in 0..10 {
+            messages.push(Message::user("x".repeat(400)));
+        }
+
+        let plan = token_budget_fit(&messages, 2000, 500);
+        assert!(plan.fits());
+        assert_eq!(plan.available_tokens(), 1500);
+        assert_eq!(plan.overflow(), 0);
+        assert_eq!(plan.recommended_keep(), None);
+
+        // ~100 tokens per message: only a few fit in 400
+        let plan = token_budget_fit(&messages, 800, 400);
+        assert!(!plan.fits());
+        assert_eq!(plan.overflow(), plan.estimated_tokens() - 400);
+        let keep = plan.recommended_keep().unwrap();
+        assert_eq!(keep, 3);
+        let truncated = truncate_messages(&messages, keep, true);
+        assert!(estimate_tokens(&truncated) <= 400);
+        assert!(estimate_tokens(&truncate_messages(&messages, keep + 1, true)) > 400);
+
+        // A system prompt over budget can't be fixed by truncation
+        let messages = vec![Message::system("x".repeat(4000)), Message::user("Hi")];
+        let plan = token_budget_fit(&messages, 500, 100);
+        assert!(!plan.fits());
+        assert_eq!(plan.recommended_keep(), None);
+    }
+
+    #[test]
+    fn test_estimate_with_hint() {
+        let prose = "The quick brown fox jumps over the lazy dog";
+        assert_eq!(estimate_tokens_with_hint(prose, TokenizerHint::English), 11);
+        assert_eq!(estimate_tokens_with_hint(prose, TokenizerHint::Code), 15);
+        assert_eq!(estimate_tokens_with_hint("", TokenizerHint::Code), 0);
+
+        // Bytes vs characters vs one token per CJK character
+        let mixed = "東京 is 晴れ";
+        assert_eq!(estimate_tokens_with_hint(mixed, TokenizerHint::Generic), 4);
+        assert_eq!(estimate_tokens_with_hint(mixed, TokenizerHint::English), 2);
+        assert_eq!(estimate_tokens_with_hint(mixed, TokenizerHint::Cjk), 5);
+
+        // Generic matches estimate_tokens exactly
+        let messages = vec![Message::system("Be brief"), Message::user(mixed)];
+        assert_eq!(
+            estimate_messages_with_hint(&messages, TokenizerHint::Generic),
+            estimate_tokens(&messages)
+        );
+        assert!(
+            estimate_messages_with_hint(&messages, TokenizerHint::Cjk)
+                > estimate_messages_with_hint(&messages, TokenizerHint::English)
+        );
+    }
+
+    #[test]
+    fn test_budget_helpers_use_hint() {
+        let code = "let total: u32 = items.iter().map(|i| i.price).sum();";
+        let mut messages = vec![Message::system("You write Rust")];
+        for _ in 0..10 {
+            messages.push(Message::user(code));
+        }
+
+        // A budget the generic estimate just fits
+        let budget = estimate_tokens(&messages);
+        assert!(estimate_messages_with_hint(&messages, TokenizerHint::Code) > budget);
+
+        let generic = token_budget_fit(&messages, budget, 0);
+        assert!(generic.fits());
+        assert_eq!(
+            token_budget_fit_with_hint(&messages, budget, 0, TokenizerHint::Generic),
+            generic
+        );
+
+        // The same history measured as code no longer fits
+        let plan = token_budget_fit_with_hint(&messages, budget, 0, TokenizerHint::Code);
+        assert!(!plan.fits());
+        let keep = plan.recommended_keep().unwrap();
+        let truncated = truncate_messages(&messages, keep, true);
+        assert!(estimate_messages_with_hint(&truncated, TokenizerHint::Code) <= budget);
+
+        assert!(!is_approaching_limit(&messages, budget, 1.0));
+        assert!(is_approaching_limit_with_hint(
+            &messages,
+            budget,
+            1.0,
+            TokenizerHint::Code
+        ));
+    }
 }
@@ -0,0 +1,200 @@
+//! Embeddings API for OpenAI-compatible servers
+//!
+//! This module talks to the `/embeddings` endpoint exposed by the same local
+//! servers the rest of the SDK uses (LM Studio, Ollama, llama.cpp, vLLM), so
+//! RAG pipelines can embed documents without a second HTTP stack.
+//!
+//! # Batching
+//!
+//! Every input in a call is sent in a single request. Servers cap how many
+//! inputs one request may carry (OpenAI allows 2048; local servers are often
+//! lower and also bound the total token count). Split large corpora into
+//! batches of a few hundred inputs and call [`embed`] once per batch.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use open_agent::embed;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let chunks = vec!["Rust is a systems language".to_string(), "Tokio is async".to_string()];
+//!
+//! let vectors = embed(
+//!     &chunks,
+//!     "nomic-embed-text",
+//!     "http://localhost:11434/v1",
+//!     "not-needed",
+//! )
+//! .await?;
+//!
+//! assert_eq!(vectors.len(), chunks.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{error_from_response, http_client_builder};
+use crate::{AgentOptions, Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Request payload for the `/embeddings` endpoint
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Response payload from the `/embeddings` endpoint
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+/// A single embedding in the response, tagged with its input position
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+/// Embed a batch of inputs using an OpenAI-compatible `/embeddings` endpoint
+///
+/// Returns one vector per input, in the same order as `input`. An empty
+/// `input` returns an empty result without contacting the server.
+///
+/// All inputs go out in a single request. Servers cap how many inputs one
+/// request may carry (OpenAI allows 2048; local servers are often lower and
+/// also bound the total token count), so split large corpora into batches of a
+/// few hundred inputs and call `embed` once per batch.
+///
+/// # Arguments
+///
+/// * `input` - Texts to embed
+/// * `model` - Embedding model name (usually different from the chat model)
+/// * `base_url` - Server base URL, e.g. `http://localhost:1234/v1`
+/// * `api_key` - API key; local servers accept any value
+///
+/// The request uses the SDK's default HTTP settings (60 second timeout, TCP
+/// keepalive, User-Agent). For large batches on a slow server, use
+/// [`Client::embed`](crate::Client::embed) with a larger
+/// [`timeout`](crate::AgentOptionsBuilder::timeout).
+///
+/// # Errors
+///
+/// Returns an error on network failures, non-success HTTP status
+/// ([`Error::RateLimited`] for 429), or if the response doesn't contain
+/// exactly one embedding per input.
+pub async fn embed(
+    input: &[String],
+    model: &str,
+    base_url: &str,
+    api_key: &str,
+) -> Result<Vec<Vec<f32>>> {
+    let client = http_client_builder(&AgentOptions::default())
+        .build()
+        .map_err(Error::Http)?;
+    embed_with_client(&client, input, model, base_url, api_key).await
+}
+
+/// Embed a batch of inputs through an existing HTTP client
+///
+/// Shared by [`embed`] and `Client::embed`, which reuses the client's
+/// configured connection pool and timeout.
+pub(crate) async fn embed_with_client(
+    client: &reqwest::Client,
+    input: &[String],
+    model: &str,
+    base_url: &str,
+    api_key: &str,
+) -> Result<Vec<Vec<f32>>> {
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let url = format!("{}/embeddings", base_url.trim_end_matches('/'));
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&EmbeddingRequest { model, input })
+        .send()
+        .await
+        .map_err(Error::Http)?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    let body: EmbeddingResponse = response.json().await.map_err(Error::Http)?;
+    into_ordered_vectors(body, input.len())
+}
+
+/// Orders embeddings by their `index` and checks one was returned per input
+fn into_ordered_vectors(mut response: EmbeddingResponse, expected: usize) -> Result<Vec<Vec<f32>>> {
+    if response.data.len() != expected {
+        return Err(Error::api(format!(
+            "Expected {} embeddings, server returned {}",
+            expected,
+            response.data.len()
+        )));
+    }
+
+    // Servers may return embeddings out of order; `index` ties each back to its input
+    response.data.sort_by_key(|d| d.index);
+    Ok(response.data.into_iter().map(|d| d.embedding).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_response_ordering() {
+        let response: EmbeddingResponse = serde_json::from_str(
+            r#"{
+                "object": "list",
+                "data": [
+                    {"object": "embedding", "index": 1, "embedding": [0.3, 0.4]},
+                    {"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}
+                ],
+                "model": "nomic-embed-text"
+            }"#,
+        )
+        .unwrap();
+
+        let vectors = into_ordered_vectors(response, 2).unwrap();
+        assert_eq!(vectors, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    }
+
+    #[test]
+    fn test_embedding_count_mismatch() {
+        let response: EmbeddingResponse =
+            serde_json::from_str(r#"{"data": [{"embedding": [1.0]}]}"#).unwrap();
+
+        let err = into_ordered_vectors(response, 2).unwrap_err();
+        assert!(err.to_string().contains("Expected 2 embeddings"));
+    }
+
+    #[tokio::test]
+    async fn test_embed_empty_input_skips_request() {
+        // Unroutable URL: would fail if a request were attempted
+        let vectors = embed(&[], "model", "http://127.0.0.1:1/v1", "key")
+            .await
+            .unwrap();
+        assert!(vectors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_embed_trims_trailing_slash() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![MockResponse::error(503, "busy")])
+            .await
+            .unwrap();
+        let base_url = format!("{}/", server.base_url());
+
+        let result = embed(&["hello".to_string()], "model", &base_url, "not-needed").await;
+        assert!(result.is_err());
+        assert_eq!(server.requests()[0].path, "/v1/embeddings");
+    }
+}
@@ -29,6 +29,7 @@
 //! }
 //! ```
 
+use std::time::Duration;
 use thiserror::Error;
 
 // ============================================================================
@@ -73,6 +74,11 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// - **Tool**: Tool execution or registration failures
 /// - **InvalidInput**: User-provided input validation failures
 /// - **Timeout**: Request timeout exceeded
+/// - **RateLimited**: The server rejected the request with HTTP 429
+/// - **CircuitOpen**: The circuit breaker is failing requests fast
+/// - **ContentFiltered**: The server's safety layer blocked the response
+/// - **ModelNotFound**: The server doesn't know the requested model
+/// - **ModelNotLoaded**: The server has no model loaded to serve the request
 /// - **Other**: Catch-all for miscellaneous errors
 ///
 /// ## Automatic Conversions
@@ -214,6 +220,128 @@ pub enum Error {
     #[error("Request timeout")]
     Timeout,
 
+    /// The server rejected the request because of rate limiting (HTTP 429).
+    ///
+    /// Carries the server's error body and the rate-limit information it sent
+    /// back, when present:
+    /// - `message`: the response body, e.g. which limit was hit
+    /// - `retry_after`: parsed from the `retry-after` header (in seconds)
+    /// - `remaining`: parsed from `x-ratelimit-remaining` (or
+    ///   `x-ratelimit-remaining-requests`)
+    ///
+    /// The retry helpers in [`crate::retry`] sleep for `retry_after` instead of
+    /// the computed backoff when it is available.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// match client.send("Hello").await {
+    ///     Err(Error::RateLimited { retry_after: Some(wait), .. }) => sleep(wait).await,
+    ///     other => other?,
+    /// }
+    /// ```
+    #[error("Rate limited: {message} (retry after: {retry_after:?}, remaining: {remaining:?})")]
+    RateLimited {
+        /// The server's error response body
+        message: String,
+        /// How long the server asked us to wait before retrying
+        retry_after: Option<Duration>,
+        /// How many requests the server says are left in the current window
+        remaining: Option<u32>,
+    },
+
+    /// The request was not sent because the circuit breaker is open.
+    ///
+    /// Returned without any network activity after repeated failures against the
+    /// endpoint (see [`CircuitBreaker`](crate::CircuitBreaker)). `retry_after` is the
+    /// time left before the breaker lets a probe request through.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if let Err(Error::CircuitOpen { retry_after }) = client.send("Hello").await {
+    ///     println!("Server is down, try again in {:?}", retry_after);
+    /// }
+    /// ```
+    #[error("Circuit breaker open: endpoint unavailable (retry after {retry_after:?})")]
+    CircuitOpen {
+        /// Time until the breaker allows a probe request
+        retry_after: Duration,
+    },
+
+    /// The server stopped generation because its content filter blocked the response.
+    ///
+    /// Reported when a stream ends with `finish_reason: "content_filter"`. Without
+    /// this, a filtered response would look like an ordinary (possibly empty)
+    /// reply. Any text streamed before the filter triggered is discarded.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// match client.receive().await {
+    ///     Err(Error::ContentFiltered) => println!("The request was refused by the server"),
+    ///     other => { other?; }
+    /// }
+    /// ```
+    #[error("Response blocked by the server's content filter")]
+    ContentFiltered,
+
+    /// The server doesn't have the requested model.
+    ///
+    /// Recognized from the error bodies of Ollama ("model 'x' not found, try
+    /// pulling it first") and of OpenAI-style servers such as vLLM
+    /// (`model_not_found`, "The model `x` does not exist"). `suggestion` holds a
+    /// fix to show the user when one is known, e.g. ``run `ollama pull x` ``.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if let Err(Error::ModelNotFound { suggestion: Some(fix), .. }) = client.send("Hi").await {
+    ///     eprintln!("Model missing: {}", fix);
+    /// }
+    /// ```
+    #[error("Model not found: {message}")]
+    ModelNotFound {
+        /// The server's error message
+        message: String,
+        /// How to make the model available, if known
+        suggestion: Option<String>,
+    },
+
+    /// The server is up but has no model loaded.
+    ///
+    /// Recognized from LM Studio's "No models loaded" error. Load a model in the
+    /// server (for LM Studio, in the app or with `lms load`) and retry.
+    #[error("No model loaded on the server: {0}")]
+    ModelNotLoaded(String),
+
+    /// The model's arguments for a tool call aren't valid JSON.
+    ///
+    /// Produced when the accumulated argument string can't be parsed, even after
+    /// repairing a truncated stream. Carries the tool name, the call's position
+    /// in the response and the raw argument text so malformed calls can be
+    /// logged and diagnosed. See
+    /// [`ToolUseBlock::arguments_error`](crate::ToolUseBlock::arguments_error).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// if let Some(Error::ToolArguments { tool, index, raw, .. }) = tool_use.arguments_error() {
+    ///     log::warn!("{} (call {:?}) sent bad arguments: {}", tool, index, raw);
+    /// }
+    /// ```
+    #[error("Tool `{tool}`{} returned invalid arguments: {source}", call_position(.index))]
+    ToolArguments {
+        /// Name of the tool the model called
+        tool: String,
+        /// Zero-based position of the call among the response's tool calls, if known
+        index: Option<u32>,
+        /// The argument text as the model produced it
+        raw: String,
+        /// The JSON parse error
+        source: serde_json::Error,
+    },
+
     /// Miscellaneous error that doesn't fit other categories.
     ///
     /// Catch-all variant for unexpected errors or edge cases that don't fit
@@ -365,6 +493,50 @@ impl Error {
         Error::Other(msg.into())
     }
 
+    /// Create a rate-limit error with the server-provided retry information.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    /// use std::time::Duration;
+    ///
+    /// let err = Error::rate_limited("Too many requests", Some(Duration::from_secs(2)), Some(0));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Rate limited: Too many requests (retry after: Some(2s), remaining: Some(0))"
+    /// );
+    /// ```
+    pub fn rate_limited(
+        message: impl Into<String>,
+        retry_after: Option<Duration>,
+        remaining: Option<u32>,
+    ) -> Self {
+        Error::RateLimited {
+            message: message.into(),
+            retry_after,
+            remaining,
+        }
+    }
+
+    /// Create a circuit-open error with the time left in the cooldown.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    /// use std::time::Duration;
+    ///
+    /// let err = Error::circuit_open(Duration::from_secs(10));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Circuit breaker open: endpoint unavailable (retry after 10s)"
+    /// );
+    /// ```
+    pub fn circuit_open(retry_after: Duration) -> Self {
+        Error::CircuitOpen { retry_after }
+    }
+
     /// Create a timeout error indicating the operation exceeded the time limit.
     ///
     /// Use this when the request or operation takes longer than the configured
@@ -381,6 +553,148 @@ impl Error {
     pub fn timeout() -> Self {
         Error::Timeout
     }
+
+    /// Create an error for a response blocked by the server's content filter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    ///
+    /// let err = Error::content_filtered();
+    /// assert!(matches!(err, Error::ContentFiltered));
+    /// ```
+    pub fn content_filtered() -> Self {
+        Error::ContentFiltered
+    }
+
+    /// Create an error for a model the server doesn't have.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    ///
+    /// let err = Error::model_not_found("model 'llama3' not found", Some("run `ollama pull llama3`".into()));
+    /// assert_eq!(err.to_string(), "Model not found: model 'llama3' not found");
+    /// ```
+    pub fn model_not_found(message: impl Into<String>, suggestion: Option<String>) -> Self {
+        Error::ModelNotFound {
+            message: message.into(),
+            suggestion,
+        }
+    }
+
+    /// Create an error for a server with no model loaded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    ///
+    /// let err = Error::model_not_loaded("No models loaded");
+    /// assert!(matches!(err, Error::ModelNotLoaded(_)));
+    /// ```
+    pub fn model_not_loaded(msg: impl Into<String>) -> Self {
+        Error::ModelNotLoaded(msg.into())
+    }
+
+    /// Create an error for tool call arguments that aren't valid JSON.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Error;
+    ///
+    /// let raw = r#"{"query" "rust"}"#;
+    /// let source = serde_json::from_str::<serde_json::Value>(raw).unwrap_err();
+    /// let err = Error::tool_arguments("search", Some(1), raw, source);
+    /// assert!(err
+    ///     .to_string()
+    ///     .starts_with("Tool `search` (call 1) returned invalid arguments"));
+    /// ```
+    pub fn tool_arguments(
+        tool: impl Into<String>,
+        index: Option<u32>,
+        raw: impl Into<String>,
+        source: serde_json::Error,
+    ) -> Self {
+        Error::ToolArguments {
+            tool: tool.into(),
+            index,
+            raw: raw.into(),
+            source,
+        }
+    }
+
+    /// Recognizes model availability errors in a server's error response body.
+    ///
+    /// Returns `None` for any other error, so the caller can fall back to a
+    /// generic [`Error::Api`].
+    pub(crate) fn from_model_error_body(body: &str) -> Option<Self> {
+        let message = error_message(body);
+        let lower = message.to_lowercase();
+
+        if lower.contains("no models loaded") || lower.contains("no model loaded") {
+            return Some(Error::model_not_loaded(message));
+        }
+
+        // Ollama: model "llama3" not found, try pulling it first
+        if lower.contains("not found") && lower.contains("pull") {
+            let suggestion =
+                quoted_name(&message).map(|name| format!("run `ollama pull {}`", name));
+            return Some(Error::model_not_found(message, suggestion));
+        }
+
+        // OpenAI and vLLM: The model `x` does not exist.
+        if lower.contains("model_not_found")
+            || (lower.contains("model") && lower.contains("does not exist"))
+        {
+            return Some(Error::model_not_found(message, None));
+        }
+
+        None
+    }
+}
+
+/// Extracts the message from an error body.
+///
+/// Handles `{"error": "..."}` (Ollama, LM Studio) and
+/// `{"error": {"message": "...", "code": "..."}}` (OpenAI, vLLM). The code is
+/// kept alongside the message so it can be matched too. Anything else is
+/// returned as is.
+fn error_message(body: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.to_string();
+    };
+
+    match value.get("error") {
+        Some(serde_json::Value::String(message)) => message.clone(),
+        Some(error) => {
+            let message = error.get("message").and_then(|m| m.as_str());
+            let code = error.get("code").and_then(|c| c.as_str());
+            match (message, code) {
+                (Some(message), Some(code)) => format!("{} ({})", message, code),
+                (Some(message), None) => message.to_string(),
+                _ => body.to_string(),
+            }
+        }
+        None => body.to_string(),
+    }
+}
+
+/// Formats a tool call's position for [`Error::ToolArguments`] messages.
+fn call_position(index: &Option<u32>) -> String {
+    index.map_or_else(String::new, |index| format!(" (call {})", index))
+}
+
+/// Returns the first name in single or double quotes or backticks.
+fn quoted_name(message: &str) -> Option<&str> {
+    let start = message.find(['\'', '"', '`'])?;
+    let quote = message[start..].chars().next()?;
+    let rest = &message[start + 1..];
+    let end = rest.find(quote)?;
+    Some(&rest[..end]).filter(|name| !name.is_empty())
 }
 
 // ============================================================================
@@ -426,6 +740,41 @@ mod tests {
         assert_eq!(err.to_string(), "Invalid input: Missing parameter");
     }
 
+    #[test]
+    fn test_error_rate_limited() {
+        let err = Error::rate_limited("slow down", Some(Duration::from_secs(5)), None);
+        assert!(matches!(
+            &err,
+            Error::RateLimited {
+                message,
+                retry_after: Some(d),
+                remaining: None
+            } if message == "slow down" && *d == Duration::from_secs(5)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Rate limited: slow down (retry after: Some(5s), remaining: None)"
+        );
+    }
+
+    #[test]
+    fn test_error_circuit_open() {
+        let err = Error::circuit_open(Duration::from_millis(1500));
+        assert!(
+            matches!(err, Error::CircuitOpen { retry_after } if retry_after == Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn test_error_content_filtered() {
+        let err = Error::content_filtered();
+        assert!(matches!(err, Error::ContentFiltered));
+        assert_eq!(
+            err.to_string(),
+            "Response blocked by the server's content filter"
+        );
+    }
+
     #[test]
     fn test_error_timeout() {
         let err = Error::timeout();
@@ -469,4 +818,40 @@ mod tests {
             Err(Error::timeout())
         }
     }
+
+    #[test]
+    fn test_model_errors_from_server_bodies() {
+        // Ollama
+        let err = Error::from_model_error_body(
+            r#"{"error":"model \"llama3\" not found, try pulling it first"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            &err,
+            Error::ModelNotFound { suggestion: Some(fix), .. } if fix == "run `ollama pull llama3`"
+        ));
+
+        // vLLM / OpenAI
+        let err = Error::from_model_error_body(
+            r#"{"error":{"message":"The model `qwen` does not exist.","type":"NotFoundError","code":404}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            err,
+            Error::ModelNotFound {
+                suggestion: None,
+                ..
+            }
+        ));
+
+        // LM Studio
+        let err = Error::from_model_error_body(
+            r#"{"error":"No models loaded. Please load a model in the developer page or use the `lms load` command."}"#,
+        )
+        .unwrap();
+        assert!(matches!(err, Error::ModelNotLoaded(_)));
+
+        assert!(Error::from_model_error_body(r#"{"error":"context length exceeded"}"#).is_none());
+        assert!(Error::from_model_error_body("Bad Gateway").is_none());
+    }
 }
@@ -7,7 +7,8 @@
 //!
 //! # Overview
 //!
-//! The hooks system operates on an event-driven model with three key interception points:
+//! The hooks system operates on an event-driven model with four key interception points,
+//! plus a notification for history changes:
 //!
 //! 1. **PreToolUse**: Fired before any tool is executed, allowing you to:
 //!    - Block dangerous operations (security gates)
@@ -17,7 +18,9 @@
 //!
 //! 2. **PostToolUse**: Fired after tool execution completes, allowing you to:
 //!    - Audit tool results
-//!    - Filter or redact sensitive information in outputs
+//!    - Filter or redact sensitive information in outputs (for compliance
+//!      redaction, prefer the `Hooks::add_tool_result_filter` filters, which
+//!      no hook can bypass)
 //!    - Collect metrics and telemetry
 //!    - Validate tool behavior
 //!
@@ -27,17 +30,32 @@
 //!    - Implement content moderation
 //!    - Track user interactions
 //!
+//! 4. **PostReceive**: Fired for each content block before `receive()` returns it, allowing you to:
+//!    - Redact secrets the model echoes back
+//!    - Filter profanity or other unwanted output
+//!    - Drop blocks the caller should never see
+//!
+//! 5. **HistoryChanged**: Fired for each message auto-execution adds to history, allowing you to:
+//!    - Persist the conversation incrementally while tools run
+//!    - Show tool calls and results in a UI as they happen
+//!
+//!    These handlers only observe; they don't return a [`HookDecision`].
+//!
 //! # Execution Model
 //!
-//! Hooks follow a **sequential "first non-None wins"** execution model:
-//! - Hooks are executed in the order they were registered
-//! - Each hook can return `None` (pass-through) or `Some(HookDecision)` (take control)
-//! - The **first hook** that returns `Some(HookDecision)` determines the outcome
-//! - Subsequent hooks are **not executed** after a decision is made
+//! Hooks of the same type run **sequentially, in registration order**:
+//! - Each hook can return `None` (pass-through) or `Some(HookDecision)`
+//! - The **first blocking decision wins**: later hooks are **not executed**
+//! - **Modifications compose**: a hook that modifies the input, result, prompt or
+//!   block hands the modified event to the next hook, and the last modification
+//!   is the one applied
+//! - A plain [`HookDecision::continue_()`] ends the chain, keeping any earlier
+//!   modifications
 //! - If all hooks return `None`, execution continues normally
 //!
 //! This model ensures predictable behavior and allows you to create hook chains where
-//! earlier hooks can implement critical security checks that later hooks cannot override.
+//! earlier hooks can implement critical security checks that later hooks cannot override,
+//! while independent rewrites (redaction, then formatting) stack.
 //!
 //! # Common Use Cases
 //!
@@ -155,6 +173,11 @@
 //! }
 //! ```
 
+use crate::types::{ContentBlock, Message};
+#[cfg(any(test, feature = "content-filter"))]
+use crate::{Error, Result};
+#[cfg(any(test, feature = "content-filter"))]
+use regex::Regex;
 use serde_json::Value;
 use std::future::Future;
 use std::pin::Pin;
@@ -329,9 +352,9 @@ impl PreToolUseEvent {
 ///
 /// # Note on Modification
 ///
-/// While `HookDecision` theoretically allows modification in PostToolUse hooks, this is
-/// rarely used in practice. The tool has already executed, and most agents don't support
-/// modifying historical results. PostToolUse hooks are primarily for observation and auditing.
+/// PostToolUse hooks can replace the result the model sees by returning
+/// [`HookDecision::modify_tool_result`]. The tool has already run, so its side
+/// effects stay; only what is recorded in history changes.
 #[derive(Debug, Clone)]
 pub struct PostToolUseEvent {
     /// Name of the tool that was executed
@@ -471,33 +494,136 @@ impl UserPromptSubmitEvent {
     }
 }
 
+/// Event fired for each content block **before** `receive()` returns it to the caller.
+///
+/// This is the output-side counterpart to [`UserPromptSubmitEvent`]: it lets you apply
+/// redaction or filtering centrally instead of at every call site. The event fires for
+/// every block the model streams, including tool-use blocks, so match on the variant
+/// you care about and return `None` for the rest.
+///
+/// # Decisions
+///
+/// - `None` or [`HookDecision::continue_()`]: Pass the block through unchanged
+/// - [`HookDecision::modify_block()`]: Replace the block before the caller sees it
+/// - [`HookDecision::block()`]: Drop the block entirely; `receive()` moves on to the next one
+///
+/// In auto-execution mode the (possibly replaced) blocks are also what gets recorded in
+/// the conversation history, so redacted text never reaches later requests either.
+///
+/// # Performance
+///
+/// Handlers run inline on the streaming path for every block. Keep them cheap and
+/// avoid I/O; anything slow here delays every token the caller sees.
+///
+/// # Example: Redacting API Keys
+///
+/// ```rust
+/// use open_agent::{ContentBlock, HookDecision, PostReceiveEvent, TextBlock};
+///
+/// async fn redact_keys(event: PostReceiveEvent) -> Option<HookDecision> {
+///     let ContentBlock::Text(text) = &event.block else {
+///         return None;
+///     };
+///     if !text.text.contains("sk-") {
+///         return None;
+///     }
+///     let redacted = text
+///         .text
+///         .split(' ')
+///         .map(|word| if word.starts_with("sk-") { "[REDACTED]" } else { word })
+///         .collect::<Vec<_>>()
+///         .join(" ");
+///     Some(HookDecision::modify_block(
+///         ContentBlock::Text(TextBlock::new(redacted)),
+///         "Redacted API key",
+///     ))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PostReceiveEvent {
+    /// The content block about to be returned to the caller
+    pub block: ContentBlock,
+}
+
+impl PostReceiveEvent {
+    /// Creates a new PostReceiveEvent.
+    ///
+    /// This constructor is typically called by the agent runtime while streaming a response,
+    /// not by user code. Users receive instances of this struct in their hook handlers.
+    pub fn new(block: ContentBlock) -> Self {
+        Self { block }
+    }
+}
+
+/// Event fired each time auto-execution adds a message to the conversation history.
+///
+/// In auto-execution mode a single `receive()` call can run several tool cycles, each
+/// appending an assistant message with tool calls, the tool results, and the empty
+/// continuation prompt. This event reports every such message as it's added, so the
+/// history can be persisted or displayed incrementally.
+///
+/// Handlers only observe: they run after the message is in history and all registered
+/// handlers run, in registration order. In manual mode the caller adds messages
+/// itself, so the event doesn't fire.
+///
+/// # Example: Streaming History to a UI
+///
+/// ```rust
+/// use open_agent::Hooks;
+///
+/// let hooks = Hooks::new().add_history_changed(|event| async move {
+///     println!("history[{}] = {:?}", event.index, event.message.role);
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct HistoryChangedEvent {
+    /// The message that was added
+    pub message: Message,
+
+    /// Position of the message in the history when it was added
+    pub index: usize,
+}
+
+impl HistoryChangedEvent {
+    /// Creates a new HistoryChangedEvent.
+    ///
+    /// This constructor is typically called by the agent runtime during auto-execution,
+    /// not by user code. Users receive instances of this struct in their hook handlers.
+    pub fn new(message: Message, index: usize) -> Self {
+        Self { message, index }
+    }
+}
+
 /// Decision returned by a hook handler to control agent execution flow.
 ///
 /// When a hook returns `Some(HookDecision)`, it takes control of the execution flow.
 /// This struct determines whether execution should continue, whether inputs/prompts should
 /// be modified, and provides a reason for logging and debugging.
 ///
-/// # "First Non-None Wins" Model
+/// # Execution Order
 ///
-/// The hooks system uses a **sequential "first non-None wins"** execution model:
+/// Hooks of the same type are executed sequentially, in the order they were
+/// registered. Each returns `Option<HookDecision>`:
 ///
-/// 1. Hooks are executed in the order they were registered
-/// 2. Each hook returns `Option<HookDecision>`:
-///    - `None` = "I don't care, let the next hook decide"
-///    - `Some(decision)` = "I'm taking control, stop checking other hooks"
-/// 3. The **first** hook that returns `Some(decision)` determines the outcome
-/// 4. Remaining hooks are **skipped** after a decision is made
+/// 1. `None` = "I don't care, let the next hook decide"
+/// 2. A **block** ends the chain: remaining hooks are **skipped** and the
+///    operation is stopped, whatever earlier hooks modified
+/// 3. A **modification** (`modify_*`) is applied to the event the next hook
+///    sees, and the chain goes on. Modifications compose: each hook sees the
+///    previous hook's output, and the last one is applied
+/// 4. A plain `continue_()` ends the chain, keeping earlier modifications
 /// 5. If **all** hooks return `None`, execution continues normally
 ///
 /// This model ensures:
 /// - Predictable behavior (order matters)
-/// - Performance (no unnecessary hook executions)
-/// - Priority (earlier hooks can't be overridden by later ones)
+/// - Priority (a block from an earlier hook can't be overridden by later ones)
+/// - Composition (e.g. a redaction hook followed by a formatting hook)
 ///
 /// # Fields
 ///
 /// - `continue_execution`: If `false`, abort the current operation (tool execution or prompt processing)
 /// - `modified_input`: For PreToolUse hooks - replaces the tool input with this value
+/// - `modified_result`: For PostToolUse hooks - replaces the tool result with this value
 /// - `modified_prompt`: For UserPromptSubmit hooks - replaces the user prompt with this value
 /// - `reason`: Optional explanation for why this decision was made (useful for debugging/logging)
 ///
@@ -539,8 +665,11 @@ impl UserPromptSubmitEvent {
 ///
 /// - `HookDecision::continue_()` - Allow execution to proceed normally
 /// - `HookDecision::block(reason)` - Block execution with a reason
-/// - `HookDecision::modify_input(input, reason)` - Continue with modified tool input
+/// - `HookDecision::modify_tool_input(input)` - Continue with modified tool input
+/// - `HookDecision::modify_tool_result(result)` - Continue with a replaced tool result
+/// - `HookDecision::modify_input(input, reason)` - Like `modify_tool_input`, with a reason
 /// - `HookDecision::modify_prompt(prompt, reason)` - Continue with modified user prompt
+/// - `HookDecision::modify_block(block, reason)` - Continue with a replaced content block
 #[derive(Debug, Clone, Default)]
 pub struct HookDecision {
     /// Whether to continue execution. If `false`, the operation is aborted.
@@ -551,10 +680,18 @@ pub struct HookDecision {
     /// The tool will execute with this modified input instead of the original.
     modified_input: Option<Value>,
 
+    /// For PostToolUse hooks: If set, replaces the tool's result with this value.
+    /// The model sees this result instead of what the tool returned.
+    modified_result: Option<Value>,
+
     /// For UserPromptSubmit hooks: If set, replaces the user's prompt with this value.
     /// The agent will process this modified prompt instead of the original.
     modified_prompt: Option<String>,
 
+    /// For PostReceive hooks: If set, replaces the received content block with this value.
+    /// The caller sees this block instead of the one the model produced.
+    modified_block: Option<ContentBlock>,
+
     /// Optional human-readable explanation for why this decision was made.
     /// Useful for logging, debugging, and audit trails.
     reason: Option<String>,
@@ -585,7 +722,9 @@ impl HookDecision {
         Self {
             continue_execution: true,
             modified_input: None,
+            modified_result: None,
             modified_prompt: None,
+            modified_block: None,
             reason: None,
         }
     }
@@ -621,7 +760,9 @@ impl HookDecision {
         Self {
             continue_execution: false,
             modified_input: None,
+            modified_result: None,
             modified_prompt: None,
+            modified_block: None,
             reason: Some(reason.into()),
         }
     }
@@ -663,11 +804,84 @@ impl HookDecision {
         Self {
             continue_execution: true,
             modified_input: Some(input),
+            modified_result: None,
             modified_prompt: None,
+            modified_block: None,
             reason: Some(reason.into()),
         }
     }
 
+    /// Creates a decision to run a tool with different input.
+    ///
+    /// For PreToolUse hooks; equivalent to [`modify_input()`](Self::modify_input)
+    /// without a reason. Add one with [`with_reason()`](Self::with_reason).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{PreToolUseEvent, HookDecision};
+    /// use serde_json::json;
+    ///
+    /// async fn cap_results(event: PreToolUseEvent) -> Option<HookDecision> {
+    ///     if event.tool_name == "search" {
+    ///         let mut input = event.tool_input.clone();
+    ///         input["limit"] = json!(10);
+    ///         return Some(HookDecision::modify_tool_input(input));
+    ///     }
+    ///     None
+    /// }
+    /// ```
+    pub fn modify_tool_input(input: Value) -> Self {
+        Self {
+            continue_execution: true,
+            modified_input: Some(input),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a decision to replace a tool's result before the model sees it.
+    ///
+    /// For PostToolUse hooks, e.g. to redact secrets or trim large outputs. The
+    /// replaced result is what gets added to history.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{PostToolUseEvent, HookDecision};
+    /// use serde_json::json;
+    ///
+    /// async fn redact(event: PostToolUseEvent) -> Option<HookDecision> {
+    ///     if event.tool_result.to_string().contains("API_KEY=") {
+    ///         return Some(HookDecision::modify_tool_result(json!({"content": "[REDACTED]"})));
+    ///     }
+    ///     None
+    /// }
+    /// ```
+    pub fn modify_tool_result(result: Value) -> Self {
+        Self {
+            continue_execution: true,
+            modified_result: Some(result),
+            ..Self::default()
+        }
+    }
+
+    /// Attaches a reason to a decision, replacing any existing one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::HookDecision;
+    /// use serde_json::json;
+    ///
+    /// let decision = HookDecision::modify_tool_input(json!({"limit": 10}))
+    ///     .with_reason("Capped search results");
+    /// assert_eq!(decision.reason(), Some("Capped search results"));
+    /// ```
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
     /// Creates a decision to modify the user's prompt before processing.
     ///
     /// Use this in UserPromptSubmit hooks to enhance, sanitize, or transform user input.
@@ -705,7 +919,47 @@ impl HookDecision {
         Self {
             continue_execution: true,
             modified_input: None,
+            modified_result: None,
             modified_prompt: Some(prompt.into()),
+            modified_block: None,
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Creates a decision to replace a received content block before the caller sees it.
+    ///
+    /// Use this in PostReceive hooks to redact or rewrite model output. To drop a block
+    /// entirely instead, return [`HookDecision::block()`].
+    ///
+    /// # Parameters
+    ///
+    /// - `block`: The content block that replaces the one the model produced
+    /// - `reason`: Explanation for why the block was replaced
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{ContentBlock, HookDecision, PostReceiveEvent, TextBlock};
+    ///
+    /// async fn mask_swearing(event: PostReceiveEvent) -> Option<HookDecision> {
+    ///     match &event.block {
+    ///         ContentBlock::Text(text) if text.text.contains("darn") => {
+    ///             Some(HookDecision::modify_block(
+    ///                 ContentBlock::Text(TextBlock::new(text.text.replace("darn", "d**n"))),
+    ///                 "Masked profanity",
+    ///             ))
+    ///         }
+    ///         _ => None,
+    ///     }
+    /// }
+    /// ```
+    pub fn modify_block(block: ContentBlock, reason: impl Into<String>) -> Self {
+        Self {
+            continue_execution: true,
+            modified_input: None,
+            modified_result: None,
+            modified_prompt: None,
+            modified_block: Some(block),
             reason: Some(reason.into()),
         }
     }
@@ -715,20 +969,87 @@ impl HookDecision {
         self.continue_execution
     }
 
-    /// Returns the modified input, if any.
+    /// Returns the modified tool input, if any.
     pub fn modified_input(&self) -> Option<&Value> {
         self.modified_input.as_ref()
     }
 
+    /// Returns the replacement tool result, if any.
+    pub fn modified_result(&self) -> Option<&Value> {
+        self.modified_result.as_ref()
+    }
+
     /// Returns the modified prompt, if any.
     pub fn modified_prompt(&self) -> Option<&str> {
         self.modified_prompt.as_deref()
     }
 
+    /// Returns the replacement content block, if any.
+    pub fn modified_block(&self) -> Option<&ContentBlock> {
+        self.modified_block.as_ref()
+    }
+
     /// Returns the reason, if any.
     pub fn reason(&self) -> Option<&str> {
         self.reason.as_deref()
     }
+
+    /// Whether the decision replaces an input, result, prompt, or block.
+    fn is_modification(&self) -> bool {
+        self.modified_input.is_some()
+            || self.modified_result.is_some()
+            || self.modified_prompt.is_some()
+            || self.modified_block.is_some()
+    }
+
+    /// Combines this decision with a later one; the later one's values take precedence.
+    fn then(self, next: HookDecision) -> HookDecision {
+        HookDecision {
+            continue_execution: next.continue_execution,
+            modified_input: next.modified_input.or(self.modified_input),
+            modified_result: next.modified_result.or(self.modified_result),
+            modified_prompt: next.modified_prompt.or(self.modified_prompt),
+            modified_block: next.modified_block.or(self.modified_block),
+            reason: next.reason.or(self.reason),
+        }
+    }
+}
+
+/// A hook handler that can return a decision, for any event type.
+type DecisionHandler<E> =
+    Arc<dyn Fn(E) -> Pin<Box<dyn Future<Output = Option<HookDecision>> + Send>> + Send + Sync>;
+
+/// Runs decision hooks in order, composing their modifications.
+///
+/// Implements the model described on [`HookDecision`]: a block is returned at
+/// once, a modification is applied to the event with `apply` before the next
+/// hook runs, and a plain continue ends the chain. The returned decision carries
+/// the latest value of each modification.
+async fn run_decision_hooks<E: Clone>(
+    handlers: &[DecisionHandler<E>],
+    mut event: E,
+    apply: fn(&mut E, &HookDecision),
+) -> Option<HookDecision> {
+    let mut combined: Option<HookDecision> = None;
+    for handler in handlers {
+        let Some(decision) = handler(event.clone()).await else {
+            continue;
+        };
+        if !decision.continue_execution {
+            return Some(decision);
+        }
+
+        let modifies = decision.is_modification();
+        apply(&mut event, &decision);
+        combined = Some(match combined {
+            Some(previous) => previous.then(decision),
+            None => decision,
+        });
+        if !modifies {
+            break;
+        }
+    }
+    combined
 }
 
 /// Type alias for PreToolUse hook handler functions.
@@ -852,6 +1173,23 @@ pub type UserPromptSubmitHandler = Arc<
         + Sync,
 >;
 
+/// Type alias for PostReceive hook handler functions.
+///
+/// Identical in structure to `PreToolUseHandler` but receives `PostReceiveEvent` instead.
+/// See [`PreToolUseHandler`] for detailed explanation of the type signature.
+pub type PostReceiveHandler = Arc<
+    dyn Fn(PostReceiveEvent) -> Pin<Box<dyn Future<Output = Option<HookDecision>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Type alias for HistoryChanged hook handler functions.
+///
+/// Unlike the other handlers, these return `()`: the message is already in history
+/// and there's no decision to make.
+pub type HistoryChangedHandler =
+    Arc<dyn Fn(HistoryChangedEvent) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
 /// Container for registering and managing lifecycle hooks.
 ///
 /// The `Hooks` struct stores collections of hook handlers for different lifecycle events.
@@ -895,6 +1233,10 @@ pub type UserPromptSubmitHandler = Arc<
 /// - `pre_tool_use`: Handlers invoked before tool execution
 /// - `post_tool_use`: Handlers invoked after tool execution
 /// - `user_prompt_submit`: Handlers invoked before processing user prompts
+/// - `post_receive`: Handlers invoked on each content block before `receive()` returns it
+/// - `history_changed`: Handlers notified of each message auto-execution adds to history
+/// - `tool_result_filters`: Redaction filters applied to every tool result
+///   (`content-filter` feature)
 ///
 /// All fields are public, allowing direct manipulation if needed, though the builder
 /// methods are the recommended approach.
@@ -908,6 +1250,16 @@ pub struct Hooks {
 
     /// Collection of UserPromptSubmit hook handlers, executed in registration order
     pub user_prompt_submit: Vec<UserPromptSubmitHandler>,
+
+    /// Collection of PostReceive hook handlers, executed in registration order
+    pub post_receive: Vec<PostReceiveHandler>,
+
+    /// Collection of HistoryChanged hook handlers, all executed in registration order
+    pub history_changed: Vec<HistoryChangedHandler>,
+
+    /// Tool result filters, all applied in registration order after the PostToolUse hooks
+    #[cfg(any(test, feature = "content-filter"))]
+    pub tool_result_filters: Vec<ContentFilter>,
 }
 
 impl Hooks {
@@ -1032,15 +1384,204 @@ impl Hooks {
         self
     }
 
-    /// Executes all registered PreToolUse hooks in order and returns the first decision.
+    /// Registers a PostReceive hook handler using the builder pattern.
+    ///
+    /// Identical to `add_pre_tool_use` but for PostReceive events. See [`Self::add_pre_tool_use`]
+    /// for detailed documentation and [`PostReceiveEvent`] for the available decisions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{ContentBlock, HookDecision, Hooks};
+    ///
+    /// let hooks = Hooks::new()
+    ///     .add_post_receive(|event| async move {
+    ///         // Hide any text block that mentions the internal hostname
+    ///         match &event.block {
+    ///             ContentBlock::Text(text) if text.text.contains("db.internal") => {
+    ///                 Some(HookDecision::block("Internal hostname"))
+    ///             }
+    ///             _ => None,
+    ///         }
+    ///     });
+    /// ```
+    pub fn add_post_receive<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(PostReceiveEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<HookDecision>> + Send + 'static,
+    {
+        // Wrap the user's function in Arc and Box::pin for type erasure and heap allocation
+        self.post_receive
+            .push(Arc::new(move |event| Box::pin(handler(event))));
+        self
+    }
+
+    /// Registers a HistoryChanged hook handler using the builder pattern.
+    ///
+    /// The handler is called with each message auto-execution adds to history. See
+    /// [`HistoryChangedEvent`] for when it fires.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Hooks;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = log.clone();
+    /// let hooks = Hooks::new().add_history_changed(move |event| {
+    ///     let sink = sink.clone();
+    ///     async move {
+    ///         // Append-only log of the conversation
+    ///         sink.lock().unwrap().push(event.message);
+    ///     }
+    /// });
+    /// ```
+    pub fn add_history_changed<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(HistoryChangedEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        // Wrap the user's function in Arc and Box::pin for type erasure and heap allocation
+        self.history_changed
+            .push(Arc::new(move |event| Box::pin(handler(event))));
+        self
+    }
+
+    /// Registers a filter that redacts regex matches in every tool result.
+    ///
+    /// Shorthand for [`add_content_filter`](Self::add_content_filter) with a
+    /// [`ContentFilter`] built from `patterns` and its defaults. Matches are
+    /// replaced with `[REDACTED]` before the result enters history, so the model
+    /// never sees them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if a pattern is not a valid regex.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::Hooks;
+    ///
+    /// // Never send social security numbers or emails back to the model
+    /// let hooks = Hooks::new().add_tool_result_filter([
+    ///     r"\b\d{3}-\d{2}-\d{4}\b",
+    ///     r"[\w.+-]+@[\w-]+\.[\w.]+",
+    /// ])?;
+    /// # Ok::<(), open_agent::Error>(())
+    /// ```
+    #[cfg(any(test, feature = "content-filter"))]
+    pub fn add_tool_result_filter<I, S>(self, patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Ok(self.add_content_filter(ContentFilter::new(patterns)?))
+    }
+
+    /// Registers a filter that redacts every tool result before it enters history.
+    ///
+    /// Filters are not PostToolUse hooks, so the hook chain's rules can't turn
+    /// them off. They run as a separate step once the PostToolUse chain is done,
+    /// whatever it decided, on the result it produced (PostToolUse hooks, audit
+    /// hooks included, see the unfiltered result). They also apply to results
+    /// added with [`Client::add_tool_result()`](crate::Client::add_tool_result).
+    /// Several filters run in registration order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{ContentFilter, Hooks};
+    ///
+    /// let filter = ContentFilter::new([r"\b\d{3}-\d{2}-\d{4}\b"])?
+    ///     .allow("000-00-0000") // documented placeholder, safe to show
+    ///     .skip_tool("lookup_own_profile")
+    ///     .replacement("[SSN]");
+    ///
+    /// let hooks = Hooks::new().add_content_filter(filter);
+    /// # Ok::<(), open_agent::Error>(())
+    /// ```
+    #[cfg(any(test, feature = "content-filter"))]
+    pub fn add_content_filter(mut self, filter: ContentFilter) -> Self {
+        self.tool_result_filters.push(filter);
+        self
+    }
+
+    /// Applies the tool result filters to a result about to enter history.
+    ///
+    /// `tool_name` is `None` when the call that produced the result isn't known,
+    /// in which case no filter skips it. Without the `content-filter` feature
+    /// the result is returned unchanged.
+    pub(crate) fn filter_tool_result(&self, tool_name: Option<&str>, result: Value) -> Value {
+        #[cfg(any(test, feature = "content-filter"))]
+        {
+            let mut result = result;
+            for filter in &self.tool_result_filters {
+                if tool_name.is_some_and(|name| filter.skips(name)) {
+                    continue;
+                }
+                if let Some(redacted) = filter.redact(&result) {
+                    result = redacted;
+                }
+            }
+            result
+        }
+        #[cfg(not(any(test, feature = "content-filter")))]
+        {
+            let _ = tool_name;
+            result
+        }
+    }
+
+    /// Combines two hook collections, running `self`'s handlers before `other`'s.
+    ///
+    /// Handlers are concatenated per event type, so the usual execution model
+    /// (see [`HookDecision`]) applies across both: a block from one of `self`'s
+    /// hooks means `other`'s hooks for that event don't run, and `other`'s
+    /// hooks see `self`'s modifications. Put the bundle that must be able to
+    /// veto first. HistoryChanged handlers from both always run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use open_agent::{HookDecision, Hooks};
+    ///
+    /// let security = Hooks::new().add_pre_tool_use(|event| async move {
+    ///     (event.tool_name == "delete_file").then(|| HookDecision::block("Not allowed"))
+    /// });
+    /// let logging = Hooks::new().add_pre_tool_use(|event| async move {
+    ///     println!("Calling {}", event.tool_name);
+    ///     None
+    /// });
+    ///
+    /// // Blocked calls are never logged as calls
+    /// let hooks = security.merge(logging);
+    /// assert_eq!(hooks.pre_tool_use.len(), 2);
+    /// ```
+    pub fn merge(mut self, other: Hooks) -> Self {
+        self.pre_tool_use.extend(other.pre_tool_use);
+        self.post_tool_use.extend(other.post_tool_use);
+        self.user_prompt_submit.extend(other.user_prompt_submit);
+        self.post_receive.extend(other.post_receive);
+        self.history_changed.extend(other.history_changed);
+        #[cfg(any(test, feature = "content-filter"))]
+        self.tool_result_filters.extend(other.tool_result_filters);
+        self
+    }
+
+    /// Executes all registered PreToolUse hooks in order and returns their combined decision.
     ///
-    /// This method implements the **"first non-None wins"** execution model:
+    /// This method implements the execution model described on [`HookDecision`]:
     ///
     /// 1. Iterates through hooks in registration order (FIFO)
     /// 2. Calls each hook with a clone of the event
-    /// 3. If a hook returns `Some(decision)`, immediately returns that decision
-    /// 4. Remaining hooks are **not executed**
-    /// 5. If all hooks return `None`, returns `None`
+    /// 3. If a hook blocks, immediately returns that decision; remaining hooks
+    ///    are **not executed**
+    /// 4. If a hook returns `modify_tool_input`, later hooks see the modified
+    ///    `tool_input`, and the returned decision carries the last one
+    /// 5. A plain `continue_()` stops the chain
+    /// 6. If all hooks return `None`, returns `None`
     ///
     /// # Parameters
     ///
@@ -1048,7 +1589,7 @@ impl Hooks {
     ///
     /// # Returns
     ///
-    /// - `Some(HookDecision)`: A hook made a decision (block, modify, or continue)
+    /// - `Some(HookDecision)`: Hooks made a decision (block, modify, or continue)
     /// - `None`: All hooks returned `None` (continue normally)
     ///
     /// # Example
@@ -1064,7 +1605,7 @@ impl Hooks {
     ///         Some(HookDecision::block("Blocked")) // Runs second, blocks
     ///     })
     ///     .add_pre_tool_use(|e| async move {
-    ///         None // NEVER runs because previous hook returned Some
+    ///         None // NEVER runs because the previous hook blocked
     ///     });
     ///
     /// let event = PreToolUseEvent::new(
@@ -1080,23 +1621,15 @@ impl Hooks {
     /// # }
     /// ```
     pub async fn execute_pre_tool_use(&self, event: PreToolUseEvent) -> Option<HookDecision> {
-        // Sequential execution: iterate through handlers in order
-        for handler in &self.pre_tool_use {
-            // Clone the event for this handler (events are cheaply cloneable)
-            let decision = handler(event.clone()).await;
-
-            // First non-None wins: return immediately if this hook made a decision
-            if decision.is_some() {
-                return decision;
+        run_decision_hooks(&self.pre_tool_use, event, |event, decision| {
+            if let Some(input) = decision.modified_input() {
+                event.tool_input = input.clone();
             }
-            // Otherwise, continue to next hook
-        }
-
-        // All hooks returned None: no decision made, continue normally
-        None
+        })
+        .await
     }
 
-    /// Executes all registered PostToolUse hooks in order and returns the first decision.
+    /// Executes all registered PostToolUse hooks in order and returns their combined decision.
     ///
     /// Identical in behavior to [`Self::execute_pre_tool_use`] but for PostToolUse events.
     /// See that method for detailed documentation of the execution model.
@@ -1106,17 +1639,16 @@ impl Hooks {
     /// PostToolUse hooks rarely return decisions in practice. They're primarily used for
     /// observation (logging, metrics) and typically always return `None`.
     pub async fn execute_post_tool_use(&self, event: PostToolUseEvent) -> Option<HookDecision> {
-        // Sequential execution with "first non-None wins" model
-        for handler in &self.post_tool_use {
-            let decision = handler(event.clone()).await;
-            if decision.is_some() {
-                return decision;
+        run_decision_hooks(&self.post_tool_use, event, |event, decision| {
+            // modify_input is accepted as a result replacement too (see the client)
+            if let Some(result) = decision.modified_result().or(decision.modified_input()) {
+                event.tool_result = result.clone();
             }
-        }
-        None
+        })
+        .await
     }
 
-    /// Executes all registered UserPromptSubmit hooks in order and returns the first decision.
+    /// Executes all registered UserPromptSubmit hooks in order and returns their combined decision.
     ///
     /// Identical in behavior to [`Self::execute_pre_tool_use`] but for UserPromptSubmit events.
     /// See that method for detailed documentation of the execution model.
@@ -1124,14 +1656,211 @@ impl Hooks {
         &self,
         event: UserPromptSubmitEvent,
     ) -> Option<HookDecision> {
-        // Sequential execution with "first non-None wins" model
-        for handler in &self.user_prompt_submit {
-            let decision = handler(event.clone()).await;
-            if decision.is_some() {
-                return decision;
+        run_decision_hooks(&self.user_prompt_submit, event, |event, decision| {
+            if let Some(prompt) = decision.modified_prompt() {
+                event.prompt = prompt.to_string();
+            }
+        })
+        .await
+    }
+
+    /// Executes all registered PostReceive hooks in order and returns their combined decision.
+    ///
+    /// Identical in behavior to [`Self::execute_pre_tool_use`] but for PostReceive events.
+    /// See that method for detailed documentation of the execution model.
+    pub async fn execute_post_receive(&self, event: PostReceiveEvent) -> Option<HookDecision> {
+        run_decision_hooks(&self.post_receive, event, |event, decision| {
+            if let Some(block) = decision.modified_block() {
+                event.block = block.clone();
+            }
+        })
+        .await
+    }
+
+    /// Executes every registered HistoryChanged hook in order.
+    ///
+    /// These hooks only observe, so there's no decision to short-circuit on: each
+    /// handler runs.
+    pub async fn execute_history_changed(&self, event: HistoryChangedEvent) {
+        for handler in &self.history_changed {
+            handler(event.clone()).await;
+        }
+    }
+}
+
+/// Redacts sensitive text from tool results, for [`Hooks::add_content_filter`].
+///
+/// Every string and object key in the result is searched, at any depth, as is
+/// the text form of every number (so an SSN returned as `123456789` is caught
+/// by a pattern for nine digits; a redacted number becomes a string). Each
+/// match of any pattern is replaced, unless the matched text is on the
+/// allowlist. Keys that redact to the same text are numbered (`"[REDACTED]"`,
+/// `"[REDACTED] (2)"`) so no value is lost.
+///
+/// Requires the `content-filter` feature (enabled by default).
+///
+/// # Example
+///
+/// ```rust
+/// use open_agent::ContentFilter;
+/// use serde_json::json;
+///
+/// let filter = ContentFilter::new([r"\d{3}-\d{2}-\d{4}"])?.allow("000-00-0000");
+///
+/// let result = json!({"rows": [{"name": "Ada", "ssn": "078-05-1120"}], "example": "000-00-0000"});
+/// assert_eq!(
+///     filter.redact(&result),
+///     Some(json!({"rows": [{"name": "Ada", "ssn": "[REDACTED]"}], "example": "000-00-0000"}))
+/// );
+///
+/// // Nothing to redact
+/// assert_eq!(filter.redact(&json!({"name": "Ada"})), None);
+/// # Ok::<(), open_agent::Error>(())
+/// ```
+#[cfg(any(test, feature = "content-filter"))]
+#[derive(Debug, Clone)]
+pub struct ContentFilter {
+    patterns: Vec<Regex>,
+    allowed: Vec<String>,
+    skipped_tools: Vec<String>,
+    replacement: String,
+}
+
+#[cfg(any(test, feature = "content-filter"))]
+impl ContentFilter {
+    /// Creates a filter that redacts every match of any of `patterns`.
+    ///
+    /// Patterns use the [`regex`](https://docs.rs/regex) crate's syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if a pattern is not a valid regex or no
+    /// pattern is given.
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                Regex::new(pattern).map_err(|e| {
+                    Error::invalid_input(format!(
+                        "Invalid tool result filter pattern '{}': {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if patterns.is_empty() {
+            return Err(Error::invalid_input(
+                "Tool result filter needs at least one pattern",
+            ));
+        }
+
+        Ok(Self {
+            patterns,
+            allowed: Vec::new(),
+            skipped_tools: Vec::new(),
+            replacement: "[REDACTED]".to_string(),
+        })
+    }
+
+    /// Never redacts a match that is exactly `value`.
+    pub fn allow(mut self, value: impl Into<String>) -> Self {
+        self.allowed.push(value.into());
+        self
+    }
+
+    /// Leaves results of the tool named `name` untouched.
+    pub fn skip_tool(mut self, name: impl Into<String>) -> Self {
+        self.skipped_tools.push(name.into());
+        self
+    }
+
+    /// Sets the text that replaces each match; defaults to `[REDACTED]`.
+    pub fn replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Returns whether results of the tool `name` are left untouched.
+    pub fn skips(&self, name: &str) -> bool {
+        self.skipped_tools.iter().any(|skipped| skipped == name)
+    }
+
+    /// Returns `value` with every match redacted, or `None` if nothing matched.
+    pub fn redact(&self, value: &Value) -> Option<Value> {
+        match value {
+            Value::String(text) => self.redact_text(text).map(Value::String),
+            Value::Number(number) => self.redact_text(&number.to_string()).map(Value::String),
+            Value::Array(items) => {
+                let redacted: Vec<Option<Value>> =
+                    items.iter().map(|item| self.redact(item)).collect();
+                if redacted.iter().all(Option::is_none) {
+                    return None;
+                }
+                Some(Value::Array(
+                    redacted
+                        .into_iter()
+                        .zip(items)
+                        .map(|(new, old)| new.unwrap_or_else(|| old.clone()))
+                        .collect(),
+                ))
             }
+            Value::Object(fields) => {
+                let mut changed = false;
+                let mut redacted = serde_json::Map::new();
+                for (key, field) in fields {
+                    let key = match self.redact_text(key) {
+                        Some(new) => {
+                            changed = true;
+                            new
+                        }
+                        None => key.clone(),
+                    };
+                    let field = match self.redact(field) {
+                        Some(new) => {
+                            changed = true;
+                            new
+                        }
+                        None => field.clone(),
+                    };
+                    // Number keys that collide after redaction rather than drop values
+                    let key = if redacted.contains_key(&key) {
+                        (2..)
+                            .map(|n| format!("{} ({})", key, n))
+                            .find(|numbered| !redacted.contains_key(numbered))
+                            .unwrap_or(key)
+                    } else {
+                        key
+                    };
+                    redacted.insert(key, field);
+                }
+                changed.then_some(Value::Object(redacted))
+            }
+            Value::Null | Value::Bool(_) => None,
+        }
+    }
+
+    /// Applies every pattern to `text`; `None` if nothing was replaced
+    fn redact_text(&self, text: &str) -> Option<String> {
+        let mut current = text.to_string();
+        let mut changed = false;
+        for pattern in &self.patterns {
+            let replaced = pattern.replace_all(&current, |caps: &regex::Captures| {
+                let matched = &caps[0];
+                if self.allowed.iter().any(|allowed| allowed == matched) {
+                    matched.to_string()
+                } else {
+                    changed = true;
+                    self.replacement.clone()
+                }
+            });
+            current = replaced.into_owned();
         }
-        None
+        changed.then_some(current)
     }
 }
 
@@ -1147,12 +1876,15 @@ impl Hooks {
 /// Hooks {
 ///     pre_tool_use: 3 handlers,
 ///     post_tool_use: 1 handlers,
-///     user_prompt_submit: 2 handlers
+///     user_prompt_submit: 2 handlers,
+///     post_receive: 0 handlers,
+///     history_changed: 0 handlers
 /// }
 /// ```
 impl std::fmt::Debug for Hooks {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Hooks")
+        let mut debug = f.debug_struct("Hooks");
+        debug
             .field(
                 "pre_tool_use",
                 &format!("{} handlers", self.pre_tool_use.len()),
@@ -1165,7 +1897,20 @@ impl std::fmt::Debug for Hooks {
                 "user_prompt_submit",
                 &format!("{} handlers", self.user_prompt_submit.len()),
             )
-            .finish()
+            .field(
+                "post_receive",
+                &format!("{} handlers", self.post_receive.len()),
+            )
+            .field(
+                "history_changed",
+                &format!("{} handlers", self.history_changed.len()),
+            );
+        #[cfg(any(test, feature = "content-filter"))]
+        debug.field(
+            "tool_result_filters",
+            &format!("{} filters", self.tool_result_filters.len()),
+        );
+        debug.finish()
     }
 }
 
@@ -1186,10 +1931,16 @@ pub const HOOK_POST_TOOL_USE: &str = "post_tool_use";
 /// See [`HOOK_PRE_TOOL_USE`] for usage details.
 pub const HOOK_USER_PROMPT_SUBMIT: &str = "user_prompt_submit";
 
+/// String constant for the PostReceive hook event name.
+///
+/// See [`HOOK_PRE_TOOL_USE`] for usage details.
+pub const HOOK_POST_RECEIVE: &str = "post_receive";
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
     #[tokio::test]
     async fn test_hook_decision_builders() {
@@ -1243,6 +1994,60 @@ mod tests {
         hooks.execute_post_tool_use(event).await;
     }
 
+    #[tokio::test]
+    async fn test_post_receive_hook() {
+        use crate::types::TextBlock;
+
+        let hooks = Hooks::new().add_post_receive(|event| async move {
+            match event.block {
+                ContentBlock::Text(text) if text.text.contains("secret") => {
+                    Some(HookDecision::modify_block(
+                        ContentBlock::Text(TextBlock::new("[REDACTED]")),
+                        "redacted",
+                    ))
+                }
+                _ => None,
+            }
+        });
+
+        let event = PostReceiveEvent::new(ContentBlock::Text(TextBlock::new("my secret")));
+        let decision = hooks.execute_post_receive(event).await.unwrap();
+        assert!(decision.continue_execution());
+        assert!(matches!(
+            decision.modified_block(),
+            Some(ContentBlock::Text(text)) if text.text == "[REDACTED]"
+        ));
+
+        let event = PostReceiveEvent::new(ContentBlock::Text(TextBlock::new("hello")));
+        assert!(hooks.execute_post_receive(event).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_history_changed_hooks_all_run() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (first, second) = (calls.clone(), calls.clone());
+        let hooks = Hooks::new()
+            .add_history_changed(move |_| {
+                let calls = first.clone();
+                async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .add_history_changed(move |event| {
+                let calls = second.clone();
+                async move {
+                    assert_eq!(event.index, 3);
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+        let event = HistoryChangedEvent::new(Message::user("hi"), 3);
+        hooks.execute_history_changed(event).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
     #[tokio::test]
     async fn test_user_prompt_submit_hook() {
         let hooks = Hooks::new().add_user_prompt_submit(|event| async move {
@@ -1258,4 +2063,206 @@ mod tests {
         assert!(decision.is_some());
         assert!(!decision.unwrap().continue_execution);
     }
+
+    #[tokio::test]
+    async fn test_modify_tool_result_hook() {
+        let hooks = Hooks::new().add_post_tool_use(|event| async move {
+            let redacted = event.tool_result.to_string().contains("secret");
+            redacted.then(|| {
+                HookDecision::modify_tool_result(json!({"content": "[REDACTED]"}))
+                    .with_reason("redacted")
+            })
+        });
+
+        let event = PostToolUseEvent::new(
+            "read".to_string(),
+            json!({}),
+            "id1".to_string(),
+            json!({"content": "secret"}),
+            vec![],
+        );
+        let decision = hooks.execute_post_tool_use(event).await.unwrap();
+        assert!(decision.continue_execution());
+        assert_eq!(
+            decision.modified_result(),
+            Some(&json!({"content": "[REDACTED]"}))
+        );
+        assert_eq!(decision.modified_input(), None);
+        assert_eq!(decision.reason(), Some("redacted"));
+
+        let decision = HookDecision::modify_tool_input(json!({"limit": 1}));
+        assert!(decision.continue_execution());
+        assert_eq!(decision.modified_input(), Some(&json!({"limit": 1})));
+        assert_eq!(decision.reason(), None);
+    }
+
+    #[tokio::test]
+    async fn test_merge_keeps_order() {
+        let security = Hooks::new().add_pre_tool_use(|event| async move {
+            (event.tool_name == "dangerous").then(|| HookDecision::block("first"))
+        });
+        let other = Hooks::new()
+            .add_pre_tool_use(|_| async move { Some(HookDecision::block("second")) })
+            .add_post_receive(|_| async move { None });
+        let hooks = security.merge(other);
+
+        assert_eq!(hooks.pre_tool_use.len(), 2);
+        assert_eq!(hooks.post_receive.len(), 1);
+
+        let event = |name: &str| {
+            PreToolUseEvent::new(name.to_string(), json!({}), "id".to_string(), vec![])
+        };
+        let decision = hooks
+            .execute_pre_tool_use(event("dangerous"))
+            .await
+            .unwrap();
+        assert_eq!(decision.reason(), Some("first"));
+        let decision = hooks.execute_pre_tool_use(event("safe")).await.unwrap();
+        assert_eq!(decision.reason(), Some("second"));
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_registration_order() {
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut hooks = Hooks::new();
+        for i in 0..3 {
+            let order = Arc::clone(&order);
+            hooks = hooks.add_pre_tool_use(move |_| {
+                let order = Arc::clone(&order);
+                async move {
+                    order.lock().unwrap().push(i);
+                    None
+                }
+            });
+        }
+
+        let event = PreToolUseEvent::new("t".to_string(), json!({}), "id".to_string(), vec![]);
+        assert!(hooks.execute_pre_tool_use(event).await.is_none());
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_first_block_short_circuits() {
+        let ran = Arc::new(AtomicBool::new(false));
+        let ran_clone = Arc::clone(&ran);
+        let hooks = Hooks::new()
+            .add_pre_tool_use(
+                |_| async move { Some(HookDecision::modify_tool_input(json!({"x": 1}))) },
+            )
+            .add_pre_tool_use(|_| async move { Some(HookDecision::block("first")) })
+            .add_pre_tool_use(|_| async move { Some(HookDecision::block("second")) })
+            .add_pre_tool_use(move |_| {
+                let ran = Arc::clone(&ran_clone);
+                async move {
+                    ran.store(true, Ordering::SeqCst);
+                    None
+                }
+            });
+
+        let event = PreToolUseEvent::new("t".to_string(), json!({}), "id".to_string(), vec![]);
+        let decision = hooks.execute_pre_tool_use(event).await.unwrap();
+        assert!(!decision.continue_execution());
+        assert_eq!(decision.reason(), Some("first"));
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_modifications_compose() {
+        let hooks = Hooks::new()
+            .add_user_prompt_submit(|event| async move {
+                Some(HookDecision::modify_prompt(
+                    event.prompt.replace("secret", "[redacted]"),
+                    "redact",
+                ))
+            })
+            .add_user_prompt_submit(|_| async move { None })
+            .add_user_prompt_submit(|event| async move {
+                Some(HookDecision::modify_prompt(
+                    format!("{}!", event.prompt),
+                    "emphasize",
+                ))
+            });
+        let event = UserPromptSubmitEvent::new("the secret".to_string(), vec![]);
+        let decision = hooks.execute_user_prompt_submit(event).await.unwrap();
+        assert!(decision.continue_execution());
+        assert_eq!(decision.modified_prompt(), Some("the [redacted]!"));
+        assert_eq!(decision.reason(), Some("emphasize"));
+
+        let hooks = Hooks::new()
+            .add_pre_tool_use(|event| async move {
+                let n = event.tool_input["n"].as_i64().unwrap();
+                Some(HookDecision::modify_tool_input(json!({"n": n + 1})))
+            })
+            .add_pre_tool_use(|event| async move {
+                let n = event.tool_input["n"].as_i64().unwrap();
+                Some(HookDecision::modify_tool_input(json!({"n": n * 10})))
+            })
+            // A plain continue keeps the modifications and ends the chain
+            .add_pre_tool_use(|_| async move { Some(HookDecision::continue_()) })
+            .add_pre_tool_use(|_| async move { Some(HookDecision::block("not reached")) });
+        let event =
+            PreToolUseEvent::new("t".to_string(), json!({"n": 1}), "id".to_string(), vec![]);
+        let decision = hooks.execute_pre_tool_use(event).await.unwrap();
+        assert!(decision.continue_execution());
+        assert_eq!(decision.modified_input(), Some(&json!({"n": 20})));
+    }
+
+    #[cfg(any(test, feature = "content-filter"))]
+    #[test]
+    fn test_tool_result_filter_redacts_values_and_keys() {
+        let hooks = Hooks::new()
+            .add_content_filter(
+                ContentFilter::new([r"\b\d{3}-?\d{2}-?\d{4}\b"])
+                    .unwrap()
+                    .allow("000-00-0000")
+                    .skip_tool("whoami"),
+            )
+            .add_tool_result_filter([r"[\w.]+@example\.com"])
+            .unwrap();
+
+        let result = json!({
+            "rows": [
+                {"ssn": "SSN 078-05-1120 on file", "id": 123456789, "ok": true},
+                {"ssn": "000-00-0000", "email": "ada@example.com"}
+            ],
+            "078-05-1120": null,
+            "ada@example.com": 1
+        });
+        assert_eq!(
+            hooks.filter_tool_result(Some("query_db"), result),
+            json!({
+                "rows": [
+                    {"ssn": "SSN [REDACTED] on file", "id": "[REDACTED]", "ok": true},
+                    {"ssn": "000-00-0000", "email": "[REDACTED]"}
+                ],
+                "[REDACTED]": null,
+                "[REDACTED] (2)": 1
+            })
+        );
+
+        // Clean results pass through; skipped tools are only exempt by name
+        let clean = json!({"count": 3});
+        assert_eq!(
+            hooks.filter_tool_result(Some("query_db"), clean.clone()),
+            clean
+        );
+        assert_eq!(
+            hooks.filter_tool_result(Some("whoami"), json!("078-05-1120")),
+            json!("078-05-1120")
+        );
+        assert_eq!(
+            hooks.filter_tool_result(None, json!("078-05-1120")),
+            json!("[REDACTED]")
+        );
+
+        // Filters are not hooks: the PostToolUse chain never sees them
+        assert!(hooks.post_tool_use.is_empty());
+        assert_eq!(hooks.merge(Hooks::new()).tool_result_filters.len(), 2);
+
+        assert!(matches!(
+            Hooks::new().add_tool_result_filter(["("]),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(ContentFilter::new(Vec::<String>::new()).is_err());
+    }
 }
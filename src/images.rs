@@ -0,0 +1,180 @@
+//! Image generation for OpenAI-compatible servers
+//!
+//! This module talks to the `/images/generations` endpoint that some servers
+//! expose next to chat completions (LocalAI, stable-diffusion front ends,
+//! gateways in front of OpenAI). It is separate from the chat path: a call is a
+//! single request, with no history, tools or hooks involved.
+//!
+//! Generated images come back as [`ImageBlock`]s, so they can be fed straight
+//! into a vision chat turn.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use open_agent::{ContentBlock, Message, TextBlock, generate_image};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let images = generate_image(
+//!     "A lighthouse at dusk, watercolor",
+//!     "stable-diffusion-xl",
+//!     "http://localhost:8080/v1",
+//!     "not-needed",
+//! )
+//! .await?;
+//!
+//! // Ask a vision model about the result
+//! let mut content = vec![ContentBlock::Text(TextBlock::new("Describe this image"))];
+//! content.extend(images.into_iter().map(ContentBlock::Image));
+//! let message = Message::user_with_blocks(content);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{error_from_response, http_client_builder};
+use crate::{AgentOptions, Error, ImageBlock, Result};
+use serde::{Deserialize, Serialize};
+
+/// MIME type of `b64_json` images; servers return PNG unless told otherwise
+const GENERATED_IMAGE_MIME_TYPE: &str = "image/png";
+
+/// Request payload for the `/images/generations` endpoint
+#[derive(Debug, Serialize)]
+struct ImageRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+/// Response payload from the `/images/generations` endpoint
+#[derive(Debug, Deserialize)]
+struct ImageResponse {
+    data: Vec<ImageData>,
+}
+
+/// A single generated image, returned either as a URL or inline base64
+#[derive(Debug, Deserialize)]
+struct ImageData {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    b64_json: Option<String>,
+}
+
+/// Generate images from a prompt using an OpenAI-compatible `/images/generations` endpoint
+///
+/// Returns the images in the order the server lists them. Images returned as
+/// URLs become URL-backed blocks; images returned as `b64_json` become
+/// `data:image/png;base64,...` blocks.
+///
+/// # Arguments
+///
+/// * `prompt` - Description of the image to generate
+/// * `model` - Image model name (usually different from the chat model)
+/// * `base_url` - Server base URL, e.g. `http://localhost:8080/v1`
+/// * `api_key` - API key; local servers accept any value
+///
+/// The request uses the SDK's default HTTP settings (60 second timeout, TCP
+/// keepalive, User-Agent). Slow image models may need longer; use
+/// [`Client::generate_image`](crate::Client::generate_image) with a larger
+/// [`timeout`](crate::AgentOptionsBuilder::timeout) for those.
+///
+/// # Errors
+///
+/// Returns an error on network failures, non-success HTTP status
+/// ([`Error::RateLimited`] for 429), a malformed response body, or an image
+/// entry with neither `url` nor `b64_json`.
+pub async fn generate_image(
+    prompt: &str,
+    model: &str,
+    base_url: &str,
+    api_key: &str,
+) -> Result<Vec<ImageBlock>> {
+    let client = http_client_builder(&AgentOptions::default())
+        .build()
+        .map_err(Error::Http)?;
+    generate_image_with_client(&client, prompt, model, base_url, api_key).await
+}
+
+/// Generate images through an existing HTTP client
+///
+/// Shared by [`generate_image`] and `Client::generate_image`, which reuses the
+/// client's configured connection pool and timeout.
+pub(crate) async fn generate_image_with_client(
+    client: &reqwest::Client,
+    prompt: &str,
+    model: &str,
+    base_url: &str,
+    api_key: &str,
+) -> Result<Vec<ImageBlock>> {
+    let url = format!("{}/images/generations", base_url.trim_end_matches('/'));
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&ImageRequest { model, prompt })
+        .send()
+        .await
+        .map_err(Error::Http)?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    let body: ImageResponse = response.json().await.map_err(Error::Http)?;
+    into_image_blocks(body)
+}
+
+/// Converts each generated image into an [`ImageBlock`]
+fn into_image_blocks(response: ImageResponse) -> Result<Vec<ImageBlock>> {
+    response
+        .data
+        .into_iter()
+        .map(|image| match (image.url, image.b64_json) {
+            (Some(url), _) => ImageBlock::from_url(url),
+            (None, Some(data)) => ImageBlock::from_base64(data, GENERATED_IMAGE_MIME_TYPE),
+            (None, None) => Err(Error::api(
+                "Image response entry contained neither 'url' nor 'b64_json'",
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_blocks_from_server_response() {
+        let response: ImageResponse = serde_json::from_str(
+            r#"{
+                "created": 1700000000,
+                "data": [
+                    {"url": "https://example.com/generated/1.png", "revised_prompt": "A lighthouse"},
+                    {"b64_json": "iVBORw0KGgo="}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let images = into_image_blocks(response).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].url(), "https://example.com/generated/1.png");
+        assert_eq!(images[1].url(), "data:image/png;base64,iVBORw0KGgo=");
+
+        let response: ImageResponse = serde_json::from_str(r#"{"data": [{}]}"#).unwrap();
+        assert!(into_image_blocks(response).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_image_trims_trailing_slash() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![MockResponse::error(503, "busy")])
+            .await
+            .unwrap();
+        let base_url = format!("{}/", server.base_url());
+
+        let result = generate_image("A lighthouse", "sdxl", &base_url, "not-needed").await;
+        assert!(result.is_err());
+        assert_eq!(server.requests()[0].path, "/v1/images/generations");
+    }
+}
@@ -110,13 +110,19 @@
 //! The SDK is organized into several modules, each with a specific responsibility:
 //!
 //! - **client**: Core streaming query engine and multi-turn client
+//! - **circuit_breaker**: Fail-fast protection when the server is down
 //! - **types**: Data structures for messages, content blocks, and configuration
 //! - **tools**: Tool definition system with automatic JSON schema generation
 //! - **hooks**: Lifecycle event system for intercepting execution
 //! - **config**: Provider-specific configuration helpers
 //! - **error**: Comprehensive error types and conversions
 //! - **context**: Token estimation and message truncation utilities
+//! - **embeddings**: Embedding vectors from the server's `/embeddings` endpoint
+//! - **images**: Image generation via the server's `/images/generations` endpoint
+//! - **models**: Model ids from the server's `/models` endpoint
+//! - **tokenize**: Exact token counts from a server's `/tokenize` endpoint
 //! - **retry**: Exponential backoff retry logic with jitter
+//! - **testing**: A mock server for testing agent code (`testing` feature)
 //! - **utils**: Internal utilities for SSE parsing and tool aggregation
 
 // ============================================================================
@@ -130,6 +136,10 @@
 /// for multi-turn conversations with automatic state management.
 mod client;
 
+/// Circuit breaker that fails requests fast after repeated endpoint failures.
+/// Can be shared across clients so they back off from a down server together.
+mod circuit_breaker;
+
 /// Provider configuration helpers for LM Studio, Ollama, llama.cpp, and vLLM.
 /// Simplifies endpoint and model name resolution with environment variable support.
 mod config;
@@ -138,6 +148,10 @@ mod config;
 /// Provides manual control over conversation memory to prevent context overflow.
 mod context;
 
+/// Embeddings API for the `/embeddings` endpoint of OpenAI-compatible servers.
+/// Lets RAG pipelines embed documents using the same server and HTTP stack.
+mod embeddings;
+
 /// Error types and conversions for comprehensive error handling throughout the SDK.
 /// Defines the `Error` enum and `Result<T>` type alias used across all public APIs.
 mod error;
@@ -146,6 +160,22 @@ mod error;
 /// Enables security gates, audit logging, input/output modification, and compliance checks.
 mod hooks;
 
+/// Image generation via the `/images/generations` endpoint of OpenAI-compatible servers.
+/// Kept apart from the chat path; results are `ImageBlock`s ready for vision turns.
+mod images;
+
+/// Model listing via the `/models` endpoint of OpenAI-compatible servers.
+/// Backs `Client::new_validated()`'s check that the configured model exists.
+mod models;
+
+/// Prompt templates with `{placeholder}` substitution for reusable prompt scaffolding.
+/// Rendered into the system prompt by `AgentOptionsBuilder::system_template()`.
+mod template;
+
+/// Exact token counts from llama.cpp/vLLM `/tokenize` endpoints.
+/// Backs `Client::count_prompt_tokens()` with a heuristic fallback.
+mod tokenize;
+
 /// Tool definition and execution system with automatic JSON schema generation.
 /// Allows LLMs to call Rust functions with type-safe parameter handling.
 mod tools;
@@ -168,9 +198,22 @@ mod utils;
 /// for their own operations that need retry logic.
 pub mod retry;
 
+/// Mock chat completions server for testing code built on the SDK.
+/// Only compiled with the `testing` feature, so it adds nothing to normal builds.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 // --- Core Client API ---
 
-pub use client::{Client, query};
+pub use client::{
+    Client, ContentStream, EventStream, QueryHandle, SharedBlockResult, fan_out, query,
+    query_complete, query_typed, query_with_client, query_with_handle, query_with_messages,
+    text_only,
+};
+
+// --- Circuit Breaker ---
+
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
 
 // --- Provider Configuration ---
 
@@ -178,7 +221,27 @@ pub use config::{Provider, get_base_url, get_model};
 
 // --- Context Management ---
 
-pub use context::{estimate_tokens, is_approaching_limit, truncate_messages};
+pub use context::{
+    BudgetPlan, TokenizerHint, estimate_messages_with_hint, estimate_tokens,
+    estimate_tokens_with_hint, is_approaching_limit, is_approaching_limit_with_hint,
+    normalize_roles, token_budget_fit, token_budget_fit_with_hint, truncate_messages,
+};
+
+// --- Embeddings ---
+
+pub use embeddings::embed;
+
+// --- Image Generation ---
+
+pub use images::generate_image;
+
+// --- Prompt Templates ---
+
+pub use template::PromptTemplate;
+
+// --- Models ---
+
+pub use models::list_models;
 
 // --- Error Handling ---
 
@@ -186,21 +249,27 @@ pub use error::{Error, Result};
 
 // --- Lifecycle Hooks ---
 
+#[cfg(any(test, feature = "content-filter"))]
+pub use hooks::ContentFilter;
 pub use hooks::{
-    HOOK_POST_TOOL_USE, HOOK_PRE_TOOL_USE, HOOK_USER_PROMPT_SUBMIT, HookDecision, Hooks,
-    PostToolUseEvent, PreToolUseEvent, UserPromptSubmitEvent,
+    HOOK_POST_RECEIVE, HOOK_POST_TOOL_USE, HOOK_PRE_TOOL_USE, HOOK_USER_PROMPT_SUBMIT,
+    HistoryChangedEvent, HookDecision, Hooks, PostReceiveEvent, PostToolUseEvent, PreToolUseEvent,
+    UserPromptSubmitEvent,
 };
 
 // --- Tool System ---
 
-pub use tools::{Tool, ToolBuilder, tool};
+pub use tools::{Tool, ToolBuilder, ToolOutput, ToolRegistry, tool};
 
 // --- Core Types ---
 
 pub use types::{
-    AgentOptions, AgentOptionsBuilder, BaseUrl, ContentBlock, ImageBlock, ImageDetail, Message,
-    MessageRole, ModelName, OpenAIContent, OpenAIContentPart, Temperature, TextBlock,
-    ToolResultBlock, ToolUseBlock,
+    AgentOptions, AgentOptionsBuilder, BaseUrl, CompletedResponse, ContentBlock,
+    DEFAULT_MAX_IMAGE_BYTES, FinishReason, ImageBlock, ImageDetail, IterationLimitBehavior,
+    Message, MessageBuilder, MessageRole, ModelName, OpenAIContent, OpenAIContentPart,
+    OpenAIFunction, OpenAIMessage, OpenAIRequest, OpenAIToolCall, PriceTable, ReasoningEffort,
+    ResponseMeta, SamplingPreset, StreamEvent, Temperature, TextBlock, ToolErrorPolicy,
+    ToolResultBlock, ToolUseBlock, Usage,
 };
 
 // ============================================================================
@@ -220,7 +289,7 @@ pub use types::{
 pub mod prelude {
     pub use crate::{
         AgentOptions, AgentOptionsBuilder, BaseUrl, Client, ContentBlock, Error, HookDecision,
-        Hooks, ModelName, PostToolUseEvent, PreToolUseEvent, Result, Temperature, TextBlock, Tool,
-        ToolUseBlock, UserPromptSubmitEvent, query, tool,
+        Hooks, ModelName, PostReceiveEvent, PostToolUseEvent, PreToolUseEvent, Result, Temperature,
+        TextBlock, Tool, ToolUseBlock, UserPromptSubmitEvent, query, tool,
     };
 }
@@ -0,0 +1,126 @@
+//! Model listing for OpenAI-compatible servers
+//!
+//! This module queries the `/models` endpoint that LM Studio, Ollama, llama.cpp
+//! and vLLM all expose, returning the ids of the models the server can serve.
+//! It backs [`Client::new_validated`](crate::Client::new_validated), which
+//! checks the configured model exists before a conversation starts.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use open_agent::list_models;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let models = list_models("http://localhost:1234/v1", "not-needed").await?;
+//! for id in &models {
+//!     println!("{}", id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{error_from_response, http_client_builder};
+use crate::{AgentOptions, Error, Result};
+use serde::Deserialize;
+
+/// Response payload from the `/models` endpoint
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+/// A single model in the response
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+/// List the model ids available on an OpenAI-compatible server
+///
+/// Ids are returned in the order the server lists them.
+///
+/// # Arguments
+///
+/// * `base_url` - Server base URL, e.g. `http://localhost:1234/v1`
+/// * `api_key` - API key; local servers accept any value
+///
+/// The request uses the SDK's default HTTP settings (60 second timeout, TCP
+/// keepalive, User-Agent).
+///
+/// # Errors
+///
+/// Returns an error on network failures, non-success HTTP status
+/// ([`Error::RateLimited`] for 429), or a malformed response body.
+pub async fn list_models(base_url: &str, api_key: &str) -> Result<Vec<String>> {
+    let client = http_client_builder(&AgentOptions::default())
+        .build()
+        .map_err(Error::Http)?;
+    list_models_with_client(&client, base_url, api_key).await
+}
+
+/// List model ids through an existing HTTP client
+///
+/// Shared by [`list_models`] and `Client::new_validated`, which uses the
+/// client's configured timeout.
+pub(crate) async fn list_models_with_client(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+) -> Result<Vec<String>> {
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+    let response = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(Error::Http)?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    let body: ModelList = response.json().await.map_err(Error::Http)?;
+    Ok(model_ids(body))
+}
+
+/// Extracts the ids from a model list
+fn model_ids(list: ModelList) -> Vec<String> {
+    list.data.into_iter().map(|model| model.id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_ids_from_server_response() {
+        let list: ModelList = serde_json::from_str(
+            r#"{
+                "object": "list",
+                "data": [
+                    {"id": "qwen2.5-32b-instruct", "object": "model", "owned_by": "organization_owner"},
+                    {"id": "nomic-embed-text", "object": "model", "created": 1700000000}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            model_ids(list),
+            vec!["qwen2.5-32b-instruct", "nomic-embed-text"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_models_trims_trailing_slash() {
+        use crate::testing::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![MockResponse::error(503, "busy")])
+            .await
+            .unwrap();
+        let base_url = format!("{}/", server.base_url());
+
+        assert!(list_models(&base_url, "not-needed").await.is_err());
+        assert_eq!(server.requests()[0].path, "/v1/models");
+    }
+}
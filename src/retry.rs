@@ -22,12 +22,51 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! # Idempotency keys
+//!
+//! With [`idempotency`](crate::AgentOptionsBuilder::idempotency) enabled, every
+//! request made inside one `retry_with_backoff()` or
+//! `retry_with_backoff_conditional()` call carries the same `Idempotency-Key`,
+//! so a gateway can recognise the retries as one logical request. Use
+//! [`with_idempotency_key`](crate::retry::with_idempotency_key) to choose the
+//! key yourself.
 
 use crate::{Error, Result};
+use rand::Rng;
 use std::future::Future;
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// How random jitter is applied to the exponential backoff delay
+///
+/// Jitter spreads retries from many clients over time so they don't all hit a
+/// recovering server at the same instant (the "thundering herd" problem). In the
+/// descriptions below, `delay` is the exponential delay capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JitterStrategy {
+    /// Symmetric jitter of `delay * jitter_factor` centred on `delay` (default)
+    ///
+    /// This is the original behavior and the only strategy that uses `jitter_factor`.
+    #[default]
+    Proportional,
+
+    /// No jitter: always sleep for exactly `delay`
+    None,
+
+    /// Uniformly random delay in `[0, delay]`
+    Full,
+
+    /// Half fixed, half random: uniformly random delay in `[delay / 2, delay]`
+    Equal,
+
+    /// Uniformly random delay in `[initial_delay, previous_delay * 3]`, capped at `max_delay`
+    ///
+    /// Each delay is derived from the previous one rather than the attempt number,
+    /// which decorrelates clients that started retrying at the same time.
+    Decorrelated,
+}
+
 /// Configuration for retry behavior
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -38,6 +77,8 @@ pub struct RetryConfig {
     pub initial_delay: Duration,
 
     /// Maximum delay between retries
+    ///
+    /// Also caps a wait requested by the server through `Retry-After`.
     pub max_delay: Duration,
 
     /// Multiplier for exponential backoff (e.g., 2.0 doubles the delay each time)
@@ -45,6 +86,9 @@ pub struct RetryConfig {
 
     /// Add random jitter to prevent thundering herd (0.0 to 1.0)
     pub jitter_factor: f64,
+
+    /// How jitter is applied to each delay
+    pub jitter_strategy: JitterStrategy,
 }
 
 impl Default for RetryConfig {
@@ -55,6 +99,7 @@ impl Default for RetryConfig {
             max_delay: Duration::from_secs(60),
             backoff_multiplier: 2.0,
             jitter_factor: 0.1,
+            jitter_strategy: JitterStrategy::default(),
         }
     }
 }
@@ -95,25 +140,130 @@ impl RetryConfig {
         self
     }
 
+    /// Set jitter strategy
+    pub fn with_jitter_strategy(mut self, strategy: JitterStrategy) -> Self {
+        self.jitter_strategy = strategy;
+        self
+    }
+
     /// Calculate delay for a given attempt with exponential backoff and jitter
-    fn calculate_delay(&self, attempt: u32) -> Duration {
+    ///
+    /// `previous` is the delay used before the last attempt; only the
+    /// [`JitterStrategy::Decorrelated`] strategy looks at it.
+    fn calculate_delay(&self, attempt: u32, previous: Duration) -> Duration {
+        self.calculate_delay_with_rng(attempt, previous, &mut rand::thread_rng())
+    }
+
+    /// Same as `calculate_delay`, drawing randomness from the given RNG
+    fn calculate_delay_with_rng<R: Rng + ?Sized>(
+        &self,
+        attempt: u32,
+        previous: Duration,
+        rng: &mut R,
+    ) -> Duration {
         let base_delay_ms = self.initial_delay.as_millis() as f64;
+        let max_delay_ms = self.max_delay.as_millis() as f64;
         let exponential_delay = base_delay_ms * self.backoff_multiplier.powi(attempt as i32);
 
         // Cap at max delay
-        let capped_delay = exponential_delay.min(self.max_delay.as_millis() as f64);
+        let capped_delay = exponential_delay.min(max_delay_ms);
 
-        // Add jitter
-        let jitter_range = capped_delay * self.jitter_factor;
-        let jitter = rand::random::<f64>() * jitter_range;
-        let final_delay = capped_delay + jitter - (jitter_range / 2.0);
+        let final_delay = match self.jitter_strategy {
+            JitterStrategy::Proportional => {
+                let jitter_range = capped_delay * self.jitter_factor;
+                let jitter = rng.r#gen::<f64>() * jitter_range;
+                capped_delay + jitter - (jitter_range / 2.0)
+            }
+            JitterStrategy::None => capped_delay,
+            JitterStrategy::Full => rng.r#gen::<f64>() * capped_delay,
+            JitterStrategy::Equal => {
+                let half = capped_delay / 2.0;
+                half + rng.r#gen::<f64>() * half
+            }
+            JitterStrategy::Decorrelated => {
+                let upper = (previous.as_millis() as f64 * 3.0).max(base_delay_ms);
+                let delay = base_delay_ms + rng.r#gen::<f64>() * (upper - base_delay_ms);
+                delay.min(max_delay_ms)
+            }
+        };
 
         Duration::from_millis(final_delay.max(0.0) as u64)
     }
 }
 
+/// Idempotency key shared by the requests made in one scope
+#[derive(Clone)]
+struct ScopedKey {
+    key: String,
+    /// Set by `with_idempotency_key()`; a retry helper's own key is only used
+    /// when the `idempotency` option is on
+    pinned: bool,
+}
+
+tokio::task_local! {
+    /// Idempotency key shared by the requests made in the current scope
+    static IDEMPOTENCY_KEY: ScopedKey;
+}
+
+/// Runs `future` with every request it makes carrying `key` as its `Idempotency-Key`.
+///
+/// Pins the key for one logical request: requests sent by `send()`, `query()`
+/// and the other request functions while `future` runs use `key` instead of a
+/// generated one, even if [`idempotency`](crate::AgentOptionsBuilder::idempotency)
+/// is off. Retry helpers called inside keep the pinned key. Requests made from
+/// tasks spawned by `future` are not covered.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::retry::{retry_with_backoff, with_idempotency_key, RetryConfig};
+/// use open_agent::{query_complete, AgentOptions};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = AgentOptions::builder()
+///     .model("qwen3:8b")
+///     .base_url("http://gateway.internal/v1")
+///     .build()?;
+///
+/// // The gateway deduplicates on the order id, across retries and restarts
+/// let response = with_idempotency_key("order-1234", retry_with_backoff(
+///     RetryConfig::default(),
+///     || query_complete("Place the order", &options),
+/// )).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_idempotency_key<F: Future>(key: impl Into<String>, future: F) -> F::Output {
+    let scoped = ScopedKey {
+        key: key.into(),
+        pinned: true,
+    };
+    IDEMPOTENCY_KEY.scope(scoped, future).await
+}
+
+/// Returns the idempotency key pinned for the current scope, if any
+pub(crate) fn pinned_idempotency_key() -> Option<String> {
+    IDEMPOTENCY_KEY
+        .try_with(|scoped| scoped.pinned.then(|| scoped.key.clone()))
+        .ok()
+        .flatten()
+}
+
+/// Returns the key a surrounding retry helper shares between its attempts, if any
+///
+/// Only used by requests whose `idempotency` option is on.
+pub(crate) fn shared_idempotency_key() -> Option<String> {
+    IDEMPOTENCY_KEY
+        .try_with(|scoped| (!scoped.pinned).then(|| scoped.key.clone()))
+        .ok()
+        .flatten()
+}
+
 /// Retry an async operation with exponential backoff
 ///
+/// With idempotency enabled, every attempt shares one `Idempotency-Key` (see the
+/// [module docs](self#idempotency-keys)).
+///
 /// # Arguments
 ///
 /// * `config` - Retry configuration
@@ -150,16 +300,21 @@ where
     Fut: Future<Output = Result<T>>,
 {
     let mut last_error = None;
+    let mut delay = config.initial_delay;
+    let key = retry_idempotency_key();
 
     for attempt in 0..config.max_attempts {
-        match operation().await {
+        match IDEMPOTENCY_KEY.scope(key.clone(), operation()).await {
             Ok(result) => return Ok(result),
             Err(err) => {
                 last_error = Some(err);
 
                 // Don't sleep after the last attempt
                 if attempt < config.max_attempts - 1 {
-                    let delay = config.calculate_delay(attempt);
+                    // Honor the server's requested wait over the computed backoff,
+                    // up to max_delay
+                    delay = server_retry_after(last_error.as_ref(), config.max_delay)
+                        .unwrap_or_else(|| config.calculate_delay(attempt, delay));
                     sleep(delay).await;
                 }
             }
@@ -169,15 +324,40 @@ where
     Err(last_error.unwrap_or_else(|| Error::other("Retry failed with no error")))
 }
 
+/// Returns the key all attempts of a retry loop share: the surrounding one, or a new one
+fn retry_idempotency_key() -> ScopedKey {
+    IDEMPOTENCY_KEY
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| ScopedKey {
+            key: crate::client::new_idempotency_key(),
+            pinned: false,
+        })
+}
+
+/// Returns the wait the server asked for, if the error carries one, capped at `max_delay`
+fn server_retry_after(error: Option<&Error>, max_delay: Duration) -> Option<Duration> {
+    match error {
+        Some(Error::RateLimited { retry_after, .. }) => retry_after.map(|wait| wait.min(max_delay)),
+        _ => None,
+    }
+}
+
 /// Determine if an error is retryable
 ///
-/// Returns true for transient errors like network issues, timeouts, and 5xx server errors.
-/// Returns false for client errors like invalid requests (4xx) or configuration errors.
+/// Returns true for transient errors like network issues, timeouts, rate limiting, and
+/// 5xx server errors. Returns false for client errors like invalid requests (4xx) or
+/// configuration errors.
 pub fn is_retryable_error(error: &Error) -> bool {
     match error {
-        Error::Http(_) => true,   // Network errors are retryable
-        Error::Timeout => true,   // Timeouts are retryable
-        Error::Stream(_) => true, // Stream errors might be transient
+        Error::Http(_) => true,               // Network errors are retryable
+        Error::Timeout => true,               // Timeouts are retryable
+        Error::Stream(_) => true,             // Stream errors might be transient
+        Error::RateLimited { .. } => true,    // Rate limits clear after waiting
+        Error::CircuitOpen { .. } => false,   // Fail fast while the endpoint is down
+        Error::ContentFiltered => false,      // The same prompt will be filtered again
+        Error::ModelNotFound { .. } => false, // Needs the model pulled or renamed
+        Error::ModelNotLoaded(_) => false,    // Needs a model loaded on the server
+        Error::ToolArguments { .. } => false, // Not a transport failure
         Error::Api(msg) => {
             // Check if it's a 5xx server error (retryable)
             // vs 4xx client error (not retryable)
@@ -192,6 +372,8 @@ pub fn is_retryable_error(error: &Error) -> bool {
 /// Retry an async operation with exponential backoff, only retrying on retryable errors
 ///
 /// This is a smarter version of `retry_with_backoff` that only retries transient errors.
+/// With idempotency enabled, every attempt shares one `Idempotency-Key` (see the
+/// [module docs](self#idempotency-keys)).
 ///
 /// # Examples
 ///
@@ -223,9 +405,11 @@ where
     Fut: Future<Output = Result<T>>,
 {
     let mut last_error = None;
+    let mut delay = config.initial_delay;
+    let key = retry_idempotency_key();
 
     for attempt in 0..config.max_attempts {
-        match operation().await {
+        match IDEMPOTENCY_KEY.scope(key.clone(), operation()).await {
             Ok(result) => return Ok(result),
             Err(err) => {
                 // Check if error is retryable
@@ -237,7 +421,10 @@ where
 
                 // Don't sleep after the last attempt
                 if attempt < config.max_attempts - 1 {
-                    let delay = config.calculate_delay(attempt);
+                    // Honor the server's requested wait over the computed backoff,
+                    // up to max_delay
+                    delay = server_retry_after(last_error.as_ref(), config.max_delay)
+                        .unwrap_or_else(|| config.calculate_delay(attempt, delay));
                     sleep(delay).await;
                 }
             }
@@ -274,15 +461,77 @@ mod tests {
             .with_backoff_multiplier(2.0)
             .with_jitter_factor(0.0); // No jitter for predictable testing
 
-        let delay0 = config.calculate_delay(0);
-        let delay1 = config.calculate_delay(1);
-        let delay2 = config.calculate_delay(2);
+        let delay0 = config.calculate_delay(0, Duration::ZERO);
+        let delay1 = config.calculate_delay(1, delay0);
+        let delay2 = config.calculate_delay(2, delay1);
 
         // Verify exponential growth
         assert!(delay1 > delay0);
         assert!(delay2 > delay1);
     }
 
+    #[test]
+    fn test_jitter_strategy_bounds() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let config = RetryConfig::new()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(10))
+            .with_backoff_multiplier(2.0)
+            .with_jitter_factor(0.2);
+        assert_eq!(config.jitter_strategy, JitterStrategy::Proportional);
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for attempt in 0..6 {
+            // Capped exponential delay for this attempt
+            let capped = (100u64 << attempt).min(10_000);
+            let previous = Duration::from_millis(capped / 2);
+
+            let proportional = config.calculate_delay_with_rng(attempt, previous, &mut rng);
+            assert!(proportional.as_millis() as f64 >= capped as f64 * 0.9 - 1.0);
+            assert!(proportional.as_millis() as f64 <= capped as f64 * 1.1);
+
+            let none = config
+                .clone()
+                .with_jitter_strategy(JitterStrategy::None)
+                .calculate_delay_with_rng(attempt, previous, &mut rng);
+            assert_eq!(none, Duration::from_millis(capped));
+
+            let full = config
+                .clone()
+                .with_jitter_strategy(JitterStrategy::Full)
+                .calculate_delay_with_rng(attempt, previous, &mut rng);
+            assert!(full <= Duration::from_millis(capped));
+
+            let equal = config
+                .clone()
+                .with_jitter_strategy(JitterStrategy::Equal)
+                .calculate_delay_with_rng(attempt, previous, &mut rng);
+            assert!(equal >= Duration::from_millis(capped / 2));
+            assert!(equal <= Duration::from_millis(capped));
+
+            let decorrelated = config
+                .clone()
+                .with_jitter_strategy(JitterStrategy::Decorrelated)
+                .calculate_delay_with_rng(attempt, previous, &mut rng);
+            assert!(decorrelated >= Duration::from_millis(100));
+            assert!(decorrelated <= (previous * 3).min(Duration::from_secs(10)));
+        }
+
+        // Same seed produces the same sequence of delays
+        let full = config.with_jitter_strategy(JitterStrategy::Full);
+        let mut a = StdRng::seed_from_u64(7);
+        let mut b = StdRng::seed_from_u64(7);
+        for attempt in 0..4 {
+            assert_eq!(
+                full.calculate_delay_with_rng(attempt, Duration::ZERO, &mut a),
+                full.calculate_delay_with_rng(attempt, Duration::ZERO, &mut b)
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_retry_success_on_first_attempt() {
         let config = RetryConfig::new().with_max_attempts(3);
@@ -343,6 +592,52 @@ mod tests {
         assert_eq!(call_count.load(std::sync::atomic::Ordering::SeqCst), 2); // Should try twice
     }
 
+    #[tokio::test]
+    async fn test_retry_prefers_server_retry_after() {
+        // Computed backoff would be 10 seconds; the server asks for 10ms
+        let config = RetryConfig::new()
+            .with_max_attempts(2)
+            .with_initial_delay(Duration::from_secs(10))
+            .with_jitter_strategy(JitterStrategy::None);
+
+        let call_count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let count_clone = call_count.clone();
+        let start = std::time::Instant::now();
+        let result = retry_with_backoff_conditional(config, move || {
+            let count = count_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if count == 0 {
+                    Err(Error::rate_limited(
+                        "Too many requests",
+                        Some(Duration::from_millis(10)),
+                        Some(0),
+                    ))
+                } else {
+                    Ok::<i32, Error>(42)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(call_count.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_server_retry_after_is_capped() {
+        let max_delay = Duration::from_secs(60);
+        let day = Error::rate_limited("", Some(Duration::from_secs(86400)), None);
+        assert_eq!(server_retry_after(Some(&day), max_delay), Some(max_delay));
+
+        let short = Error::rate_limited("", Some(Duration::from_secs(2)), None);
+        assert_eq!(
+            server_retry_after(Some(&short), max_delay),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(server_retry_after(Some(&Error::timeout()), max_delay), None);
+    }
+
     #[test]
     fn test_is_retryable_error() {
         assert!(is_retryable_error(&Error::timeout()));
@@ -358,5 +653,6 @@ mod tests {
         assert!(!is_retryable_error(&Error::invalid_input(
             "Bad input".to_string()
         )));
+        assert!(is_retryable_error(&Error::rate_limited("", None, None)));
     }
 }
@@ -0,0 +1,220 @@
+//! Lightweight prompt templates with `{placeholder}` substitution
+//!
+//! Prompts reused across agents often share the same scaffolding (role
+//! framing, output format instructions) and differ in a few values. A
+//! [`PromptTemplate`] holds that scaffolding once; [`PromptTemplate::render`]
+//! fills in the values.
+//!
+//! The syntax is deliberately small: `{name}` is replaced by the variable
+//! `name`, and `{{` / `}}` produce literal braces (handy for JSON examples in a
+//! prompt). There are no conditionals, loops or filters; build anything more
+//! involved in Rust and pass it in as a variable.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use open_agent::{AgentOptions, PromptTemplate};
+//!
+//! let reviewer = PromptTemplate::new(
+//!     "You are a {role}. Review the user's {language} code.\n\
+//!      Reply as JSON: {{\"issues\": [...]}}",
+//! );
+//!
+//! let options = AgentOptions::builder()
+//!     .model("qwen2.5-32b-instruct")
+//!     .base_url("http://localhost:1234/v1")
+//!     .system_template(reviewer.clone(), [("role", "security auditor"), ("language", "Rust")])
+//!     .build()?;
+//!
+//! assert_eq!(
+//!     options.system_prompt(),
+//!     "You are a security auditor. Review the user's Rust code.\nReply as JSON: {\"issues\": [...]}"
+//! );
+//! # Ok::<(), open_agent::Error>(())
+//! ```
+
+use crate::{Error, Result};
+use std::collections::HashMap;
+
+/// A piece of a parsed template
+enum Segment<'a> {
+    /// Text copied as is (braces already unescaped where needed)
+    Literal(&'a str),
+    /// A `{name}` placeholder
+    Placeholder(&'a str),
+}
+
+/// A prompt with `{placeholder}` slots, rendered with [`render`](PromptTemplate::render)
+///
+/// Cheap to clone, so one template can be instantiated for many agents with
+/// different variable bindings. Syntax errors (an unclosed `{`, a stray `}`
+/// or an empty `{}`) are reported when rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    source: String,
+}
+
+impl PromptTemplate {
+    /// Creates a template from its source text.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+
+    /// Returns the template's source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the placeholder names in order of first appearance, without duplicates.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if the template is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::PromptTemplate;
+    ///
+    /// let template = PromptTemplate::new("{greeting}, {name}! {greeting} again.");
+    /// assert_eq!(template.placeholders()?, vec!["greeting", "name"]);
+    /// # Ok::<(), open_agent::Error>(())
+    /// ```
+    pub fn placeholders(&self) -> Result<Vec<&str>> {
+        let mut names = Vec::new();
+        for segment in self.segments()? {
+            if let Segment::Placeholder(name) = segment
+                && !names.contains(&name)
+            {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Renders the template, replacing each `{name}` with its variable.
+    ///
+    /// Variables not used by the template are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if the template is malformed or uses a
+    /// placeholder with no matching variable.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use open_agent::PromptTemplate;
+    /// use std::collections::HashMap;
+    ///
+    /// let template = PromptTemplate::new("Answer in {language}.");
+    /// let vars = HashMap::from([("language".to_string(), "French".to_string())]);
+    /// assert_eq!(template.render(&vars)?, "Answer in French.");
+    ///
+    /// assert!(template.render(&HashMap::new()).is_err());
+    /// # Ok::<(), open_agent::Error>(())
+    /// ```
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String> {
+        let mut rendered = String::with_capacity(self.source.len());
+        for segment in self.segments()? {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder(name) => match vars.get(name) {
+                    Some(value) => rendered.push_str(value),
+                    None => {
+                        return Err(Error::invalid_input(format!(
+                            "Prompt template variable '{}' is not set",
+                            name
+                        )));
+                    }
+                },
+            }
+        }
+        Ok(rendered)
+    }
+
+    /// Splits the source into literal text and placeholders
+    fn segments(&self) -> Result<Vec<Segment<'_>>> {
+        let source = self.source.as_str();
+        let mut segments = Vec::new();
+        let mut literal_start = 0;
+        let mut chars = source.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' | '}' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                    // Escaped brace: keep one of the pair
+                    segments.push(Segment::Literal(&source[literal_start..=i]));
+                    chars.next();
+                    literal_start = i + 2;
+                }
+                '{' => {
+                    let Some(len) = source[i + 1..].find('}') else {
+                        return Err(Error::invalid_input(format!(
+                            "Prompt template has an unclosed '{{' at byte {}",
+                            i
+                        )));
+                    };
+                    let name = source[i + 1..i + 1 + len].trim();
+                    if name.is_empty() || name.contains('{') {
+                        return Err(Error::invalid_input(format!(
+                            "Prompt template has an invalid placeholder at byte {}",
+                            i
+                        )));
+                    }
+                    segments.push(Segment::Literal(&source[literal_start..i]));
+                    segments.push(Segment::Placeholder(name));
+                    // Skip past the closing brace
+                    while chars.next_if(|&(j, _)| j <= i + 1 + len).is_some() {}
+                    literal_start = i + len + 2;
+                }
+                '}' => {
+                    return Err(Error::invalid_input(format!(
+                        "Prompt template has an unmatched '}}' at byte {}; use '}}}}' for a literal brace",
+                        i
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        segments.push(Segment::Literal(&source[literal_start..]));
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_and_unescapes() {
+        let template = PromptTemplate::new("{who} says {{\"hi\": \"{ who }\"}} — {who}.");
+        assert_eq!(
+            template
+                .render(&vars(&[("who", "Ada"), ("unused", "x")]))
+                .unwrap(),
+            "Ada says {\"hi\": \"Ada\"} — Ada."
+        );
+        assert_eq!(template.placeholders().unwrap(), vec!["who"]);
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let ok = vars(&[("a", "1")]);
+        assert!(PromptTemplate::new("{b}").render(&ok).is_err());
+        assert!(PromptTemplate::new("open {a").render(&ok).is_err());
+        assert!(PromptTemplate::new("stray } brace").render(&ok).is_err());
+        assert!(PromptTemplate::new("empty {}").render(&ok).is_err());
+        assert!(PromptTemplate::new("nested {{a}").render(&ok).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+//! Test utilities for code built on the SDK
+//!
+//! Enabled with the `testing` feature. [`MockServer`](crate::testing::MockServer)
+//! is a local HTTP server that answers chat completions requests with canned
+//! SSE responses, so agent logic (tool execution, hooks, multi-turn state) can
+//! be tested deterministically without a model. Every request it receives is
+//! recorded for assertions.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! open-agent-sdk = { version = "0.6", features = ["testing"] }
+//! ```
+//!
+//! # Examples
+//!
+//! Script a tool call followed by a final answer, and check what was sent:
+//!
+//! ```rust,no_run
+//! use open_agent::testing::{MockResponse, MockServer};
+//! use open_agent::{AgentOptions, Client, tool};
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::start(vec![
+//!     MockResponse::tool_call("call_1", "add", json!({"a": 2, "b": 3})),
+//!     MockResponse::text("The sum is 5."),
+//! ])
+//! .await?;
+//!
+//! let add = tool("add", "Add two numbers")
+//!     .param("a", "number")
+//!     .param("b", "number")
+//!     .build(|args| async move {
+//!         Ok(json!(args["a"].as_f64().unwrap_or(0.0) + args["b"].as_f64().unwrap_or(0.0)))
+//!     });
+//! let options = AgentOptions::builder()
+//!     .model("test-model")
+//!     .base_url(server.base_url())
+//!     .tool(add)
+//!     .auto_execute_tools(true)
+//!     .build()?;
+//!
+//! let mut client = Client::new(options)?;
+//! client.send("What is 2 + 3?").await?;
+//! while client.receive().await?.is_some() {}
+//!
+//! let requests = server.requests();
+//! assert_eq!(requests.len(), 2);
+//! requests[1].assert_body_contains(&json!({
+//!     "messages": [{}, {}, {"role": "tool", "tool_call_id": "call_1"}]
+//! }));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Result};
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// A canned response served by [`MockServer`]
+///
+/// Text and tool call responses are streamed as OpenAI-style SSE chunks ending
+/// in `data: [DONE]`; errors are sent as a non-success status with the given
+/// body.
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// Assistant text, finishing with `stop`
+    Text(String),
+
+    /// Tool calls as `(id, name, arguments)`, finishing with `tool_calls`
+    ToolCalls(Vec<(String, String, Value)>),
+
+    /// A non-success HTTP status with a response body
+    Error {
+        /// HTTP status code, e.g. 500 or 429
+        status: u16,
+        /// Response body, sent as JSON
+        body: String,
+    },
+
+    /// A raw SSE body, sent as is
+    Sse(String),
+}
+
+impl MockResponse {
+    /// A text response.
+    pub fn text(text: impl Into<String>) -> Self {
+        MockResponse::Text(text.into())
+    }
+
+    /// A response with a single tool call.
+    pub fn tool_call(id: impl Into<String>, name: impl Into<String>, arguments: Value) -> Self {
+        MockResponse::ToolCalls(vec![(id.into(), name.into(), arguments)])
+    }
+
+    /// An error response with an OpenAI-style `{"error": {"message": ...}}` body.
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        MockResponse::Error {
+            status,
+            body: json!({"error": {"message": message.into()}}).to_string(),
+        }
+    }
+
+    /// Renders the status line, content type and body of the response.
+    fn render(&self) -> (u16, &'static str, String) {
+        match self {
+            MockResponse::Text(text) => {
+                let body = sse_body(&[json!({"role": "assistant", "content": text}), json!({})]);
+                (200, "text/event-stream", body)
+            }
+            MockResponse::ToolCalls(calls) => {
+                let tool_calls: Vec<Value> = calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (id, name, arguments))| {
+                        json!({
+                            "index": index,
+                            "id": id,
+                            "type": "function",
+                            "function": {"name": name, "arguments": arguments.to_string()}
+                        })
+                    })
+                    .collect();
+                let body = sse_body(&[
+                    json!({"role": "assistant", "tool_calls": tool_calls}),
+                    json!({}),
+                ]);
+                (200, "text/event-stream", body)
+            }
+            MockResponse::Error { status, body } => (*status, "application/json", body.clone()),
+            MockResponse::Sse(body) => (200, "text/event-stream", body.clone()),
+        }
+    }
+}
+
+/// Builds an SSE body from deltas; the last chunk carries the finish reason.
+fn sse_body(deltas: &[Value]) -> String {
+    let finish_reason = if deltas[0].get("tool_calls").is_some() {
+        "tool_calls"
+    } else {
+        "stop"
+    };
+
+    let mut body = String::new();
+    for (i, delta) in deltas.iter().enumerate() {
+        let last = i + 1 == deltas.len();
+        let chunk = json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": if last { Value::from(finish_reason) } else { Value::Null }
+            }]
+        });
+        body.push_str(&format!("data: {}\n\n", chunk));
+    }
+    body.push_str("data: [DONE]\n\n");
+    body
+}
+
+/// A request received by [`MockServer`]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// HTTP method, e.g. `POST`
+    pub method: String,
+    /// Request path, e.g. `/v1/chat/completions`
+    pub path: String,
+    /// Headers, with lowercased names
+    pub headers: HashMap<String, String>,
+    /// The JSON body, or `Value::Null` if it wasn't JSON
+    pub body: Value,
+}
+
+impl RecordedRequest {
+    /// Returns a header value by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Asserts that the body contains `expected`.
+    ///
+    /// Objects match if every key in `expected` is present with a matching
+    /// value; extra keys in the body are ignored. Arrays match element by
+    /// element, and the body's array may be longer. Anything else must be equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics with both values if the body doesn't match.
+    pub fn assert_body_contains(&self, expected: &Value) {
+        assert!(
+            json_contains(&self.body, expected),
+            "request body does not contain the expected value\n expected: {}\n body: {}",
+            serde_json::to_string_pretty(expected).unwrap_or_default(),
+            serde_json::to_string_pretty(&self.body).unwrap_or_default()
+        );
+    }
+}
+
+/// Checks whether `actual` contains `expected` (see `assert_body_contains`).
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected.iter().all(|(key, value)| {
+            actual
+                .get(key)
+                .is_some_and(|actual| json_contains(actual, value))
+        }),
+        (Value::Array(actual), Value::Array(expected)) => {
+            expected.len() <= actual.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| json_contains(actual, expected))
+        }
+        _ => actual == expected,
+    }
+}
+
+/// State shared between the server task and the [`MockServer`] handle
+#[derive(Debug, Default)]
+struct MockState {
+    responses: VecDeque<MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// A local HTTP server that serves canned chat completions
+///
+/// Responses are served in order, one per request, whatever the path. Once
+/// they run out, requests get a 500 error. The server listens on `127.0.0.1`
+/// on a free port and stops when the `MockServer` is dropped.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug)]
+pub struct MockServer {
+    base_url: String,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server that serves `responses` in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound.
+    pub async fn start(responses: Vec<MockResponse>) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| Error::other(format!("MockServer failed to bind: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| Error::other(format!("MockServer failed to bind: {}", e)))?;
+
+        let state = Arc::new(Mutex::new(MockState {
+            responses: responses.into(),
+            requests: Vec::new(),
+        }));
+
+        let task_state = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(socket, Arc::clone(&task_state)));
+            }
+        });
+
+        Ok(Self {
+            base_url: format!("http://{}/v1", addr),
+            state,
+            task,
+        })
+    }
+
+    /// Returns the base URL to pass to `AgentOptionsBuilder::base_url`, ending in `/v1`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Queues another response after the remaining ones.
+    pub fn push_response(&self, response: MockResponse) {
+        lock_state(&self.state).responses.push_back(response);
+    }
+
+    /// Returns the requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        lock_state(&self.state).requests.clone()
+    }
+
+    /// Returns the most recent request, if any.
+    pub fn last_request(&self) -> Option<RecordedRequest> {
+        lock_state(&self.state).requests.last().cloned()
+    }
+
+    /// Returns the number of responses not yet served.
+    pub fn remaining_responses(&self) -> usize {
+        lock_state(&self.state).responses.len()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Locks the shared state, recovering it if a connection task panicked.
+fn lock_state(state: &Mutex<MockState>) -> std::sync::MutexGuard<'_, MockState> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Reads one request from a connection, records it and writes the next response.
+async fn handle_connection(mut socket: TcpStream, state: Arc<Mutex<MockState>>) {
+    let Some(request) = read_request(&mut socket).await else {
+        return;
+    };
+
+    let response = {
+        let mut state = lock_state(&state);
+        state.requests.push(request);
+        state.responses.pop_front()
+    };
+    let response =
+        response.unwrap_or_else(|| MockResponse::error(500, "MockServer has no more responses"));
+
+    let (status, content_type, body) = response.render();
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(body.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+/// Reads a request head and its `Content-Length` body.
+async fn read_request(socket: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 8192];
+
+    let head_end = loop {
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buf[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length = headers
+        .get("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let body_start = head_end + 4;
+    while data.len() < body_start + length {
+        let n = socket.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    let end = data.len().min(body_start + length);
+    let body = serde_json::from_slice(&data[body_start..end]).unwrap_or(Value::Null);
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentOptions, Client, ContentBlock, query_complete, tool};
+
+    #[tokio::test]
+    async fn test_mock_server_tool_call_then_text() {
+        let server = MockServer::start(vec![
+            MockResponse::tool_call("call_1", "add", json!({"a": 2, "b": 3})),
+            MockResponse::text("The sum is 5."),
+        ])
+        .await
+        .unwrap();
+
+        let add = tool("add", "Add two numbers")
+            .param("a", "number")
+            .param("b", "number")
+            .build(|args| async move {
+                Ok(json!(
+                    args["a"].as_f64().unwrap_or(0.0) + args["b"].as_f64().unwrap_or(0.0)
+                ))
+            });
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .tool(add)
+            .auto_execute_tools(true)
+            .build()
+            .unwrap();
+
+        let mut client = Client::new(options).unwrap();
+        client.send("What is 2 + 3?").await.unwrap();
+        let mut text = String::new();
+        while let Some(block) = client.receive().await.unwrap() {
+            if let ContentBlock::Text(t) = block {
+                text.push_str(&t.text);
+            }
+        }
+        assert_eq!(text, "The sum is 5.");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/v1/chat/completions");
+        assert_eq!(requests[0].header("Content-Type"), Some("application/json"));
+        requests[0].assert_body_contains(&json!({
+            "model": "test-model",
+            "messages": [{"role": "user", "content": "What is 2 + 3?"}]
+        }));
+        requests[1].assert_body_contains(&json!({
+            "messages": [{}, {"role": "assistant"}, {"role": "tool", "tool_call_id": "call_1"}]
+        }));
+        assert_eq!(server.remaining_responses(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_errors() {
+        let server = MockServer::start(vec![MockResponse::error(400, "bad request")])
+            .await
+            .unwrap();
+        let options = AgentOptions::builder()
+            .model("test-model")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        assert!(query_complete("Hi", &options).await.is_err());
+        // Out of responses
+        assert!(query_complete("Hi", &options).await.is_err());
+        assert_eq!(server.requests().len(), 2);
+
+        server.push_response(MockResponse::text("Hello"));
+        assert_eq!(query_complete("Hi", &options).await.unwrap().text, "Hello");
+    }
+
+    #[test]
+    fn test_json_contains() {
+        let body = json!({"a": 1, "b": [1, {"c": 2, "d": 3}], "e": null});
+        assert!(json_contains(&body, &json!({"a": 1})));
+        assert!(json_contains(&body, &json!({"b": [1, {"d": 3}]})));
+        assert!(json_contains(&body, &json!({"b": [1]})));
+        assert!(!json_contains(&body, &json!({"a": 2})));
+        assert!(!json_contains(&body, &json!({"f": 1})));
+        assert!(!json_contains(&body, &json!({"b": [1, {}, 3]})));
+    }
+}
@@ -0,0 +1,111 @@
+//! Exact token counts from a server's tokenization endpoint
+//!
+//! [`estimate_tokens`](crate::estimate_tokens) is a character-based heuristic.
+//! Some servers expose their tokenizer over HTTP, which gives exact counts for
+//! the loaded model:
+//!
+//! - **llama.cpp**: `POST /tokenize` with `{"content": ...}`, returning `{"tokens": [...]}`
+//! - **vLLM**: `POST /tokenize` with `{"model": ..., "prompt": ...}`, returning
+//!   `{"count": n, "tokens": [...]}`
+//!
+//! Both live at the server root, not under `/v1`. The request sent here carries
+//! the fields both servers expect, so one code path serves either.
+//!
+//! The endpoint is configured with
+//! [`AgentOptionsBuilder::tokenize_url`](crate::AgentOptionsBuilder::tokenize_url),
+//! or filled in by [`AgentOptions::for_provider`](crate::AgentOptions::for_provider)
+//! for providers that have one. See `Client::count_prompt_tokens` for the
+//! fallback used when no endpoint is available.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Request payload accepted by both llama.cpp and vLLM
+#[derive(Debug, Serialize)]
+struct TokenizeRequest<'a> {
+    /// Text to tokenize (llama.cpp)
+    content: &'a str,
+    /// Text to tokenize (vLLM)
+    prompt: &'a str,
+    /// Model whose tokenizer to use (vLLM)
+    model: &'a str,
+}
+
+/// Response payload; vLLM adds `count`, llama.cpp only returns `tokens`
+#[derive(Debug, Deserialize)]
+struct TokenizeResponse {
+    #[serde(default)]
+    count: Option<usize>,
+    #[serde(default)]
+    tokens: Option<Vec<serde_json::Value>>,
+}
+
+/// Counts the tokens in `text` using a tokenization endpoint
+///
+/// Returns `Ok(None)` if the endpoint is unavailable (connection failure or a
+/// non-success status), so the caller can fall back to an estimate. A successful
+/// response without a usable count is an error.
+pub(crate) async fn count_tokens_with_client(
+    client: &reqwest::Client,
+    url: &str,
+    model: &str,
+    api_key: &str,
+    text: &str,
+) -> Result<Option<usize>> {
+    let result = client
+        .post(url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&TokenizeRequest {
+            content: text,
+            prompt: text,
+            model,
+        })
+        .send()
+        .await;
+
+    let response = match result {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            log::debug!("Tokenize endpoint returned {}", response.status());
+            return Ok(None);
+        }
+        Err(e) => {
+            log::debug!("Tokenize endpoint unreachable: {}", e);
+            return Ok(None);
+        }
+    };
+
+    let body: TokenizeResponse = response.json().await.map_err(Error::Http)?;
+    token_count(body).map(Some)
+}
+
+/// Extracts the count from a tokenize response
+fn token_count(response: TokenizeResponse) -> Result<usize> {
+    response
+        .count
+        .or(response.tokens.map(|tokens| tokens.len()))
+        .ok_or_else(|| Error::api("Tokenize response contained neither 'count' nor 'tokens'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_count_from_server_responses() {
+        // llama.cpp
+        let response: TokenizeResponse =
+            serde_json::from_str(r#"{"tokens": [1, 15043, 3186]}"#).unwrap();
+        assert_eq!(token_count(response).unwrap(), 3);
+
+        // vLLM
+        let response: TokenizeResponse =
+            serde_json::from_str(r#"{"count": 2, "max_model_len": 4096, "tokens": [9906, 1917]}"#)
+                .unwrap();
+        assert_eq!(token_count(response).unwrap(), 2);
+
+        let response: TokenizeResponse = serde_json::from_str("{}").unwrap();
+        assert!(token_count(response).is_err());
+    }
+}
@@ -102,7 +102,9 @@
 //! ```
 
 use crate::Result;
+use crate::types::ImageBlock;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -114,14 +116,17 @@ use std::sync::Arc;
 /// A tool handler is a complex type that enables dynamic async execution:
 ///
 /// ```text
-/// Arc<                                      // Thread-safe reference counting
-///   dyn Fn(Value)                          // Function taking JSON arguments
-///     -> Pin<Box<                           // Pinned heap allocation
-///       dyn Future<Output = Result<Value>>  // Async computation
-///         + Send>>                          // Can cross thread boundaries
-///     + Send + Sync>                        // Handler itself is thread-safe
+/// Arc<                                           // Thread-safe reference counting
+///   dyn Fn(Value)                               // Function taking JSON arguments
+///     -> Pin<Box<                                // Pinned heap allocation
+///       dyn Future<Output = Result<ToolOutput>>  // Async computation
+///         + Send>>                               // Can cross thread boundaries
+///     + Send + Sync>                             // Handler itself is thread-safe
 /// ```
 ///
+/// Handlers that only return JSON are wrapped to return a [`ToolOutput`]
+/// without images.
+///
 /// ### Why Arc?
 ///
 /// [`Arc`] (Atomic Reference Counted) allows multiple parts of the system to hold
@@ -161,14 +166,14 @@ use std::sync::Arc;
 /// use std::pin::Pin;
 /// use std::future::Future;
 /// use serde_json::{json, Value};
-/// use open_agent::Result;
+/// use open_agent::{Result, ToolOutput};
 ///
 /// // Define a handler that matches ToolHandler type
-/// let handler: Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync> =
+/// let handler: Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<ToolOutput>> + Send>> + Send + Sync> =
 ///     Arc::new(|args| {
 ///         Box::pin(async move {
 ///             // Handler implementation
-///             Ok(json!({"status": "success"}))
+///             Ok(ToolOutput::new(json!({"status": "success"})))
 ///         })
 ///     });
 ///
@@ -176,7 +181,67 @@ use std::sync::Arc;
 /// let handler_clone = handler.clone();
 /// ```
 pub type ToolHandler =
-    Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>> + Send>> + Send + Sync>;
+    Arc<dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<ToolOutput>> + Send>> + Send + Sync>;
+
+/// A tool's result: JSON for the model, plus any images it produced.
+///
+/// Returned by handlers passed to [`ToolBuilder::build_with_images`], for tools
+/// that render something the model should look at, such as a chart. With
+/// auto-execution the images travel on the call's
+/// [`ToolResultBlock`](crate::ToolResultBlock); see
+/// [`ToolResultBlock::with_image`](crate::ToolResultBlock::with_image) for how
+/// they are sent and which servers accept them.
+///
+/// # Example
+///
+/// ```
+/// use open_agent::{ImageBlock, ToolOutput};
+/// use serde_json::json;
+///
+/// let chart = ImageBlock::from_base64("iVBORw0KGgo=", "image/png")?;
+/// let output = ToolOutput::new(json!({"rendered": "sales.png"})).with_image(chart);
+/// assert_eq!(output.images().len(), 1);
+/// # Ok::<(), open_agent::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    /// JSON result sent to the model as the tool message
+    pub(crate) value: Value,
+    /// Images sent after the tool message
+    pub(crate) images: Vec<ImageBlock>,
+}
+
+impl ToolOutput {
+    /// Creates an output holding `value` and no images.
+    pub fn new(value: Value) -> Self {
+        Self {
+            value,
+            images: Vec::new(),
+        }
+    }
+
+    /// Attaches an image for the model to look at.
+    pub fn with_image(mut self, image: ImageBlock) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    /// Returns the JSON result.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the attached images.
+    pub fn images(&self) -> &[ImageBlock] {
+        &self.images
+    }
+}
+
+impl From<Value> for ToolOutput {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
 
 /// Tool definition for OpenAI-compatible function calling.
 ///
@@ -301,7 +366,9 @@ pub struct Tool {
 
     /// Async handler function that executes the tool's logic.
     ///
-    /// The handler receives arguments as a JSON [`Value`] and returns a `Result<Value>`.
+    /// The handler receives arguments as a JSON [`Value`] and returns a `Result<Value>`
+    /// (stored wrapped to return a [`ToolOutput`], so handlers built with
+    /// [`ToolBuilder::build_with_images`] can also return images).
     /// It's wrapped in an [`Arc`] for efficient sharing and cloning.
     ///
     /// ## Argument Structure
@@ -362,6 +429,23 @@ pub struct Tool {
     /// # async fn perform_search(query: &str) -> Result<Vec<String>> { Ok(vec![]) }
     /// ```
     handler: ToolHandler,
+
+    /// Example argument objects showing correct invocations of the tool.
+    ///
+    /// Examples are advisory: they are sent to the model as the standard JSON
+    /// Schema `examples` keyword on the parameters schema, which many models use
+    /// as few-shot guidance. Servers that don't understand the keyword ignore it,
+    /// and arguments are never validated against the examples. Empty by default.
+    examples: Vec<Value>,
+
+    /// Whether the tool is offered to the model by default.
+    ///
+    /// Disabled tools stay registered but are left out of requests and refuse
+    /// execution. Each [`Client`](crate::Client) starts from this value and can
+    /// toggle the tool for its own conversation with
+    /// [`Client::set_tool_enabled`](crate::Client::set_tool_enabled).
+    /// Enabled by default.
+    enabled: bool,
 }
 
 impl Tool {
@@ -523,6 +607,25 @@ impl Tool {
     where
         F: Fn(Value) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        Self::with_output_handler(name, description, input_schema, move |args| {
+            let result = handler(args);
+            async move { result.await.map(ToolOutput::from) }
+        })
+    }
+
+    /// Creates a tool whose handler returns a [`ToolOutput`].
+    ///
+    /// Shared by [`Tool::new`] and [`ToolBuilder::build_with_images`].
+    fn with_output_handler<F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolOutput>> + Send + 'static,
     {
         // Convert inputs to owned types
         let name = name.into();
@@ -539,9 +642,65 @@ impl Tool {
             // Wrap the handler in Arc for cheap cloning and thread-safe sharing
             // Box::pin converts the future to a pinned, heap-allocated trait object
             handler: Arc::new(move |args| Box::pin(handler(args))),
+            // No examples unless explicitly attached
+            examples: Vec::new(),
+            enabled: true,
         }
     }
 
+    /// Create a tool whose handler shares state across calls.
+    ///
+    /// Sugar over the closure-capture pattern shown in [`Tool::new`]: `state` is
+    /// wrapped in an `Arc` once, and every call receives a clone of that `Arc`
+    /// alongside its arguments, so the handler doesn't have to clone captures
+    /// into its async block by hand.
+    ///
+    /// The state is shared, not copied: every call (including concurrent ones,
+    /// see [`max_parallel_tools`](crate::AgentOptionsBuilder::max_parallel_tools))
+    /// sees the same value, so anything mutable inside it needs interior
+    /// mutability (atomics, `Mutex`, ...). Clones of the tool share it too.
+    ///
+    /// # Examples
+    ///
+    /// A tool limited to a fixed number of calls:
+    ///
+    /// ```rust,no_run
+    /// use open_agent::Tool;
+    /// use serde_json::json;
+    /// use std::sync::atomic::{AtomicU32, Ordering};
+    ///
+    /// let tool = Tool::stateful(
+    ///     "fetch_quote",
+    ///     "Fetch a stock quote",
+    ///     json!({"symbol": "string"}),
+    ///     AtomicU32::new(10),
+    ///     |tokens, args| async move {
+    ///         let left = tokens.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+    ///         if left.is_err() {
+    ///             return Err(open_agent::Error::tool("Rate limit reached"));
+    ///         }
+    ///         Ok(json!({"symbol": args["symbol"], "price": 101.5}))
+    ///     },
+    /// );
+    /// ```
+    pub fn stateful<S, F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: Value,
+        state: S,
+        handler: F,
+    ) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(Arc<S>, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        let state = Arc::new(state);
+        Self::new(name, description, input_schema, move |args| {
+            handler(Arc::clone(&state), args)
+        })
+    }
+
     /// Execute the tool with the provided arguments.
     ///
     /// This method invokes the tool's handler asynchronously, passing the arguments
@@ -597,6 +756,14 @@ impl Tool {
     pub async fn execute(&self, arguments: Value) -> Result<Value> {
         // Invoke the handler function with the arguments
         // The handler returns Pin<Box<dyn Future>>, which we immediately await
+        Ok(self.execute_with_images(arguments).await?.value)
+    }
+
+    /// Execute the tool, keeping any images the handler returned.
+    ///
+    /// Like [`execute`](Tool::execute), but returns the whole [`ToolOutput`].
+    /// For tools built with [`ToolBuilder::build`] the output never has images.
+    pub async fn execute_with_images(&self, arguments: Value) -> Result<ToolOutput> {
         (self.handler)(arguments).await
     }
 
@@ -656,16 +823,51 @@ impl Tool {
     /// assert!(openai_format["function"]["parameters"].is_object());
     /// ```
     pub fn to_openai_format(&self) -> Value {
+        let mut parameters = self.input_schema.clone();
+
+        // Attach examples via the standard JSON Schema `examples` keyword.
+        // Only added when present so tools without examples serialize exactly as before.
+        if !self.examples.is_empty() {
+            if let Some(obj) = parameters.as_object_mut() {
+                obj.insert("examples".to_string(), Value::Array(self.examples.clone()));
+            }
+        }
+
         serde_json::json!({
             "type": "function",
             "function": {
                 "name": self.name,
                 "description": self.description,
-                "parameters": self.input_schema
+                "parameters": parameters
             }
         })
     }
 
+    /// Attaches example argument objects to the tool.
+    ///
+    /// Examples help the model produce well-formed calls for tricky tools. They
+    /// are advisory only: they're included in [`to_openai_format()`](Tool::to_openai_format)
+    /// under the parameters schema's `examples` keyword, but incoming arguments are
+    /// not checked against them. Replaces any previously attached examples.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use open_agent::tool;
+    /// # use serde_json::json;
+    /// let search = tool("search", "Search documents")
+    ///     .param("query", "string")
+    ///     .build(|_| async { Ok(json!({})) })
+    ///     .with_examples(vec![json!({"query": "rust async traits"})]);
+    ///
+    /// let format = search.to_openai_format();
+    /// assert_eq!(format["function"]["parameters"]["examples"][0]["query"], "rust async traits");
+    /// ```
+    pub fn with_examples(mut self, examples: Vec<Value>) -> Self {
+        self.examples = examples;
+        self
+    }
+
     /// Returns the tool's name.
     pub fn name(&self) -> &str {
         &self.name
@@ -680,6 +882,19 @@ impl Tool {
     pub fn input_schema(&self) -> &Value {
         &self.input_schema
     }
+
+    /// Returns the example argument objects attached to the tool.
+    pub fn examples(&self) -> &[Value] {
+        &self.examples
+    }
+
+    /// Returns whether the tool is offered to the model by default.
+    ///
+    /// Set with [`ToolBuilder::enabled`]. A client may override it for its own
+    /// conversation; see [`Client::is_tool_enabled`](crate::Client::is_tool_enabled).
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
 }
 
 /// Custom Debug implementation for Tool.
@@ -697,6 +912,8 @@ impl std::fmt::Debug for Tool {
             .field("name", &self.name)
             .field("description", &self.description)
             .field("input_schema", &self.input_schema)
+            .field("examples", &self.examples)
+            .field("enabled", &self.enabled)
             // Handler is intentionally omitted - it's not debuggable
             .finish()
     }
@@ -754,6 +971,25 @@ impl std::fmt::Debug for Tool {
 /// // → Converts to JSON Schema with "query" required, "limit" optional
 /// ```
 ///
+/// ### 4. Nested Objects
+/// An object value that isn't a property schema (it has none of `type`, `$ref`,
+/// `enum`, `const`, `items`, `properties`, `anyOf`, `oneOf`, `allOf`, or a boolean
+/// `optional`/`required` flag) is itself simple notation, and is expanded recursively:
+/// ```json
+/// {"filter": {"status": "string", "limit": "integer"}}
+/// // → "filter" becomes
+/// {
+///   "type": "object",
+///   "properties": {
+///     "status": {"type": "string"},
+///     "limit": {"type": "integer"}
+///   },
+///   "required": ["status", "limit"]
+/// }
+/// ```
+/// Property schemas, including any `$ref`, `items`, `enum` or nested `required`
+/// array they contain, are passed through untouched.
+///
 /// ## Required vs Optional Parameters
 ///
 /// The function determines if a parameter is required using this logic:
@@ -782,10 +1018,7 @@ impl std::fmt::Debug for Tool {
 fn convert_schema_to_openai(schema: Value) -> Value {
     // Check if the input is already a complete JSON Schema
     // A complete schema has both "type": "object" and a "properties" field
-    if schema.is_object() {
-        let obj = schema
-            .as_object()
-            .expect("BUG: is_object() returned true but as_object() returned None");
+    if let Some(obj) = schema.as_object() {
         if obj.contains_key("type") && obj.contains_key("properties") {
             // This is already a full JSON Schema - pass it through unchanged
             return schema;
@@ -805,13 +1038,20 @@ fn convert_schema_to_openai(schema: Value) -> Value {
 
                 // Simple notation always means required (no way to specify optional)
                 required.push(param_name.clone());
-            } else if param_type.is_object() {
-                // Case 2: Extended property schema with metadata
+            } else if param_type
+                .as_object()
+                .is_some_and(|nested| !is_property_schema(nested))
+            {
+                // Case 2: Nested object in simple notation - expand it recursively
+                properties.insert(
+                    param_name.clone(),
+                    convert_schema_to_openai(param_type.clone()),
+                );
+                required.push(param_name.clone());
+            } else if let Some(prop_obj) = param_type.as_object() {
+                // Case 3: Extended property schema with metadata
                 // Clone the property schema so we can modify it
-                let mut prop = param_type.clone();
-                let prop_obj = prop
-                    .as_object_mut()
-                    .expect("BUG: is_object() returned true but as_object_mut() returned None");
+                let mut prop_obj = prop_obj.clone();
 
                 // Extract and remove the "optional" flag (not standard JSON Schema)
                 let is_optional = prop_obj
@@ -820,15 +1060,19 @@ fn convert_schema_to_openai(schema: Value) -> Value {
                     .unwrap_or(false);
 
                 // Extract and remove the "required" flag (not standard JSON Schema)
-                // This is different from the "required" array - it's per-property
-                let is_required = prop_obj.remove("required").and_then(|v| v.as_bool());
+                // This is different from the "required" array - it's per-property,
+                // so a nested object's "required" array is left in place
+                let is_required = prop_obj.get("required").and_then(|v| v.as_bool());
+                if is_required.is_some() {
+                    prop_obj.remove("required");
+                }
 
                 // Check if the property has a default value
                 // Properties with defaults are typically optional
                 let has_default = prop_obj.contains_key("default");
 
                 // Add the cleaned property schema to the properties map
-                properties.insert(param_name.clone(), prop);
+                properties.insert(param_name.clone(), Value::Object(prop_obj));
 
                 // Determine if this parameter should be in the required array
                 // Priority order:
@@ -866,6 +1110,28 @@ fn convert_schema_to_openai(schema: Value) -> Value {
     })
 }
 
+/// Keywords that mark an object as a property schema rather than nested simple notation.
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "type",
+    "$ref",
+    "enum",
+    "const",
+    "items",
+    "properties",
+    "anyOf",
+    "oneOf",
+    "allOf",
+];
+
+/// Whether an object parameter value is a property schema (see
+/// [`convert_schema_to_openai`]) rather than a nested object in simple notation.
+fn is_property_schema(obj: &serde_json::Map<String, Value>) -> bool {
+    SCHEMA_KEYWORDS.iter().any(|key| obj.contains_key(*key))
+        || ["optional", "required"]
+            .iter()
+            .any(|key| obj.get(*key).is_some_and(Value::is_boolean))
+}
+
 /// Convert a type string to a JSON Schema type object.
 ///
 /// This function maps friendly, Rust-like type names to their JSON Schema equivalents.
@@ -1003,6 +1269,12 @@ pub struct ToolBuilder {
 
     /// The input schema, built up through .param() calls or set via .schema()
     schema: Value,
+
+    /// Example argument objects, added through .example() calls
+    examples: Vec<Value>,
+
+    /// Whether the built tool starts enabled
+    enabled: bool,
 }
 
 impl ToolBuilder {
@@ -1033,6 +1305,8 @@ impl ToolBuilder {
             description: description.into(),
             // Start with an empty object schema
             schema: serde_json::json!({}),
+            examples: Vec::new(),
+            enabled: true,
         }
     }
 
@@ -1132,17 +1406,60 @@ impl ToolBuilder {
             self.schema = serde_json::json!({});
         }
 
-        // Get mutable reference to the object. This should always succeed because we just
-        // ensured it's an object above, but we use expect() for defensive programming.
-        let obj = self
-            .schema
-            .as_object_mut()
-            .expect("BUG: schema should be an object after initialization");
-
         // Insert the parameter as a simple type string
         // This will be converted to proper JSON Schema by convert_schema_to_openai
-        obj.insert(name.to_string(), Value::String(type_str.to_string()));
+        if let Value::Object(obj) = &mut self.schema {
+            obj.insert(name.to_string(), Value::String(type_str.to_string()));
+        }
+
+        self
+    }
+
+    /// Add an example argument object showing a correct invocation.
+    ///
+    /// Can be called multiple times. Examples are advisory guidance for the
+    /// model; see [`Tool::with_examples`] for how they're sent.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// # use open_agent::tool;
+    /// # use serde_json::json;
+    /// let convert = tool("convert_units", "Convert between units")
+    ///     .param("value", "number")
+    ///     .param("from", "string")
+    ///     .param("to", "string")
+    ///     .example(json!({"value": 10, "from": "km", "to": "mi"}))
+    ///     .example(json!({"value": 212, "from": "F", "to": "C"}))
+    ///     .build(|_| async { Ok(json!({})) });
+    ///
+    /// assert_eq!(convert.examples().len(), 2);
+    /// ```
+    pub fn example(mut self, input: Value) -> Self {
+        self.examples.push(input);
+        self
+    }
 
+    /// Set whether the tool starts enabled (the default).
+    ///
+    /// A disabled tool can be registered and switched on later for a
+    /// conversation with [`Client::set_tool_enabled`](crate::Client::set_tool_enabled);
+    /// until then the model doesn't see it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use open_agent::tool;
+    /// # use serde_json::json;
+    /// let shell = tool("run_shell", "Run a shell command")
+    ///     .param("command", "string")
+    ///     .enabled(false)
+    ///     .build(|_| async { Ok(json!({})) });
+    ///
+    /// assert!(!shell.is_enabled());
+    /// ```
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
         self
     }
 
@@ -1218,7 +1535,39 @@ impl ToolBuilder {
         Fut: Future<Output = Result<Value>> + Send + 'static,
     {
         // Delegate to Tool::new which handles schema conversion and handler wrapping
-        Tool::new(self.name, self.description, self.schema, handler)
+        let mut tool = Tool::new(self.name, self.description, self.schema, handler)
+            .with_examples(self.examples);
+        tool.enabled = self.enabled;
+        tool
+    }
+
+    /// Build the final Tool with a handler that can return images.
+    ///
+    /// Like [`build`](ToolBuilder::build), but the handler returns a
+    /// [`ToolOutput`]: the JSON result plus any images, such as a rendered chart,
+    /// for a vision model to look at. Under auto-execution the images are
+    /// attached to the call's [`ToolResultBlock`](crate::ToolResultBlock).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use open_agent::{tool, ImageBlock, ToolOutput};
+    /// # use serde_json::json;
+    /// let plot = tool("plot_sales", "Plot monthly sales as a chart")
+    ///     .build_with_images(|_args| async move {
+    ///         let png = ImageBlock::from_base64("iVBORw0KGgo=", "image/png")?;
+    ///         Ok(ToolOutput::new(json!({"months": 12})).with_image(png))
+    ///     });
+    /// ```
+    pub fn build_with_images<F, Fut>(self, handler: F) -> Tool
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolOutput>> + Send + 'static,
+    {
+        let mut tool = Tool::with_output_handler(self.name, self.description, self.schema, handler)
+            .with_examples(self.examples);
+        tool.enabled = self.enabled;
+        tool
     }
 }
 
@@ -1389,6 +1738,110 @@ pub fn tool(name: impl Into<String>, description: impl Into<String>) -> ToolBuil
     ToolBuilder::new(name, description)
 }
 
+/// A named collection of tools shared across agents
+///
+/// Applications with a large tool library can build one registry at startup and
+/// hand all of it, or a [`subset`](ToolRegistry::subset), to each agent via
+/// [`AgentOptionsBuilder::tool_registry`](crate::AgentOptionsBuilder::tool_registry).
+/// Tools are keyed by name, so inserting a tool with an existing name replaces the
+/// old one. Tools are stored as `Arc<Tool>`, so registering them with many agents
+/// doesn't copy handlers or schemas.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use open_agent::{AgentOptions, ToolRegistry, tool};
+/// use serde_json::json;
+///
+/// # fn example() -> open_agent::Result<()> {
+/// let mut registry = ToolRegistry::new();
+/// registry.insert(tool("search", "Search the docs").param("query", "string").build(
+///     |_args| async move { Ok(json!({"results": []})) },
+/// ));
+/// registry.insert(tool("read_file", "Read a file").param("path", "string").build(
+///     |_args| async move { Ok(json!({"content": ""})) },
+/// ));
+///
+/// // A research agent only gets the search tool
+/// let options = AgentOptions::builder()
+///     .model("qwen2.5-32b-instruct")
+///     .base_url("http://localhost:1234/v1")
+///     .tool_registry(&registry.subset(&["search"]))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ToolRegistry {
+    /// Tools keyed by name
+    tools: HashMap<String, Arc<Tool>>,
+}
+
+impl ToolRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tool, returning the tool it replaced if the name was taken
+    pub fn insert(&mut self, tool: Tool) -> Option<Arc<Tool>> {
+        self.insert_arc(Arc::new(tool))
+    }
+
+    /// Adds an already shared tool, returning the tool it replaced if the name was taken
+    pub fn insert_arc(&mut self, tool: Arc<Tool>) -> Option<Arc<Tool>> {
+        self.tools.insert(tool.name().to_string(), tool)
+    }
+
+    /// Looks up a tool by name
+    pub fn get(&self, name: &str) -> Option<&Arc<Tool>> {
+        self.tools.get(name)
+    }
+
+    /// Removes a tool by name, returning it if it was registered
+    pub fn remove(&mut self, name: &str) -> Option<Arc<Tool>> {
+        self.tools.remove(name)
+    }
+
+    /// Returns the registered tool names in sorted order
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.tools.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the number of registered tools
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Returns true if no tools are registered
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Returns a new registry holding only the named tools
+    ///
+    /// Names that aren't registered are skipped.
+    pub fn subset(&self, names: &[&str]) -> ToolRegistry {
+        let tools = names
+            .iter()
+            .filter_map(|name| self.tools.get_key_value(*name))
+            .map(|(name, tool)| (name.clone(), Arc::clone(tool)))
+            .collect();
+        ToolRegistry { tools }
+    }
+
+    /// Returns the tools sorted by name
+    ///
+    /// Sorting keeps the order tools are sent to the model stable between runs.
+    pub fn into_vec(self) -> Vec<Arc<Tool>> {
+        let mut tools: Vec<Arc<Tool>> = self.tools.into_values().collect();
+        tools.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+        tools
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1470,6 +1923,31 @@ mod tests {
         assert!(format["function"]["parameters"].is_object());
     }
 
+    #[test]
+    fn test_tool_examples_in_openai_format() {
+        let plain = tool("test", "Test tool")
+            .param("q", "string")
+            .build(|_| async { Ok(json!({})) });
+        assert!(
+            plain.to_openai_format()["function"]["parameters"]
+                .get("examples")
+                .is_none()
+        );
+
+        let with_examples = tool("test", "Test tool")
+            .param("q", "string")
+            .example(json!({"q": "first"}))
+            .example(json!({"q": "second"}))
+            .build(|_| async { Ok(json!({})) });
+        let format = with_examples.to_openai_format();
+        assert_eq!(
+            format["function"]["parameters"]["examples"],
+            json!([{"q": "first"}, {"q": "second"}])
+        );
+        // The stored schema itself is untouched
+        assert!(with_examples.input_schema().get("examples").is_none());
+    }
+
     #[test]
     fn test_param_after_non_object_schema() {
         // Edge case: calling .param() after setting schema to non-object
@@ -1485,4 +1963,115 @@ mod tests {
         assert!(format["function"]["parameters"].is_object());
         assert!(format["function"]["parameters"]["properties"]["key"].is_object());
     }
+
+    #[test]
+    fn test_tool_registry() {
+        let make = |name: &str, description: &str| {
+            tool(name, description).build(|_| async { Ok(json!({})) })
+        };
+
+        let mut registry = ToolRegistry::new();
+        assert!(registry.insert(make("search", "v1")).is_none());
+        assert!(registry.insert(make("add", "Add")).is_none());
+
+        // Same name replaces
+        let old = registry.insert(make("search", "v2")).unwrap();
+        assert_eq!(old.description(), "v1");
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.get("search").unwrap().description(), "v2");
+        assert_eq!(registry.names(), vec!["add", "search"]);
+
+        let subset = registry.subset(&["search", "missing"]);
+        assert_eq!(subset.names(), vec!["search"]);
+        // Subsets share the same tools
+        assert!(Arc::ptr_eq(
+            subset.get("search").unwrap(),
+            registry.get("search").unwrap()
+        ));
+
+        let names: Vec<_> = registry
+            .into_vec()
+            .iter()
+            .map(|t| t.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["add", "search"]);
+    }
+
+    #[test]
+    fn test_convert_nested_schema() {
+        let schema = convert_schema_to_openai(json!({
+            "filter": {
+                "status": "string",
+                "range": {"from": "integer", "to": {"type": "integer", "optional": true}}
+            },
+            "sort": {"enum": ["asc", "desc"], "optional": true},
+            "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}},
+            "options": {
+                "type": "object",
+                "properties": {"verbose": {"type": "boolean"}},
+                "required": ["verbose"]
+            }
+        }));
+
+        assert_eq!(
+            schema["properties"]["filter"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "status": {"type": "string"},
+                    "range": {
+                        "type": "object",
+                        "properties": {
+                            "from": {"type": "integer"},
+                            "to": {"type": "integer"}
+                        },
+                        "required": ["from"]
+                    }
+                },
+                "required": ["range", "status"]
+            })
+        );
+
+        // Property schemas pass through, minus the per-property flags
+        assert_eq!(
+            schema["properties"]["sort"],
+            json!({"enum": ["asc", "desc"]})
+        );
+        assert_eq!(
+            schema["properties"]["tags"]["items"],
+            json!({"$ref": "#/$defs/tag"})
+        );
+        assert_eq!(
+            schema["properties"]["options"]["required"],
+            json!(["verbose"])
+        );
+
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&json!("filter")));
+        assert!(required.contains(&json!("options")));
+        assert!(!required.contains(&json!("sort")));
+    }
+
+    #[tokio::test]
+    async fn test_stateful_tool_shares_state() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let tool = Tool::stateful(
+            "limited",
+            "Succeeds twice",
+            json!({}),
+            AtomicU32::new(2),
+            |tokens, _args| async move {
+                tokens
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .map(|before| json!({"left": before - 1}))
+                    .map_err(|_| Error::tool("Rate limit reached"))
+            },
+        );
+        let clone = tool.clone();
+
+        assert_eq!(tool.execute(json!({})).await.unwrap()["left"], 1);
+        assert_eq!(clone.execute(json!({})).await.unwrap()["left"], 0);
+        assert!(tool.execute(json!({})).await.is_err());
+    }
 }
@@ -46,9 +46,10 @@
 //!     ▼
 //! Stream<OpenAIChunk>
 //!     │
-//!     │ ToolCallAggregator::process_chunk()
+//!     │ ToolCallAggregator::process_chunk_events()
 //!     ▼
-//! Vec<ContentBlock>  (only when finish_reason is present)
+//! Vec<StreamEvent>  (argument fragments as they arrive; complete
+//!                    ContentBlocks only when finish_reason is present)
 //! ```
 //!
 //! ## Example: Text Response
@@ -83,10 +84,10 @@
 //! strings, making the API unusable. This module ensures that all tool calls are fully assembled
 //! and validated before being exposed to the application.
 
-use crate::types::{ContentBlock, OpenAIChunk, TextBlock, ToolUseBlock};
+use crate::types::{ContentBlock, OpenAIChunk, StreamEvent, TextBlock, ToolUseBlock};
 use crate::{Error, Result};
 use futures::stream::{Stream, StreamExt};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::pin::Pin;
 
 /// Aggregates streaming deltas into complete content blocks.
@@ -103,10 +104,10 @@ use std::pin::Pin;
 ///    are concatenated as they arrive. When generation finishes, the complete text is
 ///    emitted as a [`ContentBlock::Text`].
 ///
-/// 2. **Tool Call Map** (`tool_calls`): A HashMap indexed by tool call index (provided by
-///    the API) that tracks partially-received tool calls. Each entry accumulates the tool's
+/// 2. **Tool Call Map** (`tool_calls`): An ordered map of partially-received tool calls,
+///    routed by the tool call index provided by the API. Each entry accumulates the tool's
 ///    ID, name, and JSON argument string. When generation finishes, all tool calls are
-///    emitted as [`ContentBlock::ToolUse`] blocks.
+///    emitted as [`ContentBlock::ToolUse`] blocks, in the order they first appeared.
 ///
 /// # Why Index-Based Storage?
 ///
@@ -121,7 +122,15 @@ use std::pin::Pin;
 /// Chunk 6: tool_calls[1] = { arguments: ":\"2+2\"}" }
 /// ```
 ///
-/// The HashMap keyed by index allows us to correctly accumulate each tool call independently.
+/// Routing by index allows us to correctly accumulate each tool call independently.
+///
+/// # Complete Tool Calls
+///
+/// Some servers don't stream arguments at all: they send each tool call (or the whole
+/// `tool_calls` array) complete in one chunk, sometimes without an `index`, which then
+/// defaults to 0. A delta that carries a new id at an index already holding a different
+/// call starts a new call rather than being merged into the previous one, so such a
+/// batch yields one block per call.
 ///
 /// # Usage Pattern
 ///
@@ -151,13 +160,18 @@ use std::pin::Pin;
 ///   tool calls), an empty `Vec<ContentBlock>` is returned.
 pub struct ToolCallAggregator {
     /// Buffer for accumulating text content deltas across chunks.
-    /// Cleared when a finish_reason is encountered.
+    /// Cleared when a finish_reason is encountered. Deltas are decoded JSON
+    /// strings, so only whole characters are appended; nothing here slices text
+    /// by byte index.
     text_buffer: String,
 
-    /// Map of partially-received tool calls, indexed by their API-provided index.
+    /// Partially-received tool calls, keyed by slot in order of first appearance.
     /// Each entry accumulates ID, name, and argument deltas.
     /// Cleared when a finish_reason is encountered.
-    tool_calls: HashMap<u32, PartialToolCall>,
+    tool_calls: BTreeMap<u32, PartialToolCall>,
+
+    /// Slot currently receiving deltas for each API-provided index.
+    slots: HashMap<u32, u32>,
 }
 
 /// Represents an in-progress tool call that is being assembled from deltas.
@@ -196,6 +210,8 @@ pub struct ToolCallAggregator {
 /// 3. The `arguments` string is valid JSON (validated during parsing)
 ///
 /// Incomplete tool calls (missing ID or name) are silently dropped during aggregation.
+/// Tool calls whose arguments are truncated JSON are emitted with
+/// [`ToolUseBlock::is_partial`] set; see [`parse_tool_arguments`].
 #[derive(Debug, Default)]
 struct PartialToolCall {
     /// Unique identifier for the tool call. Usually arrives in the first chunk.
@@ -216,7 +232,8 @@ impl ToolCallAggregator {
     pub fn new() -> Self {
         Self {
             text_buffer: String::new(),
-            tool_calls: HashMap::new(),
+            tool_calls: BTreeMap::new(),
+            slots: HashMap::new(),
         }
     }
 
@@ -234,7 +251,6 @@ impl ToolCallAggregator {
     ///
     /// * `Ok(Vec<ContentBlock>)` - Empty vector if generation is ongoing, or a vector of
     ///   completed blocks when `finish_reason` is encountered
-    /// * `Err(Error)` - If tool call argument JSON is invalid
     ///
     /// # Behavior
     ///
@@ -258,13 +274,40 @@ impl ToolCallAggregator {
     /// assert_eq!(blocks.len(), 1); // Now we have the complete text
     /// ```
     ///
-    /// # Errors
+    /// # Truncated Arguments
+    ///
+    /// Tool call arguments that aren't valid JSON (typically because generation stopped
+    /// mid-call) don't fail the stream. The tool call is emitted with
+    /// [`ToolUseBlock::is_partial`] set so callers can skip it or re-request.
     ///
-    /// Returns an error if tool call arguments cannot be parsed as valid JSON. This indicates
-    /// either a streaming error or malformed data from the API.
+    /// The client streams through [`process_chunk_events`](Self::process_chunk_events);
+    /// this blocks-only view is kept for tests.
+    #[cfg(test)]
     pub fn process_chunk(&mut self, chunk: OpenAIChunk) -> Result<Vec<ContentBlock>> {
-        // Vector to collect completed blocks. Will be empty unless finish_reason is present.
-        let mut blocks = Vec::new();
+        let events = self.process_chunk_events(chunk)?;
+        Ok(events
+            .into_iter()
+            .filter_map(|event| match event {
+                StreamEvent::Block(block) => Some(block),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Processes a single chunk and returns streaming events.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ContentFiltered`] when the chunk finishes with
+    /// `finish_reason: "content_filter"`; buffered content is discarded.
+    ///
+    /// Behaves like [`process_chunk`](Self::process_chunk), but also reports each
+    /// tool call argument fragment as a [`StreamEvent::ToolArgsDelta`] as soon as it
+    /// is buffered. Completed blocks are returned as [`StreamEvent::Block`] after
+    /// any fragments from the same chunk.
+    pub fn process_chunk_events(&mut self, chunk: OpenAIChunk) -> Result<Vec<StreamEvent>> {
+        // Vector to collect events. Blocks only appear once finish_reason is present.
+        let mut events = Vec::new();
 
         // A chunk can contain multiple choices, though typically there's only one.
         // Each choice represents a separate generation path (used in n>1 scenarios).
@@ -283,7 +326,8 @@ impl ToolCallAggregator {
                     // Get or create the partial tool call for this index.
                     // The index is provided by the API and identifies which tool call
                     // this delta belongs to (important when multiple tools are called).
-                    let entry = self.tool_calls.entry(tool_call.index).or_default();
+                    let slot = self.slot_for(tool_call.index, tool_call.id.as_deref());
+                    let entry = self.tool_calls.entry(slot).or_default();
 
                     // Update the ID if present. Usually only in the first delta for this tool.
                     if let Some(id) = tool_call.id {
@@ -304,6 +348,14 @@ impl ToolCallAggregator {
                         // Chunk 3: "\"Paris\"}"
                         if let Some(args) = function.arguments {
                             entry.arguments.push_str(&args);
+
+                            // Surface the fragment for callers rendering calls live
+                            if !args.is_empty() {
+                                events.push(StreamEvent::ToolArgsDelta {
+                                    index: tool_call.index,
+                                    fragment: args,
+                                });
+                            }
                         }
                     }
                 }
@@ -315,40 +367,176 @@ impl ToolCallAggregator {
             // - "tool_calls": Model wants to call tools
             // - "length": Hit max_tokens limit
             // - "content_filter": Content filtered
+            if choice.finish_reason.as_deref() == Some("content_filter") {
+                // The server blocked the response: drop what was buffered and report
+                // it, rather than ending like a normal (partial or empty) reply
+                self.text_buffer.clear();
+                self.tool_calls.clear();
+                self.slots.clear();
+                return Err(Error::content_filtered());
+            }
+
             if choice.finish_reason.is_some() {
                 // === PHASE 3A: FLUSH TEXT BUFFER ===
                 // If we accumulated any text, emit it as a TextBlock
                 if !self.text_buffer.is_empty() {
-                    blocks.push(ContentBlock::Text(TextBlock::new(self.text_buffer.clone())));
+                    events.push(StreamEvent::Block(ContentBlock::Text(TextBlock::new(
+                        self.text_buffer.clone(),
+                    ))));
                     self.text_buffer.clear();
                 }
 
                 // === PHASE 3B: FLUSH AND VALIDATE TOOL CALLS ===
-                // drain() consumes the HashMap, giving us ownership of all partial tool calls
-                for (_, partial) in self.tool_calls.drain() {
+                // Take ownership of all partial tool calls, in order of appearance
+                self.slots.clear();
+                for (index, partial) in std::mem::take(&mut self.tool_calls) {
                     // Only emit tool calls that have both ID and name.
                     // Incomplete tool calls are silently dropped (shouldn't happen with valid API).
                     if let (Some(id), Some(name)) = (partial.id, partial.name) {
-                        // Parse the accumulated JSON argument string.
-                        // If arguments is empty, default to an empty object {}.
-                        let input: serde_json::Value = if partial.arguments.is_empty() {
-                            serde_json::json!({})
-                        } else {
-                            // This is where we validate that all the assembled JSON is valid.
-                            // If the streaming was corrupted or incomplete, this will error.
-                            serde_json::from_str(&partial.arguments).map_err(|e| {
-                                Error::stream(format!("Failed to parse tool arguments: {}", e))
-                            })?
+                        // Parse the accumulated JSON argument string, salvaging what we
+                        // can if it was cut off rather than failing the whole stream.
+                        let block = match parse_tool_arguments(&partial.arguments) {
+                            Ok(input) => ToolUseBlock::new(id, name, input),
+                            Err(input) => {
+                                if input.get("_partial").is_some() {
+                                    log::warn!(
+                                        "Tool call {} (`{}`) has unparseable arguments: {}",
+                                        index,
+                                        name,
+                                        partial.arguments
+                                    );
+                                }
+                                ToolUseBlock::new_partial(index, id, name, input)
+                            }
                         };
 
-                        blocks.push(ContentBlock::ToolUse(ToolUseBlock::new(id, name, input)));
+                        events.push(StreamEvent::Block(ContentBlock::ToolUse(block)));
                     }
                 }
             }
         }
 
-        Ok(blocks)
+        Ok(events)
+    }
+
+    /// Returns the slot for a delta at `index`.
+    ///
+    /// Deltas for an index go to the same slot, unless the delta carries an id
+    /// that differs from the call in that slot: then it is a new call (see
+    /// "Complete Tool Calls" above) and gets the next free slot.
+    fn slot_for(&mut self, index: u32, id: Option<&str>) -> u32 {
+        if let Some(&slot) = self.slots.get(&index) {
+            let current_id = self.tool_calls.get(&slot).and_then(|c| c.id.as_deref());
+            match (current_id, id) {
+                (Some(current), Some(id)) if current != id => {}
+                _ => return slot,
+            }
+        }
+
+        let slot = self
+            .tool_calls
+            .last_key_value()
+            .map_or(0, |(&last, _)| last + 1);
+        self.slots.insert(index, slot);
+        slot
+    }
+}
+
+/// Parses accumulated tool call arguments into JSON.
+///
+/// Empty arguments become an empty object. If the arguments aren't valid JSON,
+/// a best-effort repair of truncated JSON is attempted (see
+/// [`complete_truncated_json`]).
+///
+/// # Returns
+///
+/// * `Ok(value)` - The arguments parsed cleanly
+/// * `Err(value)` - The arguments were truncated. `value` is the repaired JSON if the
+///   repair succeeded, otherwise an object with the raw text under `"_partial"` and
+///   the parser's message under `"_parse_error"`
+fn parse_tool_arguments(
+    arguments: &str,
+) -> std::result::Result<serde_json::Value, serde_json::Value> {
+    if arguments.is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let parse_error = match serde_json::from_str(arguments) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    log::warn!(
+        "Tool arguments are not valid JSON ({}), attempting recovery",
+        parse_error
+    );
+
+    if let Some(repaired) = complete_truncated_json(arguments) {
+        if let Ok(value) = serde_json::from_str(&repaired) {
+            return Err(value);
+        }
+    }
+
+    Err(serde_json::json!({
+        "_partial": arguments,
+        "_parse_error": format!("Failed to parse tool arguments: {}", parse_error),
+    }))
+}
+
+/// Closes the open strings, arrays and objects of truncated JSON.
+///
+/// Handles the common shape of a cut-off stream such as `{"query": "hel` or
+/// `{"items": [1, 2,`. Dangling commas are dropped and a dangling `:` gets a
+/// `null` value. Returns `None` if the text isn't truncated JSON that this
+/// simple scan understands (e.g. it has unbalanced closing brackets).
+fn complete_truncated_json(raw: &str) -> Option<String> {
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in raw.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' if closers.pop() != Some(c) => return None,
+            _ => {}
+        }
+    }
+
+    let mut repaired = raw.to_string();
+
+    if in_string {
+        // A trailing lone backslash would escape our closing quote
+        if escaped {
+            repaired.pop();
+        }
+        repaired.push('"');
+    } else {
+        let trimmed_len = repaired.trim_end().len();
+        repaired.truncate(trimmed_len);
+        if repaired.ends_with(',') {
+            repaired.pop();
+        } else if repaired.ends_with(':') {
+            repaired.push_str("null");
+        }
+    }
+
+    while let Some(closer) = closers.pop() {
+        repaired.push(closer);
     }
+
+    Some(repaired)
 }
 
 /// Parses a raw HTTP response body as a Server-Sent Events (SSE) stream.
@@ -378,6 +566,7 @@ impl ToolCallAggregator {
 /// # Arguments
 ///
 /// * `body` - The raw HTTP response from the API request
+/// * `max_line_bytes` - Longest SSE line accepted, in bytes
 ///
 /// # Returns
 ///
@@ -389,9 +578,10 @@ impl ToolCallAggregator {
 /// Each stream item can be an error:
 /// - **HTTP errors**: Network issues, connection drops (wrapped as [`Error::Http`])
 /// - **Parse errors**: Invalid JSON in the SSE data field (wrapped as [`Error::Stream`])
-/// - **Protocol errors**: SSE chunks that don't contain a `data:` line (wrapped as [`Error::Stream`])
+/// - **Oversized lines**: A line longer than `max_line_bytes` (wrapped as [`Error::Api`])
 ///
-/// Errors are per-chunk, not fatal to the stream. Consumers should handle errors gracefully.
+/// Parse errors are per-event, not fatal to the stream. HTTP errors and oversized lines
+/// end it. Consumers should handle errors gracefully.
 ///
 /// # Example Flow
 ///
@@ -400,8 +590,13 @@ impl ToolCallAggregator {
 ///        ↓
 /// bytes_stream() splits into chunks
 ///        ↓
-/// Parse each chunk:
-///   - Find lines starting with "data: "
+/// Buffer bytes into complete lines
+///        ↓
+/// Parse each line as an SSE field:
+///   - Accumulate "data:" lines until a blank line ends the event
+///   - Skip ":" comments and unknown fields
+///        ↓
+/// Dispatch each event:
 ///   - Skip "[DONE]" sentinel
 ///   - Parse JSON into OpenAIChunk
 ///        ↓
@@ -415,16 +610,26 @@ impl ToolCallAggregator {
 ///
 /// - **Chunk boundaries**: HTTP streaming can split data at arbitrary byte positions. Each
 ///   `bytes_stream()` chunk may contain partial events, complete events, or multiple events.
-///   The line-by-line parsing handles this naturally.
+///   Bytes are buffered until a full line has arrived, and every line in a chunk is parsed.
+///
+/// - **Event format**: Lines follow the SSE spec. Comment lines (starting with `:`),
+///   such as the `: ping` keep-alives some proxies inject, are skipped. An event's
+///   `data:` lines are joined with newlines and dispatched at the blank line that
+///   ends it. Events with an `event:` type other than `message` are skipped, and
+///   unknown fields (e.g. `retry:`) are ignored.
+///
+/// - **Line limit**: A line or event longer than `max_line_bytes` ends the stream with an
+///   [`Error::Api`] instead of being buffered without bound, so a misbehaving server
+///   can't exhaust memory with a single enormous event.
 ///
-/// - **UTF-8 handling**: We use `from_utf8_lossy()` to be resilient to split UTF-8 sequences
-///   at chunk boundaries, though the API should always send well-formed UTF-8.
+/// - **UTF-8 handling**: Lines are decoded only once complete, so multi-byte characters
+///   split across chunks survive. Invalid UTF-8 is replaced rather than failing the stream.
 ///
 /// # Usage
 ///
 /// ```rust,ignore
 /// let response = client.post(url).send().await?;
-/// let mut stream = parse_sse_stream(response);
+/// let mut stream = parse_sse_stream(response, 8 * 1024 * 1024);
 ///
 /// while let Some(result) = stream.next().await {
 ///     match result {
@@ -435,51 +640,226 @@ impl ToolCallAggregator {
 /// ```
 pub fn parse_sse_stream(
     body: reqwest::Response,
+    max_line_bytes: usize,
 ) -> Pin<Box<dyn Stream<Item = Result<OpenAIChunk>> + Send>> {
-    let stream = body.bytes_stream().filter_map(move |result| async move {
-        // Convert HTTP errors to our Error type
-        let bytes = match result.map_err(Error::Http) {
-            Ok(b) => b,
-            Err(e) => return Some(Err(e)),
-        };
+    Box::pin(parse_sse_events(body, max_line_bytes).map(|event| event.map(|e| e.chunk)))
+}
+
+/// A parsed chunk together with the SSE event id in effect when it arrived.
+#[derive(Debug)]
+pub(crate) struct SseChunk {
+    /// Value of the most recent `id:` field, if the server sends them
+    pub id: Option<String>,
+    /// The parsed `data:` payload
+    pub chunk: OpenAIChunk,
+}
+
+/// Like [`parse_sse_stream`], but keeps each chunk's SSE event id.
+///
+/// Used to resume dropped streams with `Last-Event-ID`. Ids follow the SSE rule
+/// that an `id:` field stays in effect until the next one, so an event without
+/// its own id keeps the previous event's.
+pub(crate) fn parse_sse_events(
+    body: reqwest::Response,
+    max_line_bytes: usize,
+) -> Pin<Box<dyn Stream<Item = Result<SseChunk>> + Send>> {
+    // Convert HTTP errors to our Error type
+    let bytes = body
+        .bytes_stream()
+        .map(|result| result.map_err(Error::Http));
+    parse_sse_bytes(bytes, max_line_bytes)
+}
 
-        // Convert bytes to string. Use lossy conversion to handle potential
-        // UTF-8 boundary splits (though the API should send well-formed UTF-8).
-        let text = String::from_utf8_lossy(&bytes);
-
-        // Parse SSE format by examining each line.
-        // Format: "data: <payload>\n\n"
-        // Lines not starting with "data: " are ignored (e.g., comments, event types).
-        for line in text.lines() {
-            if let Some(data) = line.strip_prefix("data: ") {
-                // Skip the end-of-stream sentinel.
-                // OpenAI sends "data: [DONE]" to signal stream completion.
-                if data == "[DONE]" {
-                    continue;
+/// Parses a stream of raw byte chunks as SSE. See [`parse_sse_stream`].
+///
+/// Split out from `parse_sse_events` so the line handling can be tested
+/// without an HTTP response.
+fn parse_sse_bytes<S, B>(
+    bytes: S,
+    max_line_bytes: usize,
+) -> Pin<Box<dyn Stream<Item = Result<SseChunk>> + Send>>
+where
+    S: Stream<Item = Result<B>> + Send + 'static,
+    B: AsRef<[u8]>,
+{
+    let state = SseLineBuffer {
+        bytes: Box::pin(bytes),
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        data: None,
+        event_type: None,
+        last_event_id: None,
+        max_line_bytes,
+        finished: false,
+    };
+
+    // Pin the stream to the heap and box it for dynamic dispatch.
+    // This allows the function to return a uniform type regardless of the
+    // concrete stream implementation.
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        loop {
+            // Hand out chunks parsed from earlier lines first
+            if let Some(item) = state.pending.pop_front() {
+                return Some((item, state));
+            }
+            if state.finished {
+                return None;
+            }
+
+            match state.bytes.next().await {
+                Some(Ok(bytes)) => {
+                    state.buffer.extend_from_slice(bytes.as_ref());
+                    state.drain_lines(false);
                 }
+                Some(Err(e)) => {
+                    state.finished = true;
+                    return Some((Err(e), state));
+                }
+                None => {
+                    // Parse a final line that wasn't newline-terminated
+                    state.finished = true;
+                    state.drain_lines(true);
+                }
+            }
+        }
+    }))
+}
+
+/// State for [`parse_sse_bytes`]: HTTP chunks are split at arbitrary byte
+/// positions, so partial lines are buffered until their newline arrives.
+struct SseLineBuffer<S> {
+    /// Raw byte chunks from the response body
+    bytes: Pin<Box<S>>,
+    /// Bytes of the current, not yet terminated line
+    buffer: Vec<u8>,
+    /// Parsed chunks waiting to be yielded
+    pending: VecDeque<Result<SseChunk>>,
+    /// `data:` lines of the current event, joined with newlines
+    data: Option<String>,
+    /// `event:` type of the current event, if given
+    event_type: Option<String>,
+    /// Value of the last `id:` field seen
+    last_event_id: Option<String>,
+    /// Longest line accepted before the stream is failed
+    max_line_bytes: usize,
+    /// Set once the body ends or an error ends the stream
+    finished: bool,
+}
+
+impl<S> SseLineBuffer<S> {
+    /// Parses every complete line in the buffer, enforcing the line limit
+    fn drain_lines(&mut self, at_end: bool) {
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            if newline > self.max_line_bytes {
+                self.fail_oversized();
+                return;
+            }
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            self.parse_line(&line[..newline]);
+        }
+
+        // A partial line is checked too, so one endless line can't grow unbounded
+        if self.buffer.len() > self.max_line_bytes {
+            self.fail_oversized();
+        } else if at_end {
+            if !self.buffer.is_empty() {
+                let line = std::mem::take(&mut self.buffer);
+                self.parse_line(&line);
+            }
+            // Be lenient with a final event that lacks its blank line
+            self.dispatch_event();
+        }
+    }
+
+    /// Parses one SSE line, accumulating fields until a blank line ends the event
+    fn parse_line(&mut self, line: &[u8]) {
+        // Tolerate CRLF line endings. Use lossy conversion in case the server
+        // sends malformed UTF-8.
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = String::from_utf8_lossy(line);
+
+        // A blank line ends the event
+        if line.is_empty() {
+            self.dispatch_event();
+            return;
+        }
+
+        // Comments, e.g. ": ping" keep-alives
+        if line.starts_with(':') {
+            return;
+        }
+
+        // "field: value", where the space is optional and a line without a
+        // colon is a field with an empty value
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
 
-                // Parse the JSON payload into an OpenAIChunk.
-                // This is where we deserialize the actual chunk data.
-                let chunk: OpenAIChunk = match serde_json::from_str(data) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        return Some(Err(Error::stream(format!("Failed to parse chunk: {}", e))));
+        match field {
+            "data" => {
+                let length = match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                        data.len()
                     }
+                    None => self.data.insert(value.to_string()).len(),
                 };
-
-                return Some(Ok(chunk));
+                if length > self.max_line_bytes {
+                    self.fail_oversized();
+                }
             }
+            "event" => self.event_type = Some(value.to_string()),
+            // Event ids let a dropped stream be resumed with Last-Event-ID.
+            // Per the SSE spec, ids containing NUL are ignored.
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            // "retry" and unknown fields are ignored
+            _ => {}
         }
+    }
 
-        // If we processed all lines and found no "data: " line, skip this chunk.
-        // This handles heartbeats, comments, and other SSE events gracefully.
-        None
-    });
+    /// Queues a chunk for the event accumulated so far, if it carries data
+    fn dispatch_event(&mut self) {
+        let event_type = self.event_type.take();
+        let Some(data) = self.data.take() else {
+            return;
+        };
 
-    // Pin the stream to the heap and box it for dynamic dispatch.
-    // This allows the function to return a uniform type regardless of the
-    // concrete stream implementation.
-    Box::pin(stream)
+        // Chat chunks arrive as unnamed (or "message") events
+        if let Some(event_type) = event_type.filter(|t| t != "message") {
+            log::debug!("Skipping SSE event of type '{}'", event_type);
+            return;
+        }
+
+        // Skip the end-of-stream sentinel.
+        // OpenAI sends "data: [DONE]" to signal stream completion.
+        if data == "[DONE]" {
+            return;
+        }
+
+        // Parse the JSON payload into an OpenAIChunk.
+        // This is where we deserialize the actual chunk data.
+        self.pending.push_back(
+            serde_json::from_str(&data)
+                .map(|chunk| SseChunk {
+                    id: self.last_event_id.clone(),
+                    chunk,
+                })
+                .map_err(|e| Error::stream(format!("Failed to parse chunk: {}", e))),
+        );
+    }
+
+    /// Ends the stream with an error for a line over the limit
+    fn fail_oversized(&mut self) {
+        self.buffer = Vec::new();
+        self.data = None;
+        self.finished = true;
+        self.pending.push_back(Err(Error::api(format!(
+            "SSE line exceeded limit of {} bytes",
+            self.max_line_bytes
+        ))));
+    }
 }
 
 #[cfg(test)]
@@ -505,6 +885,7 @@ mod tests {
                 },
                 finish_reason: None,
             }],
+            usage: None,
         };
 
         let blocks = aggregator.process_chunk(chunk).unwrap();
@@ -524,6 +905,7 @@ mod tests {
                 },
                 finish_reason: Some("stop".to_string()),
             }],
+            usage: None,
         };
 
         let blocks = aggregator.process_chunk(chunk2).unwrap();
@@ -561,6 +943,7 @@ mod tests {
                 },
                 finish_reason: None,
             }],
+            usage: None,
         };
 
         let blocks = aggregator.process_chunk(chunk).unwrap();
@@ -588,6 +971,7 @@ mod tests {
                 },
                 finish_reason: Some("tool_calls".to_string()),
             }],
+            usage: None,
         };
 
         let blocks = aggregator.process_chunk(chunk2).unwrap();
@@ -602,4 +986,471 @@ mod tests {
             _ => panic!("Expected tool use block"),
         }
     }
+
+    fn tool_chunk(arguments: &str, finish_reason: Option<&str>) -> OpenAIChunk {
+        OpenAIChunk {
+            id: "test".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "test".to_string(),
+            choices: vec![OpenAIChoice {
+                index: 0,
+                delta: OpenAIDelta {
+                    role: None,
+                    content: None,
+                    tool_calls: Some(vec![OpenAIToolCallDelta {
+                        index: 0,
+                        id: Some("call_1".to_string()),
+                        call_type: Some("function".to_string()),
+                        function: Some(OpenAIFunctionDelta {
+                            name: Some("search".to_string()),
+                            arguments: Some(arguments.to_string()),
+                        }),
+                    }]),
+                },
+                finish_reason: finish_reason.map(|s| s.to_string()),
+            }],
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_tool_argument_fragments_precede_block() {
+        let mut aggregator = ToolCallAggregator::new();
+
+        let mut fragments = Vec::new();
+        for (arguments, finish) in [(r#"{"q":"hel"#, None), (r#"lo"}"#, Some("tool_calls"))] {
+            for event in aggregator
+                .process_chunk_events(tool_chunk(arguments, finish))
+                .unwrap()
+            {
+                match event {
+                    StreamEvent::ToolArgsDelta { index, fragment } => {
+                        assert_eq!(index, 0);
+                        fragments.push(fragment);
+                    }
+                    StreamEvent::Block(ContentBlock::ToolUse(tool)) => {
+                        // The completed call arrives after every fragment
+                        assert_eq!(fragments.concat(), r#"{"q":"hello"}"#);
+                        assert_eq!(tool.input()["q"], "hello");
+                        return;
+                    }
+                    other => panic!("unexpected event: {:?}", other),
+                }
+            }
+        }
+        panic!("tool call was never completed");
+    }
+
+    #[test]
+    fn test_content_filter_finish_reason_is_an_error() {
+        let mut aggregator = ToolCallAggregator::new();
+        assert!(
+            aggregator
+                .process_chunk(tool_chunk(r#"{"q":"#, None))
+                .unwrap()
+                .is_empty()
+        );
+
+        let err = aggregator
+            .process_chunk(tool_chunk("", Some("content_filter")))
+            .unwrap_err();
+        assert!(matches!(err, Error::ContentFiltered));
+
+        // Filtered content doesn't leak into the next turn
+        let blocks = aggregator
+            .process_chunk(tool_chunk("{}", Some("tool_calls")))
+            .unwrap();
+        let ContentBlock::ToolUse(tool) = &blocks[0] else {
+            panic!("expected a tool call");
+        };
+        assert!(!tool.is_partial());
+    }
+
+    #[test]
+    fn test_truncated_tool_arguments_are_repaired() {
+        let mut aggregator = ToolCallAggregator::new();
+        let blocks = aggregator
+            .process_chunk(tool_chunk(r#"{"query": "hel"#, Some("length")))
+            .unwrap();
+
+        match &blocks[0] {
+            ContentBlock::ToolUse(tool) => {
+                assert!(tool.is_partial());
+                assert_eq!(tool.input()["query"], "hel");
+            }
+            _ => panic!("Expected tool use block"),
+        }
+    }
+
+    #[test]
+    fn test_unrepairable_tool_arguments_keep_raw_text() {
+        let mut aggregator = ToolCallAggregator::new();
+        let blocks = aggregator
+            .process_chunk(tool_chunk(r#"{"query" "#, Some("length")))
+            .unwrap();
+
+        match &blocks[0] {
+            ContentBlock::ToolUse(tool) => {
+                assert!(tool.is_partial());
+                assert_eq!(tool.input()["_partial"], r#"{"query" "#);
+                assert!(
+                    tool.input()["_parse_error"]
+                        .as_str()
+                        .unwrap()
+                        .starts_with("Failed to parse tool arguments")
+                );
+            }
+            _ => panic!("Expected tool use block"),
+        }
+    }
+
+    #[test]
+    fn test_complete_truncated_json() {
+        let cases = [
+            (r#"{"a": "b"#, r#"{"a": "b"}"#),
+            (r#"{"a": [1, 2,"#, r#"{"a": [1, 2]}"#),
+            (r#"{"a":"#, r#"{"a":null}"#),
+            (r#"{"a": {"b": "c\"#, r#"{"a": {"b": "c"}}"#),
+            (r#"{"a": "}"#, r#"{"a": "}"}"#),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(complete_truncated_json(input).as_deref(), Some(expected));
+        }
+
+        assert_eq!(complete_truncated_json(r#"{"a": 1}}"#), None);
+
+        // Complete arguments are not flagged as partial
+        assert_eq!(
+            parse_tool_arguments(r#"{"a": 1}"#),
+            Ok(serde_json::json!({"a": 1}))
+        );
+        assert_eq!(parse_tool_arguments(""), Ok(serde_json::json!({})));
+    }
+
+    /// Runs raw byte chunks through the SSE parser and collects the results
+    async fn parse_chunks(chunks: Vec<Vec<u8>>, max_line_bytes: usize) -> Vec<Result<SseChunk>> {
+        let bytes = futures::stream::iter(chunks.into_iter().map(Ok));
+        parse_sse_bytes(bytes, max_line_bytes).collect().await
+    }
+
+    const SSE_EVENT: &str = r#"data: {"id":"c1","object":"chat.completion.chunk","created":0,"model":"m","choices":[{"index":0,"delta":{"content":"héllo"},"finish_reason":null}]}"#;
+
+    #[tokio::test]
+    async fn test_sse_lines_split_across_chunks() {
+        // Two events in one body, split mid-character
+        let body = format!("{}\r\n\n{}\n\ndata: [DONE]", SSE_EVENT, SSE_EVENT).into_bytes();
+        let split = SSE_EVENT.find('é').unwrap() + 1;
+        let chunks = vec![body[..split].to_vec(), body[split..].to_vec()];
+
+        let results = parse_chunks(chunks, 1024).await;
+        assert_eq!(results.len(), 2);
+        for result in results {
+            let chunk = result.unwrap().chunk;
+            assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("héllo"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_line_limit() {
+        // Oversized line split over several chunks, never terminated
+        let chunks = vec![b"data: ".to_vec(), vec![b'x'; 600], vec![b'x'; 600]];
+        let results = parse_chunks(chunks, 1024).await;
+
+        assert_eq!(results.len(), 1);
+        let err = results.into_iter().next().unwrap().unwrap_err();
+        assert!(matches!(err, Error::Api(_)));
+        assert!(err.to_string().contains("SSE line exceeded limit"));
+
+        // A line exactly at the limit parses normally
+        let chunks = vec![SSE_EVENT.as_bytes().to_vec(), b"\n".to_vec()];
+        let results = parse_chunks(chunks, SSE_EVENT.len()).await;
+        assert!(results[0].is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sse_event_ids_are_tracked() {
+        let body = format!(
+            "{}\n\nid: 7\n{}\n\nid: 8\n{}\n\n",
+            SSE_EVENT, SSE_EVENT, SSE_EVENT
+        );
+        let ids: Vec<Option<String>> = parse_chunks(vec![body.into_bytes()], 1024)
+            .await
+            .into_iter()
+            .map(|result| result.unwrap().id)
+            .collect();
+
+        assert_eq!(
+            ids,
+            vec![None, Some("7".to_string()), Some("8".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_comments_and_multiline_data() {
+        let payload = &SSE_EVENT["data: ".len()..];
+        let (head, tail) = payload.split_at(payload.find("\"choices\"").unwrap());
+        let body = format!(
+            ": ping\n\n\
+             event: message\nretry: 1000\ndata:{}\n: ping\ndata: {}\n\n\
+             : keep-alive\n\n\
+             event: status\ndata: {{\"queued\": 3}}\n\n\
+             {}\n\n",
+            head, tail, SSE_EVENT
+        );
+        let results = parse_chunks(vec![body.into_bytes()], 1024).await;
+
+        // The split event is reassembled and the custom event type is skipped
+        assert_eq!(results.len(), 2);
+        for result in results {
+            let chunk = result.unwrap().chunk;
+            assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("héllo"));
+        }
+    }
+
+    #[test]
+    fn test_unparseable_tool_arguments_error() {
+        let mut aggregator = ToolCallAggregator::new();
+        let blocks = aggregator
+            .process_chunk(tool_chunk(r#"{"query" "rust"}"#, Some("tool_calls")))
+            .unwrap();
+        let ContentBlock::ToolUse(tool) = &blocks[0] else {
+            panic!("expected a tool call");
+        };
+
+        match tool.arguments_error() {
+            Some(Error::ToolArguments {
+                tool, index, raw, ..
+            }) => {
+                assert_eq!(tool, "search");
+                assert_eq!(index, Some(0));
+                assert_eq!(raw, r#"{"query" "rust"}"#);
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+
+        // Repaired arguments are usable, so there's no error
+        let blocks = aggregator
+            .process_chunk(tool_chunk(r#"{"query": "hel"#, Some("length")))
+            .unwrap();
+        let ContentBlock::ToolUse(tool) = &blocks[0] else {
+            panic!("expected a tool call");
+        };
+        assert!(tool.arguments_error().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multibyte_text_split_at_every_byte() {
+        // Emoji (4 bytes), Japanese (3 bytes) and a ZWJ sequence, streamed as
+        // separate deltas and then cut into one-byte HTTP chunks
+        let deltas = ["こんにちは", "🦀", " 世界", "👩‍💻!"];
+        let mut body = String::new();
+        for (i, delta) in deltas.iter().enumerate() {
+            let finish_reason = if i == deltas.len() - 1 {
+                serde_json::json!("stop")
+            } else {
+                serde_json::Value::Null
+            };
+            let event = serde_json::json!({
+                "id": "c1",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "m",
+                "choices": [{"index": 0, "delta": {"content": delta}, "finish_reason": finish_reason}]
+            });
+            body.push_str(&format!("data: {}\n\n", event));
+        }
+        body.push_str("data: [DONE]\n\n");
+        let chunks = body.bytes().map(|b| vec![b]).collect();
+
+        let mut aggregator = ToolCallAggregator::new();
+        let mut blocks = Vec::new();
+        for result in parse_chunks(chunks, 1024).await {
+            blocks.extend(aggregator.process_chunk(result.unwrap().chunk).unwrap());
+        }
+
+        assert_eq!(blocks.len(), 1);
+        match &blocks[0] {
+            ContentBlock::Text(text) => assert_eq!(text.text, "こんにちは🦀 世界👩‍💻!"),
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_complete_tool_calls_in_one_chunk() {
+        // The whole tool_calls array in a single chunk, without indexes and with
+        // arguments given as a JSON object, as some servers send it
+        let chunk: OpenAIChunk = serde_json::from_str(
+            r#"{
+                "id": "c1", "object": "chat.completion.chunk", "created": 0, "model": "m",
+                "choices": [{
+                    "index": 0,
+                    "delta": {"tool_calls": [
+                        {"id": "call_1", "type": "function",
+                         "function": {"name": "search", "arguments": "{\"q\":\"rust\"}"}},
+                        {"id": "call_2", "type": "function",
+                         "function": {"name": "fetch", "arguments": {"url": "https://example.com"}}}
+                    ]},
+                    "finish_reason": "tool_calls"
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let mut aggregator = ToolCallAggregator::new();
+        let blocks = aggregator.process_chunk(chunk).unwrap();
+
+        let calls: Vec<_> = blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::ToolUse(tool) => (tool.id(), tool.name(), tool.input().clone()),
+                other => panic!("expected tool use, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                ("call_1", "search", serde_json::json!({"q": "rust"})),
+                (
+                    "call_2",
+                    "fetch",
+                    serde_json::json!({"url": "https://example.com"})
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_calls_emitted_in_index_order() {
+        let mut aggregator = ToolCallAggregator::new();
+        let mut blocks = Vec::new();
+        for index in 0..8 {
+            let mut chunk = tool_chunk("{}", (index == 7).then_some("tool_calls"));
+            let call = &mut chunk.choices[0].delta.tool_calls.as_mut().unwrap()[0];
+            call.index = index;
+            call.id = Some(format!("call_{}", index));
+            blocks.extend(aggregator.process_chunk(chunk).unwrap());
+        }
+
+        let ids: Vec<_> = blocks
+            .iter()
+            .map(|block| match block {
+                ContentBlock::ToolUse(tool) => tool.id().to_string(),
+                other => panic!("expected tool use, got {:?}", other),
+            })
+            .collect();
+        let expected: Vec<_> = (0..8).map(|i| format!("call_{}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    /// Valid SSE events that the fuzz tests corrupt
+    const FUZZ_SEEDS: [&str; 4] = [
+        SSE_EVENT,
+        r#"data: {"id":"c2","object":"chat.completion.chunk","created":0,"model":"m","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"search","arguments":"{\"q\": \"ü"}}]},"finish_reason":null}]}"#,
+        r#"data: {"id":"c3","object":"chat.completion.chunk","created":0,"model":"m","choices":[{"index":0,"delta":{"tool_calls":[{"index":4294967295,"function":{"arguments":"]]}}"}}]},"finish_reason":"tool_calls"}]}"#,
+        "id: 7\nevent: message\ndata: [DONE]",
+    ];
+
+    /// Corrupts a valid event: flips, inserts and deletes random bytes, or truncates it
+    fn corrupt(rng: &mut rand::rngs::StdRng, event: &str) -> Vec<u8> {
+        use rand::Rng;
+
+        let mut bytes = event.as_bytes().to_vec();
+        for _ in 0..rng.gen_range(0..6) {
+            let at = rng.gen_range(0..=bytes.len());
+            match rng.gen_range(0..4) {
+                0 if at < bytes.len() => bytes[at] = rng.r#gen(),
+                1 => bytes.insert(at, rng.r#gen()),
+                2 if at < bytes.len() => {
+                    bytes.remove(at);
+                }
+                _ => bytes.truncate(at),
+            }
+        }
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_sse_parser_survives_garbage() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5EED);
+        let mut errors = 0;
+
+        for _ in 0..500 {
+            // A body of corrupted events and raw random bytes
+            let mut body = Vec::new();
+            for _ in 0..rng.gen_range(1..6) {
+                if rng.gen_bool(0.2) {
+                    body.extend((0..rng.gen_range(0..64)).map(|_| rng.r#gen::<u8>()));
+                } else {
+                    let event = FUZZ_SEEDS[rng.gen_range(0..FUZZ_SEEDS.len())];
+                    body.extend(corrupt(&mut rng, event));
+                }
+                body.extend_from_slice(if rng.gen_bool(0.8) { b"\n\n" } else { b"\r\n" });
+            }
+
+            // Split at random positions, including inside multi-byte characters
+            let mut chunks = Vec::new();
+            let mut rest = body.as_slice();
+            while !rest.is_empty() {
+                let (chunk, tail) = rest.split_at(rng.gen_range(1..=rest.len()));
+                chunks.push(chunk.to_vec());
+                rest = tail;
+            }
+
+            let max_line_bytes = if rng.gen_bool(0.1) { 16 } else { 1 << 20 };
+            let results = parse_chunks(chunks, max_line_bytes).await;
+            errors += results.iter().filter(|r| r.is_err()).count();
+
+            // Whatever parsed must also survive the aggregator
+            let mut aggregator = ToolCallAggregator::new();
+            for result in results.into_iter().flatten() {
+                let _ = aggregator.process_chunk(result.chunk);
+            }
+        }
+
+        // Corrupted JSON is reported, not swallowed
+        assert!(errors > 0);
+    }
+
+    #[test]
+    fn test_aggregator_survives_adversarial_chunks() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xC4A05);
+        let fragments = [
+            "{", "}", "[", "]", "\"", "\\", ":", ",", "null", "é", "\u{0}", "𝄞", "",
+        ];
+        let ids = [None, Some("call_1"), Some("call_2"), Some("")];
+
+        for _ in 0..500 {
+            let mut aggregator = ToolCallAggregator::new();
+            for _ in 0..rng.gen_range(1..20) {
+                let arguments: String = (0..rng.gen_range(0..8))
+                    .map(|_| fragments[rng.gen_range(0..fragments.len())])
+                    .collect();
+                let mut chunk = tool_chunk(&arguments, None);
+                let choice = &mut chunk.choices[0];
+                let call = &mut choice.delta.tool_calls.as_mut().unwrap()[0];
+                call.index = *[0, 1, u32::MAX].get(rng.gen_range(0..3)).unwrap();
+                call.id = ids[rng.gen_range(0..ids.len())].map(str::to_string);
+                if rng.gen_bool(0.3) {
+                    call.function.as_mut().unwrap().name = None;
+                }
+                if rng.gen_bool(0.2) {
+                    choice.delta.content = Some(arguments.clone());
+                }
+                choice.finish_reason = match rng.gen_range(0..6) {
+                    0 => Some("tool_calls".to_string()),
+                    1 => Some("content_filter".to_string()),
+                    2 => Some(String::new()),
+                    _ => None,
+                };
+
+                // Either outcome is fine; only a panic would fail the test
+                let _ = aggregator.process_chunk(chunk);
+            }
+        }
+    }
 }
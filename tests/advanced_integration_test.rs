@@ -162,7 +162,7 @@ async fn test_auto_execution_with_context_management() {
 /// Test: Multiple hooks with auto-execution
 ///
 /// Verifies that multiple hooks of the same type work correctly together
-/// during auto-execution, with proper first-match-wins semantics.
+/// during auto-execution: the first block wins and modifications compose.
 #[tokio::test]
 async fn test_multiple_hooks_with_auto_execution() {
     let log = Arc::new(Mutex::new(Vec::new()));
@@ -223,7 +223,7 @@ async fn test_multiple_hooks_with_auto_execution() {
     let _client = Client::new(options).expect("Failed to create client");
 
     // Multiple hooks should execute in sequence
-    // First hook that returns a decision stops the chain
+    // A block from the first hook stops the chain
 }
 
 /// Test: Auto-execution preserves manual mode compatibility